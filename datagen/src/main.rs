@@ -2,14 +2,16 @@ use std::{
     env,
     fs::{File, OpenOptions},
     io::Write,
+    path::Path,
     sync::atomic::AtomicBool,
     time::Instant,
 };
 
 use crossbeam_queue::SegQueue;
 use display::Printer;
+use jackal::Bitbases;
 use policy_gen::PolicyGen;
-use spear::{ChessBoardPacked, PolicyPacked};
+use spear::PolicyPacked;
 use value_gen::ValueGen;
 
 mod display;
@@ -23,48 +25,73 @@ pub enum DataGenMode {
     Value,
 }
 
+#[derive(PartialEq, Clone, Copy)]
+pub enum StartPosition {
+    Standard,
+    Chess960,
+    DoubleChess960,
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
 
     let mut mode = DataGenMode::Value;
+    let mut start_position = StartPosition::Standard;
     let mut threads = 1;
     let mut iter_count = 1000;
     let mut target = 1000;
     let mut path = "./value_data.bin";
+    let mut bitbases_path = "./bitbases";
 
     let mut cmd = String::new();
     for arg in &args {
         match arg.as_str() {
             "policy" => mode = DataGenMode::Policy,
             "value" => mode = DataGenMode::Value,
-            "threads" | "nodes" | "path" | "target" => cmd = arg.clone(),
+            "frc" => start_position = StartPosition::Chess960,
+            "dfrc" => start_position = StartPosition::DoubleChess960,
+            "threads" | "nodes" | "path" | "target" | "bitbases" => cmd = arg.clone(),
             _ => {
                 match cmd.as_str() {
                     "threads" => threads = arg.parse::<u8>().unwrap_or(1),
                     "nodes" => iter_count = arg.parse::<u32>().unwrap_or(1000),
                     "path" => path = arg.as_str(),
                     "target" => target = arg.parse::<u64>().unwrap_or(1000),
+                    "bitbases" => bitbases_path = arg.as_str(),
                     _ => continue,
                 };
             }
         }
     }
 
+    //Games are adjudicated with bitbases, when they are available
+    let bitbases = Bitbases::load(Path::new(bitbases_path));
+    if bitbases > 0 {
+        println!("Loaded {} bitbases", bitbases);
+    }
+
     let mut file = OpenOptions::new()
+        .read(true)
         .append(true)
         .create(true)
         .open(path)
         .expect("Cannot open file");
 
-    let position_size = if mode == DataGenMode::Value {
-        std::mem::size_of::<ChessBoardPacked>() as u64
+    let (header_size, position_size) = if mode == DataGenMode::Value {
+        if let Err(error) = ValueGen::prepare_file(&mut file) {
+            println!("Cannot append value data to {}: {}", path, error);
+            return;
+        }
+
+        (ValueGen::FILE_HEADER.len() as u64, ValueGen::RECORD_SIZE as u64)
     } else {
-        std::mem::size_of::<PolicyPacked>() as u64
+        (0, std::mem::size_of::<PolicyPacked>() as u64)
     };
 
-    let saved_positions = std::fs::metadata(&path)
+    let saved_positions = (std::fs::metadata(&path)
         .expect("Cannot get file metadata")
         .len()
+        - header_size)
         / position_size;
     let target = target * 1_000_000;
     let printer = Printer::new(saved_positions, target, threads, iter_count, mode);
@@ -79,6 +106,7 @@ fn main() {
                     ValueGen::start_game_loop(
                         &save_queue,
                         iter_count,
+                        start_position,
                         &printer,
                         &interruption_token,
                     )
@@ -86,6 +114,7 @@ fn main() {
                     PolicyGen::start_game_loop(
                         &save_queue,
                         iter_count,
+                        start_position,
                         &printer,
                         &interruption_token,
                     )
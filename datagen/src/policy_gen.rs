@@ -1,6 +1,6 @@
-use super::{display::Printer, utils::DataGenUtils};
+use super::{display::Printer, utils::DataGenUtils, StartPosition};
 use crossbeam_queue::SegQueue;
-use jackal::{EngineOptions, GameState, Mcts, NoPrint, SearchLimits, SearchStats, Tree};
+use jackal::{Bitbases, EngineOptions, GameState, Mcts, NoPrint, SearchLimits, SearchStats, Tree};
 use spear::{ChessPosition, Move, PolicyPacked, Side};
 use std::sync::atomic::AtomicBool;
 
@@ -9,6 +9,7 @@ impl PolicyGen {
     pub fn start_game_loop(
         save_queue: &SegQueue<Vec<u8>>,
         iter_count: u32,
+        start_position: StartPosition,
         printer: &Printer,
         interruption_token: &AtomicBool,
     ) {
@@ -16,10 +17,10 @@ impl PolicyGen {
         options.set("DrawContempt", "15");
         let mut tree = Tree::new(options.hash(), options.hash_percentage() / 10.0);
         let mut limits = SearchLimits::new(0);
-        limits.add_iters(iter_count);
+        limits.add_iters(u64::from(iter_count));
 
         while !interruption_token.load(std::sync::atomic::Ordering::Relaxed) {
-            let mut position = DataGenUtils::get_random_position();
+            let mut position = DataGenUtils::get_random_position(start_position);
             tree.clear();
 
             let mut packed_positions: Vec<PolicyPacked> = Vec::new();
@@ -100,6 +101,12 @@ impl PolicyGen {
             return true;
         }
 
+        //Games reaching positions covered by bitbases end with their exact result
+        if let Some(bitbase_state) = Bitbases::probe(position.board()) {
+            *state = bitbase_state;
+            return true;
+        }
+
         return false;
     }
 }
@@ -1,12 +1,27 @@
+use jackal::Chess960;
 use rand::Rng;
 use spear::{ChessPosition, Move, FEN};
 
+use crate::StartPosition;
+
 pub struct DataGenUtils;
 impl DataGenUtils {
-    pub fn get_random_position() -> ChessPosition {
-        let mut result = ChessPosition::from_fen(&FEN::start_position());
+    pub fn get_random_position(start_position: StartPosition) -> ChessPosition {
+        let fen = match start_position {
+            StartPosition::Standard => FEN::start_position(),
+            StartPosition::Chess960 => {
+                FEN::from_string(Chess960::start_position(rand::thread_rng().gen_range(0..960)))
+            }
+            StartPosition::DoubleChess960 => FEN::from_string(Chess960::double_start_position(
+                rand::thread_rng().gen_range(0..960),
+                rand::thread_rng().gen_range(0..960),
+            )),
+        };
+
+        let mut result = ChessPosition::from_fen(&fen);
         Self::get_random_position_internal::<true, false>(
             &mut result,
+            start_position,
             rand::thread_rng().gen_range(7..=8),
         );
         result
@@ -14,6 +29,7 @@ impl DataGenUtils {
 
     fn get_random_position_internal<const STM_WHITE: bool, const NSTM_WHITE: bool>(
         position: &mut ChessPosition,
+        start_position: StartPosition,
         depth: u8,
     ) {
         let mut move_list: Vec<Move> = Vec::new();
@@ -22,7 +38,7 @@ impl DataGenUtils {
             .map_moves::<_, STM_WHITE, NSTM_WHITE>(|mv| move_list.push(mv));
 
         if move_list.len() == 0 {
-            *position = Self::get_random_position();
+            *position = Self::get_random_position(start_position);
             return;
         }
 
@@ -31,7 +47,11 @@ impl DataGenUtils {
         );
 
         if depth > 0 {
-            Self::get_random_position_internal::<NSTM_WHITE, STM_WHITE>(position, depth - 1);
+            Self::get_random_position_internal::<NSTM_WHITE, STM_WHITE>(
+                position,
+                start_position,
+                depth - 1,
+            );
         } else {
             let mut has_moves = false;
             position
@@ -39,7 +59,7 @@ impl DataGenUtils {
                 .map_moves::<_, NSTM_WHITE, STM_WHITE>(|_| has_moves = true);
 
             if !has_moves {
-                *position = Self::get_random_position()
+                *position = Self::get_random_position(start_position)
             }
         }
     }
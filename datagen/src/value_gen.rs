@@ -1,14 +1,42 @@
-use super::{display::Printer, utils::DataGenUtils};
+use super::{display::Printer, utils::DataGenUtils, StartPosition};
 use crossbeam_queue::SegQueue;
-use jackal::{EngineOptions, GameState, Mcts, NoPrint, SearchLimits, SearchStats, Tree};
+use jackal::{Bitbases, EngineOptions, GameState, Mcts, NoPrint, SearchLimits, SearchStats, Tree};
 use spear::{ChessBoardPacked, ChessPosition, Move, Side};
-use std::sync::atomic::AtomicBool;
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom, Write},
+    sync::atomic::AtomicBool,
+};
 
 pub struct ValueGen;
 impl ValueGen {
+    //Every saved position is followed by number of plies left until the end of the game
+    pub const RECORD_SIZE: usize = std::mem::size_of::<ChessBoardPacked>() + std::mem::size_of::<u16>();
+    //Version of the record format at the start of the file, older files without plies left have no header
+    pub const FILE_HEADER: &'static [u8; 8] = b"JVALUE02";
+
+    //Writes the header to the new file, or checks that the existing file has the same record format
+    pub fn prepare_file(file: &mut File) -> io::Result<()> {
+        if file.metadata()?.len() == 0 {
+            return file.write_all(Self::FILE_HEADER);
+        }
+
+        let mut header = *Self::FILE_HEADER;
+        file.seek(SeekFrom::Start(0))?;
+        if file.read_exact(&mut header).is_err() || header != *Self::FILE_HEADER {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "file was written with a different record format",
+            ));
+        }
+
+        Ok(())
+    }
+
     pub fn start_game_loop(
         save_queue: &SegQueue<Vec<u8>>,
         iter_count: u32,
+        start_position: StartPosition,
         printer: &Printer,
         interruption_token: &AtomicBool,
     ) {
@@ -16,10 +44,10 @@ impl ValueGen {
         options.set("DrawContempt", "15");
         let mut tree = Tree::new(options.hash(), options.hash_percentage() / 10.0);
         let mut limits = SearchLimits::new(0);
-        limits.add_iters(iter_count);
+        limits.add_iters(u64::from(iter_count));
 
         while !interruption_token.load(std::sync::atomic::Ordering::Relaxed) {
-            let mut position = DataGenUtils::get_random_position();
+            let mut position = DataGenUtils::get_random_position(start_position);
             tree.clear();
 
             let mut packed_positions: Vec<ChessBoardPacked> = Vec::new();
@@ -59,16 +87,32 @@ impl ValueGen {
                 packed_positions.push(packed_position);
             }
 
-            if state != GameState::Drawn {
+            //State is seen from the perspective of the side to move in the final position
+            let side_to_move = position.board().side_to_move();
+            let (winner, plies_after_end) = match state {
+                GameState::Won(plies) => (Some(side_to_move), usize::from(plies)),
+                GameState::Lost(plies) => (Some(side_to_move.flipped()), usize::from(plies)),
+                _ => (None, 0),
+            };
+
+            if let Some(winner) = winner {
                 for pos in &mut packed_positions {
-                    pos.apply_result(position.board().side_to_move().flipped())
+                    pos.apply_result(winner)
                 }
             }
 
             printer.add_position(packed_positions.len() as u64);
 
-            let bytes = bytemuck::cast_slice(&packed_positions);
-            save_queue.push(bytes.to_vec());
+            //Games adjudicated by bitbases continue until the mate
+            let game_length = packed_positions.len() + plies_after_end;
+            let mut bytes = Vec::with_capacity(packed_positions.len() * Self::RECORD_SIZE);
+            for (ply, pos) in packed_positions.iter().enumerate() {
+                let moves_left = (game_length - ply).min(u16::MAX as usize) as u16;
+                bytes.extend_from_slice(bytemuck::bytes_of(pos));
+                bytes.extend_from_slice(&moves_left.to_le_bytes());
+            }
+
+            save_queue.push(bytes);
         }
     }
 
@@ -98,6 +142,12 @@ impl ValueGen {
             return true;
         }
 
+        //Games reaching positions covered by bitbases end with their exact result
+        if let Some(bitbase_state) = Bitbases::probe(position.board()) {
+            *state = bitbase_state;
+            return true;
+        }
+
         return false;
     }
 }
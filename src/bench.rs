@@ -1,11 +1,19 @@
 use std::sync::atomic::AtomicBool;
 
-use spear::{ChessPosition, FEN};
+use spear::{ChessPosition, Move, FEN};
 
-use crate::{Mcts, NoPrint, SearchEngine, SearchLimits, SearchStats, Tree};
+use crate::{Engine, EngineOptions, Mcts, NoPrint, SearchLimits, SearchStats, Tree};
 
 pub struct Bench;
 impl Bench {
+    //Thread scaling bench searches the first few positions with fixed amount of iterations
+    const THREADS_BENCH_POSITIONS: usize = 16;
+    const THREADS_BENCH_ITERS: u64 = 50_000;
+
+    //Deterministic bench searches every position twice and expects identical results
+    const DETERMINISTIC_BENCH_POSITIONS: usize = 8;
+    const DETERMINISTIC_BENCH_ITERS: u64 = 20_000;
+
     const FENS: [&'static str; 50] = [
         "r3k2r/2pb1ppp/2pp1q2/p7/1nP1B3/1P2P3/P2N1PPP/R2QK2R w KQkq a6 0 14",
         "4rrk1/2p1b1p1/p1p3q1/4p3/2P2n1p/1P1NR2P/PB3PP1/3R1QK1 b - - 2 24",
@@ -59,12 +67,15 @@ impl Bench {
         "2r2b2/5p2/5k2/p1r1pP2/P2pB3/1P3P2/K1P3R1/7R w - - 23 93",
     ];
 
-    pub fn run(depth: u32, search_engine: &SearchEngine) {
+    pub fn run(depth: u32, search_engine: &Engine) {
         let mut limits = SearchLimits::new(0);
         limits.add_depth(depth);
 
-        let mut total_nodes = 0u32;
+        let mut total_nodes = 0u64;
         let mut total_time = 1;
+        let mut hash_hits = 0u64;
+        let mut hash_misses = 0u64;
+        let mut hash_collisions = 0u64;
 
         let options = search_engine.engine_options();
         let mut tree = Tree::new(options.hash(), options.hash_percentage() / 10.0);
@@ -78,9 +89,162 @@ impl Bench {
             mcts.search::<NoPrint>();
             total_nodes += stats.iters();
             total_time += stats.time_passed();
+            hash_hits += stats.hash_hits();
+            hash_misses += stats.hash_misses();
+            hash_collisions += stats.hash_collisions();
         }
-        let total_nps = (total_nodes * 1000) as u64 / total_time;
+        let total_nps = total_nodes * 1000 / total_time;
 
+        //Bench line is printed last, so tools reading the last line still find the nps
+        println!(
+            "Hash: {hash_hits} hits {hash_misses} misses {hash_collisions} collisions, hit rate {:.2}%",
+            hash_hits as f64 * 100.0 / (hash_hits + hash_misses).max(1) as f64
+        );
         println!("Bench: {total_nodes} nodes {total_nps} nps");
     }
+
+    //Searches the positions with 1..max_threads threads and given batch size, reporting nps and share
+    //of iterations, that collided with another thread on the same leaf
+    pub fn run_threads(max_threads: u32, batch_size: u32, search_engine: &Engine) {
+        let mut limits = SearchLimits::new(0);
+        limits.add_iters(Self::THREADS_BENCH_ITERS);
+
+        let mut options = search_engine.engine_options().clone();
+        options.set("BatchSize", &batch_size.to_string());
+        let mut tree = Tree::new(options.hash(), options.hash_percentage() / 10.0);
+        let mut single_thread_nps = 0;
+
+        for threads in 1..=max_threads.max(1) {
+            options.set("Threads", &threads.to_string());
+
+            let mut total_nodes = 0u64;
+            let mut total_collisions = 0u64;
+            let mut total_time = 1;
+
+            for fen in Bench::FENS.iter().take(Self::THREADS_BENCH_POSITIONS) {
+                let position = ChessPosition::from_fen(&FEN::from_str(fen));
+                let stats = SearchStats::new();
+                let token = AtomicBool::new(false);
+                tree.clear();
+                let mcts = Mcts::new(position, &tree, &token, &options, &stats, &limits);
+                mcts.search::<NoPrint>();
+                total_nodes += stats.iters();
+                total_collisions += stats.collisions();
+                total_time += stats.time_passed();
+            }
+
+            let total_nps = total_nodes * 1000 / total_time;
+            if threads == 1 {
+                single_thread_nps = total_nps.max(1);
+            }
+
+            println!(
+                "Threads {threads:>3} batch {batch_size:>3}: {total_nodes} nodes {total_nps} nps, speedup {:.2}x, collisions {:.2}%",
+                total_nps as f64 / single_thread_nps as f64,
+                total_collisions as f64 * 100.0 / total_nodes.max(1) as f64
+            );
+        }
+    }
+
+    //Regression check of the Deterministic option, returns false if any of the runs differed
+    pub fn run_deterministic(search_engine: &Engine) -> bool {
+        let mut limits = SearchLimits::new(0);
+        limits.add_iters(Self::DETERMINISTIC_BENCH_ITERS);
+
+        let mut options = search_engine.engine_options().clone();
+        options.set("Deterministic", "true");
+        let mut tree = Tree::new(options.hash(), options.hash_percentage() / 10.0);
+        let mut passed = true;
+
+        for fen in Bench::FENS.iter().take(Self::DETERMINISTIC_BENCH_POSITIONS) {
+            let position = ChessPosition::from_fen(&FEN::from_str(fen));
+            let first = Self::deterministic_search(position, &options, &limits, &mut tree);
+            let second = Self::deterministic_search(position, &options, &limits, &mut tree);
+
+            let (best_move, _, iters, nodes) = &first;
+            if first == second {
+                println!("{fen}: bestmove {best_move} iterations {iters} nodes {nodes}");
+            } else {
+                let (other_move, _, other_iters, other_nodes) = &second;
+                let pv_text = if first.1 == second.1 { "same pv" } else { "different pv" };
+                println!(
+                    "{fen}: mismatch, bestmove {best_move}/{other_move} iterations {iters}/{other_iters} nodes {nodes}/{other_nodes}, {pv_text}"
+                );
+                passed = false;
+            }
+        }
+
+        println!("Deterministic bench: {}", if passed { "passed" } else { "failed" });
+        passed
+    }
+
+    //Searches the position from an empty tree and returns best move, principal variation,
+    //number of iterations and number of created nodes
+    fn deterministic_search(
+        position: ChessPosition,
+        options: &EngineOptions,
+        limits: &SearchLimits,
+        tree: &mut Tree,
+    ) -> (Move, Vec<Move>, u64, u64) {
+        let stats = SearchStats::new();
+        let token = AtomicBool::new(false);
+        tree.clear();
+
+        let mcts = Mcts::new(position, tree, &token, options, &stats, limits);
+        let (best_move, _) = mcts.search::<NoPrint>();
+        let (_, _, pv) = tree.get_pv_by_index::<true, false>(0, options.draw_contempt());
+
+        (best_move, pv, stats.iters(), stats.nodes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_reproducible(options: &EngineOptions) {
+        let mut limits = SearchLimits::new(0);
+        limits.add_iters(2_000);
+
+        let mut tree = Tree::new(options.hash(), options.hash_percentage() / 10.0);
+        for fen in Bench::FENS.iter().take(4) {
+            let position = ChessPosition::from_fen(&FEN::from_str(fen));
+            let (best_move, pv, iters, nodes) = Bench::deterministic_search(position, options, &limits, &mut tree);
+            let (other_move, other_pv, other_iters, other_nodes) =
+                Bench::deterministic_search(position, options, &limits, &mut tree);
+
+            assert_ne!(best_move, Move::NULL, "{fen}");
+            assert_eq!(best_move, other_move, "{fen}");
+            assert_eq!(pv, other_pv, "{fen}");
+            assert_eq!(iters, other_iters, "{fen}");
+            assert_eq!(nodes, other_nodes, "{fen}");
+        }
+    }
+
+    fn deterministic_options(threads: &str) -> EngineOptions {
+        let mut options = EngineOptions::new();
+        options.set("Hash", "16");
+        options.set("Deterministic", "true");
+        options.set("Threads", threads);
+        options
+    }
+
+    #[test]
+    fn single_thread_search_is_reproducible() {
+        assert_reproducible(&deterministic_options("1"));
+    }
+
+    #[test]
+    fn multi_thread_search_is_reproducible() {
+        assert_reproducible(&deterministic_options("4"));
+    }
+
+    //Strength limit samples the move with the seed taken from the position
+    #[test]
+    fn strength_limited_search_is_reproducible() {
+        let mut options = deterministic_options("1");
+        options.set("LimitStrength", "true");
+        options.set("StrengthLevel", "6");
+        assert_reproducible(&options);
+    }
 }
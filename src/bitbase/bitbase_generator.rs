@@ -0,0 +1,237 @@
+use super::{
+    bitbase_position::BitbasePosition,
+    bitbase_table::{Bitbase, DRAW, LOST, MAX_PLIES, UNKNOWN},
+    Bitbases,
+};
+
+//Position has a move into another table that doesn't lose, so it can't be lost
+const BLOCKED: u8 = 1;
+//Predecessors of the position were already updated with its result
+const PROCESSED: u8 = 2;
+
+//Retrograde analysis of single material. Results are found in order of their distance to mate,
+//so every position gets the shortest win and the longest loss. Positions left unresolved are draws
+pub struct BitbaseGenerator {
+    material: BitbasePosition,
+    values: Vec<u8>,
+    //Amount of moves staying in this table that are not yet known to lose
+    remaining: Vec<u8>,
+    //Longest loss reachable with a capture or a promotion
+    exit_loss: Vec<u8>,
+    flags: Vec<u8>,
+    layers: Vec<Vec<u32>>,
+}
+
+impl BitbaseGenerator {
+    //Tables of all materials reachable with captures and promotions have to be provided
+    pub fn generate(material: BitbasePosition, tables: &[Bitbase]) -> Bitbase {
+        let size = material.table_size();
+        let mut generator = Self {
+            material,
+            values: vec![UNKNOWN; size],
+            remaining: vec![0; size],
+            exit_loss: vec![0; size],
+            flags: vec![0; size],
+            layers: vec![Vec::new(); usize::from(MAX_PLIES) + 2],
+        };
+
+        generator.initialize(tables);
+        for plies in 0..=MAX_PLIES {
+            generator.process_layer(plies);
+        }
+
+        for value in &mut generator.values {
+            if *value == UNKNOWN {
+                *value = DRAW;
+            }
+        }
+
+        Bitbase::new(material, generator.values)
+    }
+
+    //Finds mates, stalemates and results of moves leaving the table
+    fn initialize(&mut self, tables: &[Bitbase]) {
+        let material_key = self.material.material_key();
+        for index in 0..self.values.len() {
+            let Some(position) = self.material.with_index(index).filter(|position| position.is_legal()) else {
+                self.values[index] = DRAW;
+                continue;
+            };
+
+            let mut moves = 0;
+            let mut in_table = 0;
+            let mut exit_win = None;
+            position.map_moves(|next| {
+                moves += 1;
+                if next.material_key() == material_key {
+                    in_table += 1;
+                    return;
+                }
+
+                let value = Bitbases::probe_position(tables, &next).expect("Missing bitbase of reachable material");
+                if value == DRAW {
+                    self.flags[index] |= BLOCKED;
+                } else if value & LOST != 0 {
+                    let plies = (value & !LOST) + 1;
+                    exit_win = Some(exit_win.map_or(plies, |win: u8| win.min(plies)));
+                } else {
+                    self.exit_loss[index] = self.exit_loss[index].max(value + 1);
+                }
+            });
+
+            if moves == 0 {
+                if position.is_in_check(position.white_to_move()) {
+                    self.values[index] = LOST;
+                    self.layers[0].push(index as u32);
+                } else {
+                    self.values[index] = DRAW;
+                }
+
+                continue;
+            }
+
+            self.remaining[index] = in_table;
+
+            //Value is set once the layer is reached, unless a faster win is found before
+            if let Some(plies) = exit_win {
+                self.flags[index] |= BLOCKED;
+                self.layers[usize::from(plies)].push(index as u32);
+            } else if in_table == 0 && self.flags[index] & BLOCKED == 0 {
+                self.schedule_loss(index, 0);
+            }
+        }
+    }
+
+    fn process_layer(&mut self, plies: u8) {
+        let layer = std::mem::take(&mut self.layers[usize::from(plies)]);
+        for index in layer {
+            let index = index as usize;
+            if self.flags[index] & PROCESSED != 0 {
+                continue;
+            }
+
+            if self.values[index] == UNKNOWN {
+                self.values[index] = plies;
+            }
+
+            self.flags[index] |= PROCESSED;
+
+            let position = self.material.with_index(index).expect("Scheduled position is valid");
+            if self.values[index] & LOST != 0 {
+                //Every position that can move here is won
+                position.map_unmoves(|previous| {
+                    let previous_index = previous.index();
+                    if self.values[previous_index] == UNKNOWN {
+                        assert!(plies < MAX_PLIES, "Distance to mate exceeds bitbase range");
+                        self.values[previous_index] = plies + 1;
+                        self.layers[usize::from(plies) + 1].push(previous_index as u32);
+                    }
+                });
+            } else {
+                //Position that can move only to won positions is lost
+                position.map_unmoves(|previous| {
+                    let previous_index = previous.index();
+                    if self.values[previous_index] != UNKNOWN {
+                        return;
+                    }
+
+                    self.remaining[previous_index] -= 1;
+                    if self.remaining[previous_index] == 0 && self.flags[previous_index] & BLOCKED == 0 {
+                        self.schedule_loss(previous_index, plies + 1);
+                    }
+                });
+            }
+        }
+    }
+
+    fn schedule_loss(&mut self, index: usize, plies: u8) {
+        let plies = plies.max(self.exit_loss[index]);
+        assert!(plies <= MAX_PLIES, "Distance to mate exceeds bitbase range");
+        self.values[index] = LOST | plies;
+        self.layers[usize::from(plies)].push(index as u32);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Results {
+        wins: usize,
+        draws: usize,
+        losses: usize,
+        longest_win: u8,
+        longest_loss: u8,
+    }
+
+    //Results of legal positions with the given side to move
+    fn results(bitbase: &Bitbase, material: &BitbasePosition, white_to_move: bool) -> Results {
+        let mut results = Results { wins: 0, draws: 0, losses: 0, longest_win: 0, longest_loss: 0 };
+        for index in 0..material.table_size() {
+            let Some(position) = material.with_index(index).filter(|position| position.is_legal()) else {
+                continue;
+            };
+
+            if position.white_to_move() != white_to_move {
+                continue;
+            }
+
+            match bitbase.value(&position) {
+                DRAW => results.draws += 1,
+                value if value & LOST != 0 => {
+                    results.losses += 1;
+                    results.longest_loss = results.longest_loss.max(value & !LOST);
+                }
+                value => {
+                    results.wins += 1;
+                    results.longest_win = results.longest_win.max(value);
+                }
+            }
+        }
+
+        results
+    }
+
+    fn generate(signatures: &[&str]) -> Vec<Bitbase> {
+        let mut tables = Vec::new();
+        for signature in signatures {
+            let material = BitbasePosition::from_signature(signature).unwrap();
+            let bitbase = BitbaseGenerator::generate(material, &tables);
+            tables.push(bitbase);
+        }
+
+        tables
+    }
+
+    #[test]
+    fn solves_heavy_piece_endings() {
+        //Longest mates are 10 moves with the queen and 16 moves with the rook
+        let tables = generate(&["KQK", "KRK"]);
+        for (bitbase, wins, draws, losses, longest_win, longest_loss) in [
+            (&tables[0], 72254, 11524, 100448, 19, 20),
+            (&tables[1], 87584, 11122, 100850, 31, 32),
+        ] {
+            let material = BitbasePosition::from_signature(&bitbase.signature()).unwrap();
+
+            let white = results(bitbase, &material, true);
+            assert_eq!((white.wins, white.draws, white.losses), (wins, 0, 0));
+            assert_eq!(white.longest_win, longest_win);
+
+            let black = results(bitbase, &material, false);
+            assert_eq!((black.wins, black.draws, black.losses), (0, draws, losses));
+            assert_eq!(black.longest_loss, longest_loss);
+        }
+    }
+
+    #[test]
+    fn solves_pawn_ending() {
+        let tables = generate(&["KQK", "KRK", "KPK"]);
+        let material = BitbasePosition::from_signature("KPK").unwrap();
+
+        let white = results(&tables[2], &material, true);
+        assert_eq!((white.wins, white.draws, white.losses), (62480, 19184, 0));
+
+        let black = results(&tables[2], &material, false);
+        assert_eq!((black.wins, black.draws, black.losses), (0, 35210, 48802));
+    }
+}
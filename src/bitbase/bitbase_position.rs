@@ -0,0 +1,506 @@
+use std::cmp::Reverse;
+
+use spear::{ChessBoard, Piece, Side};
+
+pub const MAX_PIECES: usize = 4;
+
+pub const PAWN: u8 = 0;
+pub const KNIGHT: u8 = 1;
+pub const BISHOP: u8 = 2;
+pub const ROOK: u8 = 3;
+pub const QUEEN: u8 = 4;
+pub const KING: u8 = 5;
+
+const PIECE_CHARS: [char; 6] = ['P', 'N', 'B', 'R', 'Q', 'K'];
+const PIECE_VALUES: [u32; 6] = [1, 3, 3, 5, 9, 0];
+const PROMOTIONS: [u8; 4] = [QUEEN, ROOK, BISHOP, KNIGHT];
+
+const KNIGHT_DELTAS: [(i8, i8); 8] = [(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)];
+const KING_DELTAS: [(i8, i8); 8] = [(1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1), (1, -1)];
+const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+const ROOK_DIRECTIONS: [(i8, i8); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct BitbasePiece {
+    pub kind: u8,
+    pub white: bool,
+    pub square: u8,
+}
+
+//Small position with its own move generation, so the generator can also walk the moves backwards.
+//Pieces are kept in the signature order, white king first, then white pieces from the most valuable
+//and the same for black. Castling and en passant never happen in positions covered by bitbases
+#[derive(Clone, Copy)]
+pub struct BitbasePosition {
+    pieces: [BitbasePiece; MAX_PIECES],
+    count: usize,
+    white_to_move: bool,
+}
+
+impl BitbasePosition {
+    fn empty(white_to_move: bool) -> Self {
+        Self {
+            pieces: [BitbasePiece { kind: KING, white: true, square: 0 }; MAX_PIECES],
+            count: 0,
+            white_to_move,
+        }
+    }
+
+    //Parses material like "KRKP", white pieces are listed first and each side starts with its king
+    pub fn from_signature(signature: &str) -> Option<Self> {
+        let mut position = Self::empty(true);
+        let mut kings = 0;
+        for char in signature.chars() {
+            let kind = PIECE_CHARS.iter().position(|&piece| piece == char.to_ascii_uppercase())? as u8;
+            if kind == KING {
+                kings += 1;
+            }
+
+            if kings == 0 || position.count == MAX_PIECES {
+                return None;
+            }
+
+            position.add(kind, kings == 1, 0);
+        }
+
+        if kings != 2 {
+            return None;
+        }
+
+        position.sort();
+        Some(position)
+    }
+
+    //Returns None, when the board has more pieces than bitbases cover
+    pub fn from_board(board: &ChessBoard) -> Option<Self> {
+        let mut position = Self::empty(board.side_to_move() == Side::WHITE);
+        let mut piece_count = 0;
+        for (kind, piece) in [
+            (PAWN, Piece::PAWN),
+            (KNIGHT, Piece::KNIGHT),
+            (BISHOP, Piece::BISHOP),
+            (ROOK, Piece::ROOK),
+            (QUEEN, Piece::QUEEN),
+            (KING, Piece::KING),
+        ] {
+            board.get_piece_mask_for_side::<true>(piece).map(|square| {
+                piece_count += 1;
+                position.add(kind, true, square.get_raw())
+            });
+            board.get_piece_mask_for_side::<false>(piece).map(|square| {
+                piece_count += 1;
+                position.add(kind, false, square.get_raw())
+            });
+        }
+
+        if piece_count > MAX_PIECES {
+            return None;
+        }
+
+        position.sort();
+        Some(position)
+    }
+
+    pub fn pieces(&self) -> &[BitbasePiece] {
+        &self.pieces[..self.count]
+    }
+
+    pub fn white_to_move(&self) -> bool {
+        self.white_to_move
+    }
+
+    pub fn signature(&self) -> String {
+        self.pieces().iter().map(|piece| PIECE_CHARS[usize::from(piece.kind)]).collect()
+    }
+
+    //Unique number of the material, pieces are already sorted so it doesn't depend on their order
+    pub fn material_key(&self) -> u64 {
+        self.pieces()
+            .iter()
+            .fold(0, |key, piece| key * 16 + u64::from(piece.kind) + 1 + if piece.white { 0 } else { 8 })
+    }
+
+    pub fn pawn_count(&self) -> usize {
+        self.pieces().iter().filter(|piece| piece.kind == PAWN).count()
+    }
+
+    pub fn is_insufficient_material(&self) -> bool {
+        self.count == 2
+            || (self.count == 3 && self.pieces().iter().all(|piece| matches!(piece.kind, KING | KNIGHT | BISHOP)))
+    }
+
+    //Same position with colors swapped and board flipped vertically
+    pub fn flipped(&self) -> Self {
+        let mut result = *self;
+        result.white_to_move = !self.white_to_move;
+        for piece in &mut result.pieces[..self.count] {
+            piece.white = !piece.white;
+            piece.square ^= 56;
+        }
+
+        result.sort();
+        result
+    }
+
+    //Material is stored with the stronger side as white
+    pub fn canonical(&self) -> Self {
+        let value = |white: bool| -> u32 {
+            self.pieces()
+                .iter()
+                .filter(|piece| piece.white == white)
+                .map(|piece| PIECE_VALUES[usize::from(piece.kind)])
+                .sum()
+        };
+
+        let flipped = self.flipped();
+        if value(false) > value(true) || (value(false) == value(true) && flipped.signature() < self.signature()) {
+            flipped
+        } else {
+            *self
+        }
+    }
+
+    //Materials that can be reached with a capture or a promotion
+    pub fn successor_materials(&self) -> Vec<Self> {
+        let mut result = Vec::new();
+        for index in 0..self.count {
+            let piece = self.pieces[index];
+            if piece.kind == KING {
+                continue;
+            }
+
+            result.push(self.without(index));
+            if piece.kind == PAWN {
+                for kind in PROMOTIONS {
+                    let mut promoted = *self;
+                    promoted.pieces[index].kind = kind;
+                    promoted.sort();
+                    result.push(promoted);
+
+                    //Promotion with a capture
+                    for captured in 0..self.count {
+                        if self.pieces[captured].white != piece.white && self.pieces[captured].kind != KING {
+                            let mut promoted = *self;
+                            promoted.pieces[index].kind = kind;
+                            result.push(promoted.without(captured));
+                        }
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
+    //Amount of positions in the table, white king is mirrored to files a-d
+    pub fn table_size(&self) -> usize {
+        2 * 32 * 64usize.pow(self.count as u32 - 1)
+    }
+
+    pub fn index(&self) -> usize {
+        let mirror = if self.pieces[0].square % 8 >= 4 { 7 } else { 0 };
+        let king = self.pieces[0].square ^ mirror;
+        let mut index = usize::from(!self.white_to_move) * 32 + usize::from(king / 8) * 4 + usize::from(king % 8);
+        for piece in &self.pieces[1..self.count] {
+            index = index * 64 + usize::from(piece.square ^ mirror);
+        }
+
+        index
+    }
+
+    //Position of this material with the given index. Returns None, when pieces share a square
+    //or a pawn stands on the first or last rank
+    pub fn with_index(&self, mut index: usize) -> Option<Self> {
+        let mut position = *self;
+        for piece in position.pieces[1..self.count].iter_mut().rev() {
+            piece.square = (index % 64) as u8;
+            index /= 64;
+        }
+
+        let king = index % 32;
+        position.pieces[0].square = ((king / 4) * 8 + king % 4) as u8;
+        position.white_to_move = index / 32 == 0;
+
+        for (piece_index, piece) in position.pieces().iter().enumerate() {
+            if piece.kind == PAWN && !(8..56).contains(&piece.square) {
+                return None;
+            }
+
+            if position.pieces[..piece_index].iter().any(|other| other.square == piece.square) {
+                return None;
+            }
+        }
+
+        Some(position)
+    }
+
+    //Side that is not to move can't be in check
+    pub fn is_legal(&self) -> bool {
+        !self.is_in_check(!self.white_to_move)
+    }
+
+    pub fn is_in_check(&self, white: bool) -> bool {
+        let occupancy = self.occupancy();
+        let Some(king) = self.pieces().iter().find(|piece| piece.kind == KING && piece.white == white) else {
+            return false;
+        };
+
+        self.pieces()
+            .iter()
+            .any(|piece| piece.white != white && Self::attacks(piece, king.square, occupancy))
+    }
+
+    //Calls the method with every position reachable by a legal move
+    pub fn map_moves<F: FnMut(Self)>(&self, mut method: F) {
+        let occupancy = self.occupancy();
+        for (index, piece) in self.pieces().iter().enumerate() {
+            if piece.white != self.white_to_move {
+                continue;
+            }
+
+            self.map_targets(piece, occupancy, |target| {
+                let captured = self.pieces().iter().position(|other| other.square == target);
+                if let Some(captured) = captured {
+                    if self.pieces[captured].white == piece.white || self.pieces[captured].kind == KING {
+                        return;
+                    }
+                }
+
+                let mut moved = *self;
+                moved.pieces[index].square = target;
+                moved.white_to_move = !self.white_to_move;
+
+                let promotion = piece.kind == PAWN && !(8..56).contains(&target);
+                let (kinds, kind_count) = if promotion { (PROMOTIONS, PROMOTIONS.len()) } else { ([piece.kind; 4], 1) };
+                for &kind in &kinds[..kind_count] {
+                    let mut next = moved;
+                    next.pieces[index].kind = kind;
+                    if let Some(captured) = captured {
+                        next = next.without(captured);
+                    } else if promotion {
+                        next.sort();
+                    }
+
+                    if !next.is_in_check(self.white_to_move) {
+                        method(next)
+                    }
+                }
+            });
+        }
+    }
+
+    //Calls the method with every position, from which this one can be reached by a legal move
+    //of the same material, so captures and promotions are never taken back
+    pub fn map_unmoves<F: FnMut(Self)>(&self, mut method: F) {
+        let occupancy = self.occupancy();
+        for (index, piece) in self.pieces().iter().enumerate() {
+            if piece.white == self.white_to_move {
+                continue;
+            }
+
+            self.map_origins(piece, occupancy, |origin| {
+                let mut previous = *self;
+                previous.pieces[index].square = origin;
+                previous.white_to_move = !self.white_to_move;
+
+                if !previous.is_in_check(self.white_to_move) {
+                    method(previous)
+                }
+            });
+        }
+    }
+
+    fn add(&mut self, kind: u8, white: bool, square: u8) {
+        if self.count < MAX_PIECES {
+            self.pieces[self.count] = BitbasePiece { kind, white, square };
+            self.count += 1;
+        }
+    }
+
+    fn without(&self, index: usize) -> Self {
+        let mut result = *self;
+        result.pieces.copy_within(index + 1..self.count, index);
+        result.count -= 1;
+        result.sort();
+        result
+    }
+
+    fn sort(&mut self) {
+        self.pieces[..self.count].sort_by_key(|piece| (!piece.white, Reverse(piece.kind)));
+    }
+
+    fn occupancy(&self) -> u64 {
+        self.pieces().iter().fold(0, |occupancy, piece| occupancy | (1 << piece.square))
+    }
+
+    //Squares the piece moves to, including the ones occupied by other pieces
+    fn map_targets<F: FnMut(u8)>(&self, piece: &BitbasePiece, occupancy: u64, mut method: F) {
+        match piece.kind {
+            PAWN => {
+                let (direction, start_rank) = if piece.white { (1, 1) } else { (-1, 6) };
+                if let Some(forward) = offset(piece.square, (0, direction)).filter(|&square| !is_occupied(occupancy, square)) {
+                    method(forward);
+                    if piece.square / 8 == start_rank {
+                        if let Some(double) = offset(forward, (0, direction)).filter(|&square| !is_occupied(occupancy, square)) {
+                            method(double);
+                        }
+                    }
+                }
+
+                for file_delta in [-1, 1] {
+                    if let Some(capture) = offset(piece.square, (file_delta, direction)).filter(|&square| is_occupied(occupancy, square)) {
+                        method(capture);
+                    }
+                }
+            }
+            KNIGHT => KNIGHT_DELTAS.iter().filter_map(|&delta| offset(piece.square, delta)).for_each(method),
+            KING => KING_DELTAS.iter().filter_map(|&delta| offset(piece.square, delta)).for_each(method),
+            _ => {
+                for &direction in slider_directions(piece.kind) {
+                    let mut square = piece.square;
+                    while let Some(next) = offset(square, direction) {
+                        method(next);
+                        if is_occupied(occupancy, next) {
+                            break;
+                        }
+
+                        square = next;
+                    }
+                }
+            }
+        }
+    }
+
+    //Empty squares the piece could have come from with a non-capturing move
+    fn map_origins<F: FnMut(u8)>(&self, piece: &BitbasePiece, occupancy: u64, mut method: F) {
+        match piece.kind {
+            PAWN => {
+                let (direction, double_rank) = if piece.white { (-1, 3) } else { (1, 4) };
+                let Some(back) = offset(piece.square, (0, direction)).filter(|&square| !is_occupied(occupancy, square)) else {
+                    return;
+                };
+
+                if (8..56).contains(&back) {
+                    method(back);
+                }
+
+                if piece.square / 8 == double_rank {
+                    if let Some(double) = offset(back, (0, direction)).filter(|&square| !is_occupied(occupancy, square)) {
+                        method(double);
+                    }
+                }
+            }
+            KNIGHT | KING => {
+                let deltas = if piece.kind == KNIGHT { &KNIGHT_DELTAS } else { &KING_DELTAS };
+                deltas
+                    .iter()
+                    .filter_map(|&delta| offset(piece.square, delta))
+                    .filter(|&square| !is_occupied(occupancy, square))
+                    .for_each(method)
+            }
+            _ => {
+                for &direction in slider_directions(piece.kind) {
+                    let mut square = piece.square;
+                    while let Some(next) = offset(square, direction).filter(|&square| !is_occupied(occupancy, square)) {
+                        method(next);
+                        square = next;
+                    }
+                }
+            }
+        }
+    }
+
+    fn attacks(piece: &BitbasePiece, target: u8, occupancy: u64) -> bool {
+        match piece.kind {
+            PAWN => {
+                let direction = if piece.white { 1 } else { -1 };
+                [-1, 1].iter().any(|&file_delta| offset(piece.square, (file_delta, direction)) == Some(target))
+            }
+            KNIGHT => KNIGHT_DELTAS.iter().any(|&delta| offset(piece.square, delta) == Some(target)),
+            KING => KING_DELTAS.iter().any(|&delta| offset(piece.square, delta) == Some(target)),
+            _ => slider_directions(piece.kind).iter().any(|&direction| {
+                let mut square = piece.square;
+                while let Some(next) = offset(square, direction) {
+                    if next == target {
+                        return true;
+                    }
+
+                    if is_occupied(occupancy, next) {
+                        return false;
+                    }
+
+                    square = next;
+                }
+
+                false
+            }),
+        }
+    }
+}
+
+fn slider_directions(kind: u8) -> &'static [(i8, i8)] {
+    match kind {
+        BISHOP => &BISHOP_DIRECTIONS,
+        ROOK => &ROOK_DIRECTIONS,
+        _ => &KING_DELTAS,
+    }
+}
+
+fn offset(square: u8, (file_delta, rank_delta): (i8, i8)) -> Option<u8> {
+    let file = (square % 8) as i8 + file_delta;
+    let rank = (square / 8) as i8 + rank_delta;
+    ((0..8).contains(&file) && (0..8).contains(&rank)).then_some((rank * 8 + file) as u8)
+}
+
+fn is_occupied(occupancy: u64, square: u8) -> bool {
+    occupancy & (1 << square) != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use spear::{ChessPosition, FEN};
+
+    use super::*;
+
+    #[test]
+    fn parses_signatures() {
+        let signature = |signature: &str| BitbasePosition::from_signature(signature).map(|material| material.signature());
+        assert_eq!(signature("KRKP").as_deref(), Some("KRKP"));
+        assert_eq!(signature("KRQK").as_deref(), Some("KQRK"));
+        assert_eq!(BitbasePosition::from_signature("KRK").unwrap().table_size(), 2 * 32 * 64 * 64);
+
+        for signature in ["", "QKK", "KQ", "KKK", "KQRBK", "KXK"] {
+            assert!(BitbasePosition::from_signature(signature).is_none(), "{signature}");
+        }
+    }
+
+    #[test]
+    fn stronger_side_is_white() {
+        let canonical = |signature: &str| BitbasePosition::from_signature(signature).unwrap().canonical().signature();
+        assert_eq!(canonical("KKQ"), "KQK");
+        assert_eq!(canonical("KPKR"), "KRKP");
+        assert_eq!(canonical("KRKP"), "KRKP");
+    }
+
+    #[test]
+    fn index_survives_conversion() {
+        let material = BitbasePosition::from_signature("KRKP").unwrap();
+        for index in (0..material.table_size()).step_by(7919) {
+            if let Some(position) = material.with_index(index) {
+                assert_eq!(position.index(), index);
+                assert_eq!(position.flipped().flipped().index(), index);
+            }
+        }
+    }
+
+    #[test]
+    fn mirrored_positions_share_index() {
+        let index = |fen: &str| {
+            let position = ChessPosition::from_fen(&FEN::from_str(fen));
+            BitbasePosition::from_board(position.board()).unwrap().index()
+        };
+
+        assert_eq!(index("8/8/8/8/4P3/8/2K5/7k b - - 0 1"), index("8/8/8/8/3P4/8/5K2/k7 b - - 0 1"));
+        assert_ne!(index("8/8/8/8/4P3/8/2K5/7k b - - 0 1"), index("8/8/8/8/4P3/8/2K5/7k w - - 0 1"));
+    }
+}
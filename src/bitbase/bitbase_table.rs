@@ -0,0 +1,167 @@
+use std::{fs, io, path::Path};
+
+use crate::GameState;
+
+use super::bitbase_position::BitbasePosition;
+
+//Every position takes one byte with the distance to mate in plies from the perspective of the side
+//to move. Draws and illegal positions are 0, lost positions have the highest bit set
+pub const DRAW: u8 = 0;
+pub const LOST: u8 = 0x80;
+pub const UNKNOWN: u8 = 0xFF;
+pub const MAX_PLIES: u8 = 126;
+
+const MAGIC: &[u8; 4] = b"JBB1";
+
+//Runs of the same value are stored as the marker, value and u16 length. Marker never appears
+//as a value of the finished table
+const RUN_MARKER: u8 = UNKNOWN;
+const MIN_RUN: usize = 4;
+
+pub struct Bitbase {
+    material: BitbasePosition,
+    values: Vec<u8>,
+}
+
+impl Bitbase {
+    pub fn new(material: BitbasePosition, values: Vec<u8>) -> Self {
+        Self { material, values }
+    }
+
+    pub fn signature(&self) -> String {
+        self.material.signature()
+    }
+
+    pub fn material_key(&self) -> u64 {
+        self.material.material_key()
+    }
+
+    pub fn value(&self, position: &BitbasePosition) -> u8 {
+        self.values[position.index()]
+    }
+
+    pub fn to_game_state(value: u8) -> GameState {
+        match value {
+            DRAW => GameState::Drawn,
+            value if value & LOST != 0 => GameState::Lost(value & !LOST),
+            value => GameState::Won(value),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let signature = self.signature();
+        let mut bytes = Vec::with_capacity(self.values.len() / 2);
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(signature.len() as u8);
+        bytes.extend_from_slice(signature.as_bytes());
+
+        let mut index = 0;
+        while index < self.values.len() {
+            let value = self.values[index];
+            let mut run = 1;
+            while index + run < self.values.len() && self.values[index + run] == value && run < usize::from(u16::MAX) {
+                run += 1;
+            }
+
+            if run >= MIN_RUN {
+                bytes.extend_from_slice(&[RUN_MARKER, value]);
+                bytes.extend_from_slice(&(run as u16).to_le_bytes());
+            } else {
+                bytes.resize(bytes.len() + run, value);
+            }
+
+            index += run;
+        }
+
+        fs::write(path, bytes)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+
+        let bytes = fs::read(path)?;
+        if bytes.len() < 5 || !bytes.starts_with(MAGIC) {
+            return Err(invalid("not a bitbase file"));
+        }
+
+        let signature_end = 5 + usize::from(bytes[4]);
+        let material = bytes
+            .get(5..signature_end)
+            .and_then(|signature| std::str::from_utf8(signature).ok())
+            .and_then(BitbasePosition::from_signature)
+            .ok_or_else(|| invalid("invalid material"))?;
+
+        let size = material.table_size();
+        let mut values = Vec::with_capacity(size);
+        let mut index = signature_end;
+        while index < bytes.len() && values.len() <= size {
+            if bytes[index] != RUN_MARKER {
+                values.push(bytes[index]);
+                index += 1;
+                continue;
+            }
+
+            let run = bytes.get(index + 1..index + 4).ok_or_else(|| invalid("truncated file"))?;
+            values.resize(values.len() + usize::from(u16::from_le_bytes([run[1], run[2]])), run[0]);
+            index += 4;
+        }
+
+        if values.len() != size {
+            return Err(invalid("wrong table size"));
+        }
+
+        Ok(Self { material, values })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temporary_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("jackal-{}-{}.bitbase", name, std::process::id()))
+    }
+
+    #[test]
+    fn survives_save_and_load() {
+        let material = BitbasePosition::from_signature("KQK").unwrap();
+
+        //Long run of draws has to be split, because runs are stored with u16 length
+        let values: Vec<u8> = (0..material.table_size())
+            .map(|index| match index {
+                0..=69999 => DRAW,
+                index if index % 5 == 0 => LOST | (index % 7) as u8,
+                index => (index / 3 % 11) as u8,
+            })
+            .collect();
+
+        let path = temporary_path("round-trip");
+        Bitbase::new(material, values.clone()).save(&path).unwrap();
+        let loaded = Bitbase::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.signature(), "KQK");
+        assert!(loaded.values == values);
+    }
+
+    #[test]
+    fn rejects_invalid_files() {
+        let material = BitbasePosition::from_signature("KRK").unwrap();
+        let path = temporary_path("invalid");
+        Bitbase::new(material, vec![DRAW; material.table_size()]).save(&path).unwrap();
+
+        let mut bytes = fs::read(&path).unwrap();
+        bytes.truncate(bytes.len() - 2);
+        fs::write(&path, &bytes).unwrap();
+        let truncated = Bitbase::load(&path);
+
+        bytes[0] = b'X';
+        fs::write(&path, &bytes).unwrap();
+        let wrong_magic = Bitbase::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert!(truncated.is_err_and(|error| error.kind() == io::ErrorKind::InvalidData));
+        assert!(wrong_magic.is_err_and(|error| error.kind() == io::ErrorKind::InvalidData));
+    }
+}
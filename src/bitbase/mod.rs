@@ -0,0 +1,228 @@
+mod bitbase_generator;
+mod bitbase_position;
+mod bitbase_table;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::RwLock,
+    time::Instant,
+};
+
+use spear::ChessBoard;
+
+use crate::GameState;
+
+use bitbase_generator::BitbaseGenerator;
+use bitbase_position::BitbasePosition;
+use bitbase_table::{Bitbase, DRAW};
+
+//Tables loaded by the engine, shared by all searches
+static BITBASES: RwLock<Vec<Bitbase>> = RwLock::new(Vec::new());
+
+//Endgame tables generated by the engine itself with retrograde analysis
+pub struct Bitbases;
+impl Bitbases {
+    pub const DEFAULT_DIRECTORY: &'static str = "bitbases";
+    pub const DEFAULT_MATERIALS: [&'static str; 5] = ["KPK", "KRK", "KQK", "KBNK", "KRKP"];
+    const FILE_EXTENSION: &'static str = "bitbase";
+
+    //Default directory is placed next to the binary
+    pub fn default_path() -> PathBuf {
+        std::env::current_exe()
+            .ok()
+            .and_then(|path| path.parent().map(|parent| parent.join(Self::DEFAULT_DIRECTORY)))
+            .unwrap_or_else(|| PathBuf::from(Self::DEFAULT_DIRECTORY))
+    }
+
+    //Loads all bitbases from the directory and returns their amount
+    pub fn load(directory: &Path) -> usize {
+        let Ok(entries) = fs::read_dir(directory) else {
+            return 0;
+        };
+
+        let mut loaded = 0;
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.extension().and_then(|extension| extension.to_str()) != Some(Self::FILE_EXTENSION) {
+                continue;
+            }
+
+            match Bitbase::load(&path) {
+                Ok(bitbase) => {
+                    Self::insert(bitbase);
+                    loaded += 1;
+                }
+                Err(error) => println!("Cannot load bitbase {}: {}", path.display(), error),
+            }
+        }
+
+        loaded
+    }
+
+    //Generates missing bitbases of the materials together with all materials reachable from them,
+    //saves them in the directory and loads them
+    pub fn generate(directory: &Path, signatures: &[String]) {
+        let mut materials: Vec<BitbasePosition> = Vec::new();
+        let mut pending = Vec::new();
+        for signature in signatures {
+            match BitbasePosition::from_signature(signature) {
+                Some(material) => pending.push(material),
+                None => println!("Invalid bitbase material {}", signature),
+            }
+        }
+
+        while let Some(material) = pending.pop() {
+            let material = material.canonical();
+            if material.is_insufficient_material()
+                || materials.iter().any(|known| known.material_key() == material.material_key())
+            {
+                continue;
+            }
+
+            pending.extend(material.successor_materials());
+            materials.push(material);
+        }
+
+        //Tables are generated from the smallest ones, because bigger ones depend on them
+        materials.sort_by_key(|material| (material.pieces().len(), material.pawn_count()));
+
+        if let Err(error) = fs::create_dir_all(directory) {
+            println!("Cannot create directory {}: {}", directory.display(), error);
+            return;
+        }
+
+        let mut tables = std::mem::take(&mut *BITBASES.write().unwrap());
+        for material in materials {
+            if tables.iter().any(|table| table.material_key() == material.material_key()) {
+                continue;
+            }
+
+            let timer = Instant::now();
+            let bitbase = BitbaseGenerator::generate(material, &tables);
+
+            let path = directory.join(format!("{}.{}", bitbase.signature(), Self::FILE_EXTENSION));
+            match bitbase.save(&path) {
+                Ok(()) => println!("Generated {} in {:.1}s", bitbase.signature(), timer.elapsed().as_secs_f32()),
+                Err(error) => println!("Cannot save bitbase {}: {}", path.display(), error),
+            }
+
+            tables.push(bitbase);
+        }
+
+        *BITBASES.write().unwrap() = tables;
+    }
+
+    //Exact result of the position from the perspective of the side to move
+    pub fn probe(board: &ChessBoard) -> Option<GameState> {
+        let tables = BITBASES.read().ok()?;
+        if tables.is_empty() {
+            return None;
+        }
+
+        let position = BitbasePosition::from_board(board)?;
+        Self::probe_position(&tables, &position).map(Bitbase::to_game_state)
+    }
+
+    fn probe_position(tables: &[Bitbase], position: &BitbasePosition) -> Option<u8> {
+        if position.is_insufficient_material() {
+            return Some(DRAW);
+        }
+
+        let material_key = position.material_key();
+        if let Some(table) = tables.iter().find(|table| table.material_key() == material_key) {
+            return Some(table.value(position));
+        }
+
+        //Tables are stored only with the stronger side as white
+        let flipped = position.flipped();
+        let material_key = flipped.material_key();
+        tables
+            .iter()
+            .find(|table| table.material_key() == material_key)
+            .map(|table| table.value(&flipped))
+    }
+
+    fn insert(bitbase: Bitbase) {
+        let mut tables = BITBASES.write().unwrap();
+        tables.retain(|table| table.material_key() != bitbase.material_key());
+        tables.push(bitbase);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::OnceLock;
+
+    use spear::{ChessPosition, FEN};
+
+    use super::*;
+
+    //Promotions of KPK lead to KQK and KRK, minor pieces are drawn without tables
+    fn tables() -> &'static [Bitbase] {
+        static TABLES: OnceLock<Vec<Bitbase>> = OnceLock::new();
+        TABLES.get_or_init(|| {
+            let mut tables = Vec::new();
+            for signature in ["KQK", "KRK", "KPK"] {
+                let material = BitbasePosition::from_signature(signature).unwrap();
+                let bitbase = BitbaseGenerator::generate(material, &tables);
+                tables.push(bitbase);
+            }
+
+            tables
+        })
+    }
+
+    fn probe(fen: &str) -> Option<GameState> {
+        let position = ChessPosition::from_fen(&FEN::from_str(fen));
+        let position = BitbasePosition::from_board(position.board())?;
+        Bitbases::probe_position(tables(), &position).map(Bitbase::to_game_state)
+    }
+
+    #[test]
+    fn probes_mates() {
+        assert!(probe("k7/3Q4/1K6/8/8/8/8/8 w - - 0 1") == Some(GameState::Won(1)));
+        assert!(probe("k7/8/1K6/8/8/8/8/7R w - - 0 1") == Some(GameState::Won(1)));
+        assert!(probe("4k3/8/8/8/8/8/8/3QK3 w - - 0 1") == Some(GameState::Won(15)));
+        assert!(probe("4k3/8/8/8/8/8/8/3QK3 b - - 0 1") == Some(GameState::Lost(16)));
+    }
+
+    #[test]
+    fn probes_draws() {
+        //Black captures the hanging rook
+        assert!(probe("8/8/8/8/8/8/k7/R3K3 b - - 0 1") == Some(GameState::Drawn));
+
+        //Stalemate
+        assert!(probe("4k3/4P3/4K3/8/8/8/8/8 b - - 0 1") == Some(GameState::Drawn));
+
+        //Rook pawn can't win, when the defending king reaches the corner
+        assert!(probe("k7/8/K7/P7/8/8/8/8 w - - 0 1") == Some(GameState::Drawn));
+
+        //Knight alone can't mate, so it doesn't need a table
+        assert!(probe("4k3/8/8/8/8/8/8/4KN2 w - - 0 1") == Some(GameState::Drawn));
+    }
+
+    #[test]
+    fn probes_pawn_endings() {
+        assert!(matches!(probe("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1"), Some(GameState::Won(_))));
+        assert!(matches!(probe("4k3/8/4K3/4P3/8/8/8/8 b - - 0 1"), Some(GameState::Lost(_))));
+
+        //Rook pawn is faster than the king
+        assert!(matches!(probe("8/8/8/8/8/8/P7/K6k w - - 0 1"), Some(GameState::Won(_))));
+    }
+
+    #[test]
+    fn probes_flipped_colors() {
+        assert!(probe("8/8/8/8/8/1k6/3q4/K7 b - - 0 1") == Some(GameState::Won(1)));
+        assert!(probe("3qk3/8/8/8/8/8/8/4K3 w - - 0 1") == Some(GameState::Lost(16)));
+        assert!(matches!(probe("8/8/8/8/4p3/4k3/8/4K3 b - - 0 1"), Some(GameState::Won(_))));
+        assert!(matches!(probe("8/8/8/8/4p3/4k3/8/4K3 w - - 0 1"), Some(GameState::Lost(_))));
+    }
+
+    #[test]
+    fn positions_without_tables_are_not_probed() {
+        assert!(probe("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").is_none());
+
+        //KBNK table is not generated in the tests
+        assert!(probe("4k3/8/8/8/8/8/8/2B1K1N1 w - - 0 1").is_none());
+    }
+}
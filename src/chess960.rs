@@ -0,0 +1,250 @@
+use std::sync::RwLock;
+
+use spear::{ChessBoard, ChessPosition, Move, MoveFlag, Piece, Side, Square};
+
+use crate::utils::square_to_string;
+
+//Files of the rooks holding castling rights, indexed by side and then by castling side
+//(queen side first). Board keeps only KQkq rights, so the rook can't be found from it
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct CastlingFiles(pub [[Option<u8>; 2]; 2]);
+
+static CASTLING_FILES: RwLock<CastlingFiles> = RwLock::new(CastlingFiles([[None; 2]; 2]));
+
+pub struct Chess960;
+impl Chess960 {
+    //Knight placements on the five squares left after placing bishops and queen (Scharnagl numbering)
+    const KNIGHT_TABLE: [(usize, usize); 10] = [
+        (0, 1), (0, 2), (0, 3), (0, 4), (1, 2),
+        (1, 3), (1, 4), (2, 3), (2, 4), (3, 4),
+    ];
+
+    //Returns fen of Chess960 start position with given Scharnagl index (518 is standard chess)
+    pub fn start_position(index: usize) -> String {
+        Self::double_start_position(index, index)
+    }
+
+    //Returns fen of Double Fischer Random start position, where both sides have independent setups
+    pub fn double_start_position(white_index: usize, black_index: usize) -> String {
+        let white = Self::back_rank(white_index).to_uppercase();
+        let black = Self::back_rank(black_index);
+        format!("{black}/pppppppp/8/8/8/8/PPPPPPPP/{white} w KQkq - 0 1")
+    }
+
+    fn back_rank(index: usize) -> String {
+        let mut rank = [' '; 8];
+        let index = index % 960;
+
+        rank[(index % 4) * 2 + 1] = 'b';
+        let index = index / 4;
+        rank[(index % 4) * 2] = 'b';
+        let index = index / 4;
+
+        let queen_square = Self::empty_squares(&rank)[index % 6];
+        rank[queen_square] = 'q';
+        let index = index / 6;
+
+        let empty = Self::empty_squares(&rank);
+        let (first_knight, second_knight) = Self::KNIGHT_TABLE[index];
+        rank[empty[first_knight]] = 'n';
+        rank[empty[second_knight]] = 'n';
+
+        //Remaining three squares are always filled in order rook, king, rook
+        for (square, piece) in Self::empty_squares(&rank).into_iter().zip(['r', 'k', 'r']) {
+            rank[square] = piece;
+        }
+
+        rank.iter().collect()
+    }
+
+    fn empty_squares(rank: &[char; 8]) -> Vec<usize> {
+        (0..8).filter(|&idx| rank[idx] == ' ').collect()
+    }
+
+    //Converts Shredder-FEN and X-FEN castling rights into KQkq form expected by the fen parser.
+    //Files of the rooks holding the rights are returned separately, KQkq rights belong to the
+    //outermost rook and file letters name the rook directly, so inner rooks keep their rights
+    pub fn normalize_castling_rights(fen: &str) -> (String, CastlingFiles) {
+        let mut files = CastlingFiles::default();
+        let parts: Vec<&str> = fen.split_whitespace().collect();
+        if parts.len() < 3 || parts[2] == "-" {
+            return (fen.trim().to_string(), files);
+        }
+
+        let ranks: Vec<&str> = parts[0].split('/').collect();
+        if ranks.len() != 8 {
+            return (fen.trim().to_string(), files);
+        }
+
+        let mut rights = String::new();
+        for right in parts[2].chars() {
+            let (side, back_rank, king, rook) = if right.is_ascii_uppercase() {
+                (0, ranks[7], 'K', 'R')
+            } else {
+                (1, ranks[0], 'k', 'r')
+            };
+
+            let Some(king_file) = Self::piece_files(back_rank, king).first().copied() else {
+                continue;
+            };
+
+            let rook_files = Self::piece_files(back_rank, rook);
+            let (king_side, rook_file) = match right.to_ascii_uppercase() {
+                'K' => (true, rook_files.iter().copied().filter(|&file| file > king_file).max()),
+                'Q' => (false, rook_files.iter().copied().filter(|&file| file < king_file).min()),
+                'A'..='H' => {
+                    let file = right.to_ascii_uppercase() as u8 - b'A';
+                    (file > king_file, Some(file))
+                }
+                _ => continue,
+            };
+
+            let normalized = match (side, king_side) {
+                (0, true) => 'K',
+                (0, false) => 'Q',
+                (_, true) => 'k',
+                (_, false) => 'q',
+            };
+
+            if !rights.contains(normalized) {
+                rights.push(normalized);
+                files.0[side][usize::from(king_side)] = rook_file;
+            }
+        }
+
+        //Fen parser expects rights in KQkq order
+        let rights: String = "KQkq".chars().filter(|right| rights.contains(*right)).collect();
+        let rights = if rights.is_empty() { "-".to_string() } else { rights };
+
+        let mut result = parts.clone();
+        result[2] = rights.as_str();
+        (result.join(" "), files)
+    }
+
+    //Castling rooks of the current game, used when castling moves are printed and parsed
+    pub fn set_castling_files(files: CastlingFiles) {
+        *CASTLING_FILES.write().unwrap() = files;
+    }
+
+    pub fn castling_files() -> CastlingFiles {
+        *CASTLING_FILES.read().unwrap()
+    }
+
+    fn piece_files(rank: &str, piece: char) -> Vec<u8> {
+        let mut files = Vec::new();
+        let mut file = 0;
+        for symbol in rank.chars() {
+            if symbol == piece {
+                files.push(file);
+            }
+
+            file += symbol.to_digit(10).unwrap_or(1) as u8;
+        }
+
+        files
+    }
+
+    //Prints move in UCI notation, in Chess960 mode castling is printed as king takes own rook
+    pub fn move_to_string(mv: Move, board: &ChessBoard, chess960: bool) -> String {
+        if !chess960
+            || (mv.get_flag() != MoveFlag::KING_SIDE_CASTLE
+                && mv.get_flag() != MoveFlag::QUEEN_SIDE_CASTLE)
+        {
+            return mv.to_string();
+        }
+
+        let king_square = mv.get_from_square();
+        match Self::castle_rook_square(board, king_square, mv.get_flag() == MoveFlag::KING_SIDE_CASTLE) {
+            Some(rook_square) => format!("{}{}", square_to_string(king_square), square_to_string(rook_square)),
+            None => mv.to_string(),
+        }
+    }
+
+    //Converts the line of moves into string, tracking the board to print castling moves correctly
+    pub fn pv_to_string(position: &ChessPosition, pv: &[Move], chess960: bool) -> String {
+        let mut board = *position.board();
+        let mut result = String::new();
+
+        for &mv in pv {
+            result.push_str(&format!("{} ", Self::move_to_string(mv, &board, chess960)));
+
+            if board.side_to_move() == Side::WHITE {
+                board.make_move::<true, false>(mv)
+            } else {
+                board.make_move::<false, true>(mv)
+            }
+        }
+
+        result
+    }
+
+    //Castling rook is the rook holding the castling right. Without known file, e.g. in the start
+    //position, it's the outermost rook on the king's rank on the castling side
+    fn castle_rook_square(board: &ChessBoard, king_square: Square, king_side: bool) -> Option<Square> {
+        let (rooks, side) = if board.side_to_move() == Side::WHITE {
+            (board.get_piece_mask(Piece::ROOK) & board.get_occupancy_for_side::<true>(), 0)
+        } else {
+            (board.get_piece_mask(Piece::ROOK) & board.get_occupancy_for_side::<false>(), 1)
+        };
+
+        let king_rank = king_square.get_raw() / 8;
+        let king_file = king_square.get_raw() % 8;
+
+        if let Some(file) = Self::castling_files().0[side][usize::from(king_side)] {
+            let square = Square::from_raw(king_rank * 8 + file);
+            if rooks.get_bit(square) {
+                return Some(square);
+            }
+        }
+
+        let mut result: Option<Square> = None;
+        rooks.map(|square| {
+            let file = square.get_raw() % 8;
+            if square.get_raw() / 8 != king_rank || (file > king_file) != king_side {
+                return;
+            }
+
+            let is_outer = match result {
+                None => true,
+                Some(current) if king_side => file > current.get_raw() % 8,
+                Some(current) => file < current.get_raw() % 8,
+            };
+
+            if is_outer {
+                result = Some(square);
+            }
+        });
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shredder_rights_keep_inner_rook_file() {
+        //Rooks on b1 and c1 with the king on d1, right belongs to the inner rook on c1
+        let (fen, files) = Chess960::normalize_castling_rights("1rrk4/8/8/8/8/8/8/1RRK4 w Cc - 0 1");
+        assert_eq!(fen, "1rrk4/8/8/8/8/8/8/1RRK4 w Qq - 0 1");
+        assert_eq!(files.0[0][0], Some(2));
+        assert_eq!(files.0[1][0], Some(2));
+    }
+
+    #[test]
+    fn xfen_rights_use_outermost_rook() {
+        let (fen, files) = Chess960::normalize_castling_rights("rk4rr/8/8/8/8/8/8/RK4RR w KQk - 0 1");
+        assert_eq!(fen, "rk4rr/8/8/8/8/8/8/RK4RR w KQk - 0 1");
+        assert_eq!(files.0[0], [Some(0), Some(7)]);
+        assert_eq!(files.0[1], [None, Some(7)]);
+    }
+
+    #[test]
+    fn standard_fen_is_unchanged() {
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let (normalized, files) = Chess960::normalize_castling_rights(fen);
+        assert_eq!(normalized, fen);
+        assert_eq!(files.0, [[Some(0), Some(7)], [Some(0), Some(7)]]);
+    }
+}
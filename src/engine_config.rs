@@ -0,0 +1,242 @@
+use std::{fs, path::PathBuf};
+
+use crate::Engine;
+
+//Engine setup read from a small subset of TOML:
+//
+//  [options]
+//  Hash = 256
+//  "Move Overhead" = 20
+//
+//  [startup]
+//  commands = ["position startpos", "go nodes 1000"]
+//
+//Keys placed before any section are treated as options
+pub struct EngineConfig {
+    options: Vec<(usize, String, String)>,
+    commands: Vec<String>,
+    errors: Vec<(usize, String)>,
+}
+
+#[derive(PartialEq)]
+enum Section {
+    Options,
+    Startup,
+    Unknown,
+}
+
+impl EngineConfig {
+    pub const DEFAULT_FILE_NAME: &'static str = "jackal.toml";
+
+    //Default config is placed next to the binary
+    pub fn default_path() -> Option<PathBuf> {
+        let path = std::env::current_exe().ok()?.parent()?.join(Self::DEFAULT_FILE_NAME);
+        path.is_file().then_some(path)
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        fs::read_to_string(path)
+            .map(|content| Self::parse(&content))
+            .map_err(|error| format!("Cannot read config file {}: {}", path, error))
+    }
+
+    pub fn parse(content: &str) -> Self {
+        let mut result = Self {
+            options: Vec::new(),
+            commands: Vec::new(),
+            errors: Vec::new(),
+        };
+
+        let mut section = Section::Options;
+        let lines: Vec<&str> = content.lines().collect();
+        let mut index = 0;
+        while index < lines.len() {
+            let line_number = index + 1;
+            let mut line = strip_comment(lines[index]).trim().to_string();
+            index += 1;
+
+            if line.is_empty() {
+                continue;
+            }
+
+            if line.starts_with('[') && line.ends_with(']') {
+                section = match line[1..line.len() - 1].trim() {
+                    "options" => Section::Options,
+                    "startup" => Section::Startup,
+                    name => {
+                        result.errors.push((line_number, format!("Unknown section [{}]", name)));
+                        Section::Unknown
+                    }
+                };
+                continue;
+            }
+
+            let Some((key, _)) = line.split_once('=') else {
+                result.errors.push((line_number, format!("Expected 'key = value', found '{}'", line)));
+                continue;
+            };
+            let key = unquote(key.trim()).to_string();
+
+            //Arrays can span multiple lines, so we keep reading until the closing bracket
+            if line.split_once('=').unwrap().1.trim().starts_with('[') {
+                while !line.ends_with(']') && index < lines.len() {
+                    line.push(' ');
+                    line.push_str(strip_comment(lines[index]).trim());
+                    index += 1;
+                }
+            }
+
+            let value = line.split_once('=').unwrap().1.trim();
+            match section {
+                Section::Options => result.options.push((line_number, key, unquote(value).to_string())),
+                Section::Startup if key == "commands" => match parse_array(value) {
+                    Some(commands) => result.commands.extend(commands),
+                    None => result.errors.push((line_number, "Invalid list of commands".to_string())),
+                },
+                Section::Startup => result.errors.push((line_number, format!("Unknown key '{}'", key))),
+                Section::Unknown => (),
+            }
+        }
+
+        result
+    }
+
+    //Sets the options on the engine and returns all problems found in the config, with their line numbers
+    pub fn apply(&self, engine: &mut Engine) -> Vec<(usize, String)> {
+        let mut errors = self.errors.clone();
+        for (line_number, name, value) in &self.options {
+            if let Err(error) = engine.try_set_option(name, value) {
+                errors.push((*line_number, error.to_string()));
+            }
+        }
+
+        errors.sort_by_key(|(line_number, _)| *line_number);
+        errors
+    }
+
+    pub fn commands(&self) -> &[String] {
+        &self.commands
+    }
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut in_quotes = false;
+    for (index, char) in line.char_indices() {
+        match char {
+            '"' => in_quotes = !in_quotes,
+            '#' if !in_quotes => return &line[..index],
+            _ => (),
+        }
+    }
+
+    line
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+//Parses array of quoted strings, like ["uci", "isready"]
+fn parse_array(value: &str) -> Option<Vec<String>> {
+    let inner = value.strip_prefix('[')?.strip_suffix(']')?;
+
+    let mut result = Vec::new();
+    let mut current: Option<String> = None;
+    for char in inner.chars() {
+        match (char, current.as_mut()) {
+            ('"', None) => current = Some(String::new()),
+            ('"', Some(_)) => result.push(current.take()?),
+            (_, Some(string)) => string.push(char),
+            (',', None) => (),
+            (char, None) if char.is_whitespace() => (),
+            _ => return None,
+        }
+    }
+
+    current.is_none().then_some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(config: &EngineConfig) -> Vec<(usize, &str, &str)> {
+        config
+            .options
+            .iter()
+            .map(|(line_number, name, value)| (*line_number, name.as_str(), value.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_options_section() {
+        let config = EngineConfig::parse("[options]\nHash = 256\n\"Move Overhead\" = 20\nSyzygyPath = \"/tb/wdl\"\n");
+
+        assert_eq!(
+            options(&config),
+            vec![(2, "Hash", "256"), (3, "Move Overhead", "20"), (4, "SyzygyPath", "/tb/wdl")]
+        );
+        assert!(config.commands().is_empty());
+        assert!(config.errors.is_empty());
+    }
+
+    #[test]
+    fn keys_before_sections_are_options() {
+        let config = EngineConfig::parse("Threads = 4\n\n[startup]\ncommands = [\"uci\"]\n");
+
+        assert_eq!(options(&config), vec![(1, "Threads", "4")]);
+        assert_eq!(config.commands(), ["uci"]);
+    }
+
+    #[test]
+    fn strips_comments_outside_quotes() {
+        let config = EngineConfig::parse("# engine setup\nHash = 64 # in MB\nSyzygyPath = \"/tb/#1\" # path\n");
+
+        assert_eq!(options(&config), vec![(2, "Hash", "64"), (3, "SyzygyPath", "/tb/#1")]);
+        assert!(config.errors.is_empty());
+    }
+
+    #[test]
+    fn parses_multiline_commands() {
+        let content = "[startup]\ncommands = [\n  \"position startpos\", # comment\n  \"go nodes 1000\",\n]\n";
+        let config = EngineConfig::parse(content);
+
+        assert_eq!(config.commands(), ["position startpos", "go nodes 1000"]);
+        assert!(config.errors.is_empty());
+    }
+
+    #[test]
+    fn reports_errors_with_line_numbers() {
+        let content = "Hash 64\n[engine]\nThreads = 2\n[startup]\nscript = []\ncommands = [uci]\n";
+        let config = EngineConfig::parse(content);
+
+        assert_eq!(
+            config.errors,
+            vec![
+                (1, "Expected 'key = value', found 'Hash 64'".to_string()),
+                (2, "Unknown section [engine]".to_string()),
+                (5, "Unknown key 'script'".to_string()),
+                (6, "Invalid list of commands".to_string()),
+            ]
+        );
+
+        //Keys of unknown sections are ignored
+        assert!(config.options.is_empty());
+        assert!(config.commands().is_empty());
+    }
+
+    #[test]
+    fn parses_arrays() {
+        assert_eq!(parse_array("[]"), Some(Vec::new()));
+        assert_eq!(
+            parse_array("[\"uci\", \"isready\"]"),
+            Some(vec!["uci".to_string(), "isready".to_string()])
+        );
+        assert_eq!(parse_array("[\"uci\""), None);
+        assert_eq!(parse_array("[\"uci\", isready]"), None);
+        assert_eq!(parse_array("[\"uci]"), None);
+    }
+}
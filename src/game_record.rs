@@ -0,0 +1,343 @@
+use spear::{ChessBoard, ChessPosition, Move, MoveFlag, Piece, Side, Square, FEN};
+
+use crate::{search::SearchResult, utils::square_to_string};
+
+#[derive(Clone, Copy)]
+pub struct RecordedMove {
+    pub mv: Move,
+    pub search_result: Option<SearchResult>,
+}
+
+//Full game played from the start position, together with results of the searches that
+//chose the moves. Game ply is derived from fen fullmove counter and the number of moves played
+#[derive(Clone)]
+pub struct GameRecord {
+    start_fen: String,
+    start_position: ChessPosition,
+    start_ply: u32,
+    position: ChessPosition,
+    moves: Vec<RecordedMove>,
+    pending_result: Option<(usize, SearchResult)>,
+}
+
+impl Default for GameRecord {
+    fn default() -> Self {
+        Self::new(&FEN::start_position().to_string())
+    }
+}
+
+impl GameRecord {
+    //Fen has to be validated before creating the record
+    pub fn new(fen: &str) -> Self {
+        let start_position = ChessPosition::from_fen(&FEN::from_string(fen.to_string()));
+
+        Self {
+            start_fen: fen.trim().to_string(),
+            start_position,
+            start_ply: Self::ply_from_fen(fen),
+            position: start_position,
+            moves: Vec::new(),
+            pending_result: None,
+        }
+    }
+
+    //Fullmove counter starts at 1 and is increased after black move
+    pub fn ply_from_fen(fen: &str) -> u32 {
+        let parts: Vec<&str> = fen.split_whitespace().collect();
+        let black_to_move = parts.get(1).is_some_and(|&side| side == "b");
+        let full_moves = parts.get(5).and_then(|moves| moves.parse::<u32>().ok()).unwrap_or(1).max(1);
+
+        (full_moves - 1) * 2 + black_to_move as u32
+    }
+
+    //Creates record of the new game, moves it shares with this game keep their search results
+    pub fn with_moves(&self, fen: &str, moves: &[Move]) -> Self {
+        let mut record = Self::new(fen);
+        let mut shared = record.start_position.board() == self.start_position.board() && record.start_ply == self.start_ply;
+
+        for (index, &mv) in moves.iter().enumerate() {
+            match self.moves.get(index) {
+                Some(&recorded) if shared && recorded.mv == mv => {
+                    record.push_recorded(recorded);
+                    continue;
+                }
+                //First move after the end of this game can take the result of the last search
+                None if shared => record.pending_result = self.pending_result,
+                _ => (),
+            }
+
+            shared = false;
+            record.push(mv);
+        }
+
+        if shared && moves.len() == self.moves.len() {
+            record.pending_result = self.pending_result;
+        }
+
+        record
+    }
+
+    pub fn start_fen(&self) -> &str {
+        &self.start_fen
+    }
+
+    pub fn position(&self) -> ChessPosition {
+        self.position
+    }
+
+    pub fn moves(&self) -> &[RecordedMove] {
+        &self.moves
+    }
+
+    pub fn ply(&self) -> u32 {
+        self.start_ply + self.moves.len() as u32
+    }
+
+    //Stores the result of the search of the current position, it's attached to the move,
+    //if that move is played next
+    pub fn add_search_result(&mut self, search_result: SearchResult) {
+        self.pending_result = Some((self.moves.len(), search_result))
+    }
+
+    pub fn push(&mut self, mv: Move) {
+        let search_result = match self.pending_result.take() {
+            Some((index, result)) if index == self.moves.len() && result.best_move == mv => Some(result),
+            _ => None,
+        };
+
+        self.push_recorded(RecordedMove { mv, search_result })
+    }
+
+    fn push_recorded(&mut self, recorded: RecordedMove) {
+        if self.position.board().side_to_move() == Side::WHITE {
+            self.position.make_move::<true, false>(recorded.mv)
+        } else {
+            self.position.make_move::<false, true>(recorded.mv)
+        }
+
+        self.moves.push(recorded);
+    }
+
+    //Takes back the last move and returns false, if there was no move to take back
+    pub fn pop(&mut self) -> bool {
+        if self.moves.pop().is_none() {
+            return false;
+        }
+
+        self.pending_result = None;
+        self.position = self.start_position;
+        for recorded in &self.moves {
+            if self.position.board().side_to_move() == Side::WHITE {
+                self.position.make_move::<true, false>(recorded.mv)
+            } else {
+                self.position.make_move::<false, true>(recorded.mv)
+            }
+        }
+
+        true
+    }
+
+    //Exports the game in PGN format, searched moves are commented with engine evaluation
+    //from the perspective of the side that played the move
+    pub fn to_pgn(&self, chess960: bool) -> String {
+        let result = self.result();
+        let mut pgn = String::new();
+
+        pgn.push_str("[Event \"?\"]\n");
+        pgn.push_str("[Site \"?\"]\n");
+        pgn.push_str("[Date \"????.??.??\"]\n");
+        pgn.push_str("[Round \"?\"]\n");
+        pgn.push_str("[White \"?\"]\n");
+        pgn.push_str("[Black \"?\"]\n");
+        pgn.push_str(&format!("[Result \"{result}\"]\n"));
+
+        if chess960 {
+            pgn.push_str("[Variant \"Chess960\"]\n");
+        }
+
+        let standard_start = ChessPosition::from_fen(&FEN::start_position());
+        if self.start_position.board() != standard_start.board() || self.start_ply != 0 {
+            pgn.push_str("[SetUp \"1\"]\n");
+            pgn.push_str(&format!("[FEN \"{}\"]\n", self.start_fen));
+        }
+
+        pgn.push('\n');
+
+        let mut tokens = Vec::new();
+        let mut position = self.start_position;
+        let mut force_move_number = true;
+        for (index, recorded) in self.moves.iter().enumerate() {
+            let ply = self.start_ply + index as u32;
+            let move_number = ply / 2 + 1;
+
+            //Black move gets its number only at the start of the game or after the comment
+            if ply % 2 == 0 {
+                tokens.push(format!("{move_number}."));
+            } else if force_move_number {
+                tokens.push(format!("{move_number}..."));
+            }
+
+            tokens.push(move_to_san(&position, recorded.mv));
+            force_move_number = false;
+
+            if let Some(search_result) = recorded.search_result {
+                tokens.push(format!(
+                    "{{{:+.2}, {} nodes, {:.2}s}}",
+                    search_result.score.as_cp_f32(),
+                    search_result.nodes,
+                    search_result.time as f64 / 1000.0
+                ));
+                force_move_number = true;
+            }
+
+            if position.board().side_to_move() == Side::WHITE {
+                position.make_move::<true, false>(recorded.mv)
+            } else {
+                position.make_move::<false, true>(recorded.mv)
+            }
+        }
+
+        tokens.push(result.to_string());
+
+        //Movetext lines are kept under 80 characters
+        let mut line_length = 0;
+        for token in tokens {
+            if line_length > 0 && line_length + token.len() + 1 > 80 {
+                pgn.push('\n');
+                line_length = 0;
+            } else if line_length > 0 {
+                pgn.push(' ');
+                line_length += 1;
+            }
+
+            line_length += token.len();
+            pgn.push_str(&token);
+        }
+
+        pgn
+    }
+
+    fn result(&self) -> &'static str {
+        let board = self.position.board();
+        if !legal_moves(board).is_empty() {
+            return "*";
+        }
+
+        match (is_in_check(board), board.side_to_move() == Side::WHITE) {
+            (true, true) => "0-1",
+            (true, false) => "1-0",
+            (false, _) => "1/2-1/2",
+        }
+    }
+}
+
+//Converts the move into Standard Algebraic Notation, using the position before the move
+fn move_to_san(position: &ChessPosition, mv: Move) -> String {
+    let board = position.board();
+    let mut san = if mv.get_flag() == MoveFlag::KING_SIDE_CASTLE {
+        "O-O".to_string()
+    } else if mv.get_flag() == MoveFlag::QUEEN_SIDE_CASTLE {
+        "O-O-O".to_string()
+    } else {
+        let from_square = mv.get_from_square();
+        let to_square = mv.get_to_square();
+        let piece = board.get_piece_on_square(from_square);
+        let mut san = String::new();
+
+        if piece == Piece::PAWN {
+            if mv.is_capture() {
+                san.push(file_char(from_square));
+                san.push('x');
+            }
+
+            san.push_str(&square_to_string(to_square));
+
+            if mv.is_promotion() {
+                san.push('=');
+                san.push(piece_char(mv.get_promotion_piece()));
+            }
+
+            san
+        } else {
+            san.push(piece_char(piece));
+
+            //Other pieces of the same type, that can move to the same square
+            let ambiguous: Vec<Square> = legal_moves(board)
+                .into_iter()
+                .filter(|other| {
+                    other.get_to_square() == to_square
+                        && other.get_from_square() != from_square
+                        && board.get_piece_on_square(other.get_from_square()) == piece
+                })
+                .map(|other| other.get_from_square())
+                .collect();
+
+            if !ambiguous.is_empty() {
+                let same_file = ambiguous.iter().any(|&square| square.get_raw() % 8 == from_square.get_raw() % 8);
+                let same_rank = ambiguous.iter().any(|&square| square.get_raw() / 8 == from_square.get_raw() / 8);
+
+                if !same_file {
+                    san.push(file_char(from_square));
+                } else if !same_rank {
+                    san.push(rank_char(from_square));
+                } else {
+                    san.push_str(&square_to_string(from_square));
+                }
+            }
+
+            if mv.is_capture() {
+                san.push('x');
+            }
+
+            san.push_str(&square_to_string(to_square));
+            san
+        }
+    };
+
+    let mut board_after = *board;
+    if board.side_to_move() == Side::WHITE {
+        board_after.make_move::<true, false>(mv)
+    } else {
+        board_after.make_move::<false, true>(mv)
+    }
+
+    if is_in_check(&board_after) {
+        san.push(if legal_moves(&board_after).is_empty() { '#' } else { '+' });
+    }
+
+    san
+}
+
+fn legal_moves(board: &ChessBoard) -> Vec<Move> {
+    fn collect<const STM_WHITE: bool, const NSTM_WHITE: bool>(board: &ChessBoard) -> Vec<Move> {
+        let mut moves = Vec::new();
+        board.map_moves::<_, STM_WHITE, NSTM_WHITE>(|mv| moves.push(mv));
+        moves
+    }
+
+    if board.side_to_move() == Side::WHITE {
+        collect::<true, false>(board)
+    } else {
+        collect::<false, true>(board)
+    }
+}
+
+fn is_in_check(board: &ChessBoard) -> bool {
+    if board.side_to_move() == Side::WHITE {
+        board.is_in_check::<true, false>()
+    } else {
+        board.is_in_check::<false, true>()
+    }
+}
+
+fn piece_char(piece: Piece) -> char {
+    ['P', 'N', 'B', 'R', 'Q', 'K'][(piece.get_raw() - Piece::PAWN.get_raw()) as usize]
+}
+
+fn file_char(square: Square) -> char {
+    (b'a' + square.get_raw() % 8) as char
+}
+
+fn rank_char(square: Square) -> char {
+    (b'1' + square.get_raw() / 8) as char
+}
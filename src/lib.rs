@@ -1,13 +1,28 @@
 mod bench;
+mod bitbase;
+mod chess960;
 mod color_config;
+mod engine_config;
+mod game_record;
 mod options;
 mod processors;
 mod search;
 mod utils;
 mod see;
+mod syzygy;
 
-pub use options::EngineOptions;
-pub use processors::{MiscCommandsProcessor, ParamsProcessor, UciProcessor};
-pub use search::{GameState, Mcts, NoPrint, SearchEngine, SearchLimits, SearchStats, Tree, PolicyNetwork};
+pub use bitbase::Bitbases;
+pub use chess960::{CastlingFiles, Chess960};
+pub use engine_config::EngineConfig;
+pub use game_record::{GameRecord, RecordedMove};
+pub use options::{EngineOptions, OptionError};
+pub use processors::{
+    CommandProcessor, MiscCommandsProcessor, ParamsProcessor, UciProcessor, XboardProcessor,
+};
+pub use search::{
+    Engine, GameState, JsonPrint, Mcts, NoPrint, PolicyNetwork, PvInfo, SearchInfo, SearchLimits, SearchResult,
+    SearchStats, Tree, ValueNetwork,
+};
 pub use utils::clear_terminal_screen;
 pub use see::SEE;
+pub use syzygy::Syzygy;
@@ -1,63 +1,54 @@
-use std::{env, io::stdin, sync::atomic::AtomicBool};
+use std::{env, io::stdin};
 
-use jackal::{
-    EngineOptions, MiscCommandsProcessor, ParamsProcessor, SearchEngine, Tree, UciProcessor,
-};
-use spear::{ChessPosition, FEN};
+use jackal::{Bitbases, CommandProcessor, Engine, ParamsProcessor};
 
 fn main() {
-    //Init search engine
-    let start_position = ChessPosition::from_fen(&FEN::start_position());
-    let interruption_token = AtomicBool::new(false);
-    let mut options = EngineOptions::new();
-    let mut tree = Tree::new(options.hash(), options.hash_percentage() / 10.0);
-    let mut command_queue: Vec<String> = Vec::new();
-    let mut search_engine = SearchEngine::new(
-        start_position,
-        &interruption_token,
-        &mut tree,
-        &mut options,
-        &mut command_queue,
-    );
+    //Init search engine, search runs in the background so we can keep reading commands
+    let mut search_engine = Engine::new();
+    search_engine.set_print_reports(true);
+    let mut command_processor = CommandProcessor::new();
 
     //Process arguments passed when starting the engine
-    ParamsProcessor::execute(env::args().collect(), &mut search_engine);
+    let startup_commands = ParamsProcessor::execute(env::args().collect(), &mut search_engine);
 
     println!("Jackal v{} by Tomasz Jaworski\n", env!("CARGO_PKG_VERSION"));
 
+    //Load bitbases placed next to the binary
+    let bitbases = Bitbases::load(&Bitbases::default_path());
+    if bitbases > 0 {
+        println!("Loaded {} bitbases", bitbases);
+    }
+
+    //Each search started by startup commands is finished before the next command
+    for command in startup_commands {
+        command_processor.execute(&command, &mut search_engine);
+        finish_search(&mut search_engine);
+    }
+
     loop {
         //Reading the input to obtain command
-        let queue = search_engine.command_queue();
-        let input_command = if !queue.is_empty() {
-            queue.remove(0)
-        } else {
-            let mut input_command = String::new();
-            if stdin().read_line(&mut input_command).is_err() {
+        let mut input_command = String::new();
+        match stdin().read_line(&mut input_command) {
+            Ok(0) => break,
+            Ok(_) => (),
+            Err(_) => {
                 println!("Error reading input, please try again.");
                 continue;
             }
-
-            input_command
-        };
-
-        //Parsing command string and skipping if command is empty
-        let input_command = input_command.trim();
-        let parts: Vec<&str> = input_command.split_whitespace().collect();
-        if parts.is_empty() {
-            continue;
         }
 
-        //Split command string into command and it's arguments, and progress misc commands
-        let command = parts[0];
-        let args = &parts[1..]
-            .iter()
-            .map(|arg_str| arg_str.to_string())
-            .collect::<Vec<String>>();
-        if MiscCommandsProcessor::execute(command, args, &search_engine) {
-            continue;
-        }
+        command_processor.execute(&input_command, &mut search_engine);
+    }
 
-        //Process UCI protocol commands
-        UciProcessor::execute(command, args, &mut search_engine);
+    //Input has ended, so we let the last search finish before exiting
+    finish_search(&mut search_engine);
+}
+
+//Limited search is allowed to finish, infinite one has nothing left to stop it, so it's stopped
+fn finish_search(search_engine: &mut Engine) {
+    if search_engine.is_search_unlimited() {
+        search_engine.stop();
     }
+
+    search_engine.wait();
 }
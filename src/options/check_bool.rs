@@ -1,5 +1,6 @@
-use super::OptionTrait;
+use super::{OptionError, OptionTrait};
 
+#[derive(Clone)]
 pub struct CheckBool {
     value: bool,
     default: bool,
@@ -25,12 +26,10 @@ impl CheckBool {
 impl OptionTrait for CheckBool {
     type ValueType = bool;
 
-    fn set(&mut self, new_value: &str) {
-        if let Ok(parsed_value) = new_value.parse::<bool>() {
-            self.set_value(parsed_value);
-        } else {
-            println!("Invalid value for option.");
-        }
+    fn set(&mut self, new_value: &str) -> Result<(), OptionError> {
+        let parsed_value = new_value.parse::<bool>().map_err(|_| OptionError::InvalidValue)?;
+        self.set_value(parsed_value);
+        Ok(())
     }
 
     fn get(&self) -> bool {
@@ -4,8 +4,10 @@ mod spin_float;
 mod spin_float_tunable;
 mod spin_int;
 mod spin_int_tunable;
+mod string_option;
 mod traits;
 
 pub use options_base::EngineOptions;
+pub use traits::OptionError;
 pub(super) use traits::OptionTrait;
 pub(super) use traits::Tunable;
@@ -1,9 +1,10 @@
 use super::{
-    check_bool::CheckBool, spin_float::SpinOptionFloat, spin_float_tunable::SpinOptionFloatTunable, spin_int::SpinOptionInt, OptionTrait
+    check_bool::CheckBool, spin_float::SpinOptionFloat, spin_float_tunable::SpinOptionFloatTunable, spin_int::SpinOptionInt, string_option::StringOption, OptionError, OptionTrait
 };
 
 macro_rules! create_option_structs {
     ($($option_name:expr => $name:ident: $type:ty, $($values:expr), +;)*) => {
+        #[derive(Clone)]
         pub struct EngineOptions {
             $($name: $type,)*
         }
@@ -23,12 +24,35 @@ macro_rules! create_option_structs {
             }
 
             pub fn set(&mut self, key: &str, new_value: &str) {
-                match key {
-                    $($option_name => Self::update_option(&mut self.$name, new_value),)*
-                    _ => println!("Option {} doesn't exist.", key),
+                if let Err(error) = self.try_set(key, new_value) {
+                    println!("{}", error);
                 }
             }
 
+            pub fn contains(&self, key: &str) -> bool {
+                let normalized_key = Self::normalize_name(key);
+                $(
+                    if normalized_key == Self::normalize_name($option_name) {
+                        return true;
+                    }
+                )*
+
+                false
+            }
+
+            //Option names are matched ignoring case and spaces, so "Move Overhead" sets "MoveOverhead".
+            //Returns the name of the option that was set, as it's declared
+            pub fn try_set(&mut self, key: &str, new_value: &str) -> Result<&'static str, OptionError> {
+                let normalized_key = Self::normalize_name(key);
+                $(
+                    if normalized_key == Self::normalize_name($option_name) {
+                        return Self::update_option(&mut self.$name, new_value).map(|_| $option_name);
+                    }
+                )*
+
+                Err(OptionError::UnknownOption(key.to_string()))
+            }
+
             pub fn print(&self) {
                 $(
                     self.$name.print($option_name);
@@ -42,8 +66,15 @@ macro_rules! create_option_structs {
                 }
             )*
 
-            fn update_option<T: OptionTrait>(option: &mut T, new_value: &str) {
-                option.set(new_value);
+            fn update_option<T: OptionTrait>(option: &mut T, new_value: &str) -> Result<(), OptionError> {
+                option.set(new_value)
+            }
+
+            fn normalize_name(name: &str) -> String {
+                name.chars()
+                    .filter(|char| !char.is_whitespace())
+                    .collect::<String>()
+                    .to_ascii_lowercase()
             }
         }
     };
@@ -55,10 +86,21 @@ create_option_structs!(
     "MoveOverhead"           => move_overhead:            SpinOptionInt,   10, 0, 500;
     "MultiPV"                => multi_pv:                 SpinOptionInt,   1, 1, 256;
     "UCI_ShowWDL"            => show_wdl:                 CheckBool,       false;
+    "UCI_ShowMovesLeft"      => show_moves_left:          CheckBool,       false;
     "UCI_AnalyseMode"        => analyse_mode:             CheckBool,       false;
+    "Ponder"                 => ponder:                   CheckBool,       false;
+    "UCI_Chess960"           => chess960:                 CheckBool,       false;
+    "LimitStrength"          => limit_strength:           CheckBool,       false;
+    "StrengthLevel"          => strength_level:           SpinOptionInt,   20, 0, 20;
+    "Deterministic"          => deterministic:            CheckBool,       false;
+    "BatchSize"              => batch_size:               SpinOptionInt,   1, 1, 256;
     "DrawContempt"           => draw_contempt:            SpinOptionFloat, 0.1, -0.5, 0.5;
     "PolicySacBonus"         => policy_sac_bonus:         SpinOptionFloat, 0.14, 0.0, 1.0;
     "MaterialReductionBonus" => material_reduction_bonus: SpinOptionFloat, 0.25, 0.0, 10.0;
+    "MovesLeftThreshold"     => moves_left_threshold:     SpinOptionFloat, 0.6, 0.0, 1.0;
+    "MovesLeftScale"         => moves_left_scale:         SpinOptionFloat, 0.0, 0.0, 1.0;
+    "TranspositionWeight"    => transposition_weight:     SpinOptionFloat, 0.0, 0.0, 1.0;
+    "SyzygyPath"             => syzygy_path:              StringOption,    "<empty>";
     
     "RootCpuctValue"      => root_cpuct_value:      SpinOptionFloatTunable, 0.96, 0.1, 5.0, 0.055, 0.002;
     "CpuctValue"          => cpuct_value:           SpinOptionFloatTunable, 0.64, 0.1, 5.0, 0.055, 0.002;
@@ -68,4 +110,32 @@ create_option_structs!(
     "CpuctVarianceScale"  => cpuct_variance_scale:  SpinOptionFloatTunable, 0.2, 0.0, 2.0, 0.02, 0.002;
     "CpuctVarianceWeight" => cpuct_variance_weight: SpinOptionFloatTunable, 0.85, 0.0, 2.0, 0.084, 0.002;
     "HashPercentage"      => hash_percentage:       SpinOptionFloatTunable, 0.11, 0.01, 5.0, 0.025, 0.002;
+    "FiftyMoveStart"      => fifty_move_start:      SpinOptionFloatTunable, 20.0, 0.0, 99.0, 4.0, 0.002;
+    "FiftyMoveScale"      => fifty_move_scale:      SpinOptionFloatTunable, 0.5, 0.0, 1.0, 0.05, 0.002;
+    "FiftyMovePower"      => fifty_move_power:      SpinOptionFloatTunable, 2.0, 0.5, 4.0, 0.2, 0.002;
 );
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn try_set_returns_declared_name() {
+        let mut options = EngineOptions::new();
+
+        assert_eq!(options.try_set("hash", "64"), Ok("Hash"));
+        assert_eq!(options.try_set("Syzygy Path", "/tb"), Ok("SyzygyPath"));
+        assert_eq!(options.try_set(" move overhead ", "20"), Ok("MoveOverhead"));
+        assert_eq!(options.hash(), 64);
+        assert_eq!(options.move_overhead(), 20);
+    }
+
+    #[test]
+    fn try_set_rejects_unknown_options() {
+        let mut options = EngineOptions::new();
+
+        assert!(matches!(options.try_set("Hashes", "64"), Err(OptionError::UnknownOption(_))));
+        assert!(options.try_set("Hash", "many").is_err());
+        assert_eq!(options.hash(), 32);
+    }
+}
@@ -1,6 +1,7 @@
-use super::OptionTrait;
+use super::{OptionError, OptionTrait};
 
 #[allow(unused)]
+#[derive(Clone)]
 pub struct SpinOptionFloat {
     value: f32,
     default: f32,
@@ -19,12 +20,13 @@ impl SpinOptionFloat {
         }
     }
 
-    pub fn set_value(&mut self, new_value: i32) {
+    pub fn set_value(&mut self, new_value: i32) -> Result<(), OptionError> {
         let adjusted = new_value as f32 / 100.0;
         if adjusted >= self.min && adjusted <= self.max {
             self.value = adjusted;
+            Ok(())
         } else {
-            println!("Value out of range.");
+            Err(OptionError::OutOfRange)
         }
     }
 
@@ -36,12 +38,9 @@ impl SpinOptionFloat {
 impl OptionTrait for SpinOptionFloat {
     type ValueType = f32;
 
-    fn set(&mut self, new_value: &str) {
-        if let Ok(parsed_value) = new_value.parse::<i32>() {
-            self.set_value(parsed_value);
-        } else {
-            println!("Invalid value for option.");
-        }
+    fn set(&mut self, new_value: &str) -> Result<(), OptionError> {
+        let parsed_value = new_value.parse::<i32>().map_err(|_| OptionError::InvalidValue)?;
+        self.set_value(parsed_value)
     }
 
     fn get(&self) -> f32 {
@@ -1,6 +1,7 @@
-use super::{OptionTrait, Tunable};
+use super::{OptionError, OptionTrait, Tunable};
 
 #[allow(unused)]
+#[derive(Clone)]
 pub struct SpinOptionFloatTunable {
     value: f32,
     default: f32,
@@ -23,12 +24,13 @@ impl SpinOptionFloatTunable {
         }
     }
 
-    pub fn set_value(&mut self, new_value: i32) {
+    pub fn set_value(&mut self, new_value: i32) -> Result<(), OptionError> {
         let adjusted = new_value as f32 / 100.0;
         if adjusted >= self.min && adjusted <= self.max {
             self.value = adjusted;
+            Ok(())
         } else {
-            println!("Value out of range.");
+            Err(OptionError::OutOfRange)
         }
     }
 
@@ -40,12 +42,9 @@ impl SpinOptionFloatTunable {
 impl OptionTrait for SpinOptionFloatTunable {
     type ValueType = f32;
 
-    fn set(&mut self, new_value: &str) {
-        if let Ok(parsed_value) = new_value.parse::<i32>() {
-            self.set_value(parsed_value);
-        } else {
-            println!("Invalid value for option.");
-        }
+    fn set(&mut self, new_value: &str) -> Result<(), OptionError> {
+        let parsed_value = new_value.parse::<i32>().map_err(|_| OptionError::InvalidValue)?;
+        self.set_value(parsed_value)
     }
 
     fn get(&self) -> f32 {
@@ -1,5 +1,6 @@
-use super::OptionTrait;
+use super::{OptionError, OptionTrait};
 
+#[derive(Clone)]
 pub struct SpinOptionInt {
     value: i32,
     default: i32,
@@ -17,11 +18,12 @@ impl SpinOptionInt {
         }
     }
 
-    pub fn set_value(&mut self, new_value: i32) {
+    pub fn set_value(&mut self, new_value: i32) -> Result<(), OptionError> {
         if new_value >= self.min && new_value <= self.max {
             self.value = new_value;
+            Ok(())
         } else {
-            println!("Value out of range.");
+            Err(OptionError::OutOfRange)
         }
     }
 
@@ -33,12 +35,9 @@ impl SpinOptionInt {
 impl OptionTrait for SpinOptionInt {
     type ValueType = i32;
 
-    fn set(&mut self, new_value: &str) {
-        if let Ok(parsed_value) = new_value.parse::<i32>() {
-            self.set_value(parsed_value);
-        } else {
-            println!("Invalid value for option.");
-        }
+    fn set(&mut self, new_value: &str) -> Result<(), OptionError> {
+        let parsed_value = new_value.parse::<i32>().map_err(|_| OptionError::InvalidValue)?;
+        self.set_value(parsed_value)
     }
 
     fn get(&self) -> i32 {
@@ -1,6 +1,7 @@
-use super::{OptionTrait, Tunable};
+use super::{OptionError, OptionTrait, Tunable};
 
 #[allow(unused)]
+#[derive(Clone)]
 pub struct SpinOptionIntTunable {
     value: i32,
     default: i32,
@@ -23,11 +24,12 @@ impl SpinOptionIntTunable {
         }
     }
 
-    pub fn set_value(&mut self, new_value: i32) {
+    pub fn set_value(&mut self, new_value: i32) -> Result<(), OptionError> {
         if new_value >= self.min && new_value <= self.max {
             self.value = new_value;
+            Ok(())
         } else {
-            println!("Value out of range.");
+            Err(OptionError::OutOfRange)
         }
     }
 
@@ -39,12 +41,9 @@ impl SpinOptionIntTunable {
 impl OptionTrait for SpinOptionIntTunable {
     type ValueType = i32;
 
-    fn set(&mut self, new_value: &str) {
-        if let Ok(parsed_value) = new_value.parse::<i32>() {
-            self.set_value(parsed_value);
-        } else {
-            println!("Invalid value for option.");
-        }
+    fn set(&mut self, new_value: &str) -> Result<(), OptionError> {
+        let parsed_value = new_value.parse::<i32>().map_err(|_| OptionError::InvalidValue)?;
+        self.set_value(parsed_value)
     }
 
     fn get(&self) -> i32 {
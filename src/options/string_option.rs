@@ -0,0 +1,55 @@
+use super::{OptionError, OptionTrait};
+
+//Empty value is printed and accepted as "<empty>", because GUIs can't send an empty string
+#[derive(Clone)]
+pub struct StringOption {
+    value: String,
+    default: String,
+}
+
+impl StringOption {
+    const EMPTY: &'static str = "<empty>";
+
+    pub fn new(value: &str) -> Self {
+        let value = Self::normalize(value);
+        Self {
+            value: value.clone(),
+            default: value,
+        }
+    }
+
+    pub fn set_value(&mut self, new_value: &str) {
+        self.value = Self::normalize(new_value);
+    }
+
+    pub fn get(&self) -> String {
+        self.value.clone()
+    }
+
+    fn normalize(value: &str) -> String {
+        let value = value.trim();
+        if value == Self::EMPTY {
+            String::new()
+        } else {
+            value.to_string()
+        }
+    }
+}
+
+impl OptionTrait for StringOption {
+    type ValueType = String;
+
+    fn set(&mut self, new_value: &str) -> Result<(), OptionError> {
+        self.set_value(new_value);
+        Ok(())
+    }
+
+    fn get(&self) -> String {
+        self.get()
+    }
+
+    fn print(&self, name: &str) {
+        let default = if self.default.is_empty() { Self::EMPTY } else { &self.default };
+        println!("option name {} type string default {}", name, default);
+    }
+}
@@ -1,7 +1,26 @@
+use std::fmt::{Display, Formatter, Result};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum OptionError {
+    UnknownOption(String),
+    InvalidValue,
+    OutOfRange,
+}
+
+impl Display for OptionError {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> Result {
+        match self {
+            Self::UnknownOption(name) => write!(formatter, "Option {} doesn't exist.", name),
+            Self::InvalidValue => write!(formatter, "Invalid value for option."),
+            Self::OutOfRange => write!(formatter, "Value out of range."),
+        }
+    }
+}
+
 #[allow(unused)]
 pub trait OptionTrait {
     type ValueType;
-    fn set(&mut self, new_value: &str);
+    fn set(&mut self, new_value: &str) -> std::result::Result<(), OptionError>;
     fn get(&self) -> Self::ValueType;
     fn print(&self, name: &str);
 }
@@ -0,0 +1,58 @@
+use crate::Engine;
+
+use super::{MiscCommandsProcessor, UciProcessor, XboardProcessor};
+
+pub struct CommandProcessor {
+    xboard: XboardProcessor,
+}
+
+impl Default for CommandProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommandProcessor {
+    pub fn new() -> Self {
+        Self {
+            xboard: XboardProcessor::new(),
+        }
+    }
+
+    //Routes the command to the right processor, commands from the input and startup commands
+    //take the same path
+    pub fn execute(&mut self, input_command: &str, search_engine: &mut Engine) {
+        //Parsing command string and skipping if command is empty
+        let input_command = input_command.trim();
+        let parts: Vec<&str> = input_command.split_whitespace().collect();
+        if parts.is_empty() {
+            return;
+        }
+
+        //Split command string into command and it's arguments, and progress misc commands
+        let command = parts[0];
+        let args = &parts[1..]
+            .iter()
+            .map(|arg_str| arg_str.to_string())
+            .collect::<Vec<String>>();
+
+        //Protocol is selected by the first command, XBoard commands take priority once it's selected
+        if ((command == "xboard" && !search_engine.protocol_initialized()) || search_engine.xboard_initialized())
+            && self.xboard.execute(command, args, search_engine)
+        {
+            return;
+        }
+
+        if MiscCommandsProcessor::execute(command, args, search_engine) {
+            return;
+        }
+
+        if search_engine.xboard_initialized() {
+            println!("Error (unknown command): {}", command);
+            return;
+        }
+
+        //Process UCI protocol commands
+        UciProcessor::execute(input_command, search_engine);
+    }
+}
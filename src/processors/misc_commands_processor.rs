@@ -3,14 +3,14 @@ use std::cmp::Ordering;
 use spear::{ChessBoard, Move, Perft, Piece, Side, FEN};
 
 use crate::{
-    search::{NodeIndex, PolicyNetwork, Score, SearchEngine, ValueNetwork},
-    utils::{clear_terminal_screen, heat_color}, EngineOptions,
+    search::{NodeIndex, PolicyNetwork, Score, Engine, ValueNetwork},
+    utils::{clear_terminal_screen, heat_color}, Bitbases, EngineOptions,
 };
 
 pub struct MiscCommandsProcessor;
 impl MiscCommandsProcessor {
     //Handles commands that are not UCI related and returns true, if command was found, so it can skip uci commands
-    pub fn execute(command: &str, args: &[String], search_engine: &SearchEngine) -> bool {
+    pub fn execute(command: &str, args: &[String], search_engine: &Engine) -> bool {
         match command {
             "exit" | "quit" | "q" => std::process::exit(0),
             "clean" | "clear" | "cls" | "cln" => clear_terminal_screen(),
@@ -24,12 +24,25 @@ impl MiscCommandsProcessor {
             "moves" => Self::moves(search_engine),
             "tree" => Self::draw_tree(args, search_engine),
             "eval" | "e" => Self::eval(search_engine),
+            "pgn" => println!("{}", search_engine.game_record().to_pgn(search_engine.engine_options().chess960())),
+            "gen-bitbase" => Self::gen_bitbase(args),
             _ => return false,
         }
 
         true
     }
 
+    //Generates bitbases of given materials, or of the default ones, next to the binary
+    fn gen_bitbase(args: &[String]) {
+        let materials: Vec<String> = if args.is_empty() {
+            Bitbases::DEFAULT_MATERIALS.iter().map(|material| material.to_string()).collect()
+        } else {
+            args.iter().map(|material| material.to_ascii_uppercase()).collect()
+        };
+
+        Bitbases::generate(&Bitbases::default_path(), &materials);
+    }
+
     //Performs move generator performance test
     fn perft<const BULK: bool>(args: &[String], current_fen: &FEN) {
         //Obtain test depth from command arguments
@@ -47,7 +60,7 @@ impl MiscCommandsProcessor {
     }
 
     //Prints all legal moves together with thier policy
-    fn moves(search_engine: &SearchEngine) {
+    fn moves(search_engine: &Engine) {
         println!("All legal moves");
         let mut moves: Vec<(Move, f32)> = Vec::new();
         let board = *search_engine.current_position().board();
@@ -125,7 +138,7 @@ impl MiscCommandsProcessor {
         }
     }
 
-    fn draw_tree(args: &[String], search_engine: &SearchEngine) {
+    fn draw_tree(args: &[String], search_engine: &Engine) {
         if args.len() > 3 {
             return;
         }
@@ -167,9 +180,9 @@ impl MiscCommandsProcessor {
         }
     }
 
-    fn eval(search_engine: &SearchEngine) {
+    fn eval(search_engine: &Engine) {
         let position: &spear::ChessPosition = &search_engine.current_position();
-        let (w, d, _) = if position.board().side_to_move() == Side::WHITE {
+        let (w, d, _, moves_left) = if position.board().side_to_move() == Side::WHITE {
             ValueNetwork.forward::<true, false>(position.board())
         } else {
             ValueNetwork.forward::<false, true>(position.board())
@@ -186,6 +199,10 @@ impl MiscCommandsProcessor {
         println!("For them");
         println!("Score: {} ({:.2})", score.single(0.0), score.as_cp_f32());
         println!("WDL: [{:.2}%, {:.2}%, {:.2}%]\n", score.win_chance() * 100.0, score.draw_chance() * 100.0, score.lose_chance() * 100.0);
+
+        if ValueNetwork::HAS_MOVES_LEFT {
+            println!("Moves left: {:.1}", moves_left);
+        }
     }
 }
 
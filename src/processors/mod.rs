@@ -1,7 +1,13 @@
+mod command_processor;
 mod misc_commands_processor;
 mod params_processor;
+mod uci_command;
 mod uci_processor;
+mod xboard_processor;
 
+pub use command_processor::CommandProcessor;
 pub use misc_commands_processor::MiscCommandsProcessor;
 pub use params_processor::ParamsProcessor;
+pub use uci_command::{GoCommand, UciCommand, UciParseError};
 pub use uci_processor::UciProcessor;
+pub use xboard_processor::XboardProcessor;
@@ -1,26 +1,60 @@
-use crate::{bench::Bench, SearchEngine};
+use crate::{bench::Bench, Engine, EngineConfig};
+
+//Benchmark requested with 'bench' argument, arguments following it choose the kind of the bench
+#[derive(Clone, Copy)]
+enum BenchMode {
+    Depth(u32),
+    Deterministic,
+    //Max threads and batch size, batch size is known once max threads are given
+    Threads(u32, Option<u32>),
+}
 
 pub struct ParamsProcessor;
 impl ParamsProcessor {
-    #[allow(unused_assignments)]
-    pub fn execute(args: Vec<String>, search_engine: &mut SearchEngine) {
-        let mut bench = false;
-        let mut bench_depth = 0;
+    //Processes arguments passed when starting the engine and returns startup commands,
+    //that have to be executed before reading the input. Arguments that are not recognized
+    //are treated as commands, so 'jackal "go nodes 1000" quit' runs a search and exits
+    pub fn execute(args: Vec<String>, search_engine: &mut Engine) -> Vec<String> {
+        //Config is applied first, so options passed in arguments can override it
+        let mut commands = Self::load_config(&args, search_engine);
+
+        let mut bench = None;
+        let mut bench_arguments = false;
         let mut options = false;
+        let mut config = false;
         let mut option_name = String::new();
 
-        for arg in args {
+        //First argument is the path to the binary
+        for arg in args.into_iter().skip(1) {
             match arg.as_str() {
                 "--options" => options = true,
+                "--config" => config = true,
+                "--json" => search_engine.set_json_output(true),
                 "bench" => {
-                    bench_depth = 5;
+                    bench = Some(BenchMode::Depth(5));
+                    bench_arguments = true;
                     options = false;
-                    bench = true;
                 }
                 _ => {
-                    if bench {
-                        bench_depth = arg.parse::<u32>().unwrap_or(5);
-                        bench = false;
+                    if config {
+                        config = false;
+                        continue;
+                    }
+
+                    if bench_arguments {
+                        bench_arguments = false;
+                        if let Some((mode, more_arguments)) =
+                            bench.and_then(|mode| Self::extend_bench(mode, &arg, search_engine))
+                        {
+                            bench = Some(mode);
+                            bench_arguments = more_arguments;
+                            continue;
+                        }
+                    }
+
+                    //Option pairs are read until the argument is not an option name
+                    if options && option_name.is_empty() && !search_engine.engine_options().contains(&arg) {
+                        options = false;
                     }
 
                     if options {
@@ -29,27 +63,98 @@ impl ParamsProcessor {
                             continue;
                         }
 
-                        search_engine.engine_options_mut().set(&option_name, &arg);
-
-                        if option_name == "Hash" {
-                            let hash_size = search_engine.engine_options().hash();
-                            let hash_percentage = search_engine.engine_options().hash_percentage() / 10.0;
-                            search_engine.tree_mut().resize_tree(hash_size, hash_percentage)
-                        }
+                        search_engine.set_option(&option_name, &arg);
 
                         option_name.clear();
+                        continue;
                     }
+
+                    commands.push(arg);
                 }
             }
         }
 
-        if bench_depth > 0 {
-            Self::bench(bench_depth, search_engine);
+        match bench {
+            Some(BenchMode::Depth(depth)) if depth > 0 => Self::bench(depth, search_engine),
+            Some(BenchMode::Deterministic) => Self::bench_deterministic(search_engine),
+            Some(BenchMode::Threads(max_threads, batch_size)) => {
+                let batch_size = batch_size.unwrap_or(search_engine.engine_options().batch_size() as u32);
+                Self::bench_threads(max_threads, batch_size, search_engine)
+            }
+            _ => (),
+        }
+
+        commands
+    }
+
+    //Applies the argument following the bench and returns the new mode together with whether more
+    //arguments can follow. Returns None, when the argument doesn't belong to the bench. Thread bench
+    //takes max threads followed by batch size
+    fn extend_bench(mode: BenchMode, arg: &str, search_engine: &Engine) -> Option<(BenchMode, bool)> {
+        match (mode, arg) {
+            (BenchMode::Depth(_), "deterministic") => Some((BenchMode::Deterministic, false)),
+            (BenchMode::Depth(_), "threads") => Some((BenchMode::Threads(Self::available_threads(), None), true)),
+            (BenchMode::Depth(_), _) => arg.parse().ok().map(|depth| (BenchMode::Depth(depth), false)),
+            (BenchMode::Threads(_, None), _) => {
+                let batch_size = search_engine.engine_options().batch_size() as u32;
+                arg.parse().ok().map(|max_threads| (BenchMode::Threads(max_threads, Some(batch_size)), true))
+            }
+            (BenchMode::Threads(max_threads, Some(_)), _) => {
+                arg.parse().ok().map(|batch_size| (BenchMode::Threads(max_threads, Some(batch_size)), false))
+            }
+            (BenchMode::Deterministic, _) => None,
+        }
+    }
+
+    //Loads config from '--config <path>' or default config next to the binary, if there is one
+    fn load_config(args: &[String], search_engine: &mut Engine) -> Vec<String> {
+        let config_path = match args.iter().position(|arg| arg == "--config") {
+            Some(index) => match args.get(index + 1) {
+                Some(path) => path.clone(),
+                None => {
+                    println!("Missing path after --config");
+                    return Vec::new();
+                }
+            },
+            None => match EngineConfig::default_path() {
+                Some(path) => path.to_string_lossy().to_string(),
+                None => return Vec::new(),
+            },
+        };
+
+        let config = match EngineConfig::from_file(&config_path) {
+            Ok(config) => config,
+            Err(error) => {
+                println!("{}", error);
+                return Vec::new();
+            }
+        };
+
+        for (line_number, error) in config.apply(search_engine) {
+            println!("{}:{}: {}", config_path, line_number, error);
         }
+
+        config.commands().to_vec()
     }
 
-    fn bench(depth: u32, search_engine: &SearchEngine) {
+    fn bench(depth: u32, search_engine: &Engine) {
         Bench::run(depth, search_engine);
         std::process::exit(0)
     }
+
+    fn bench_deterministic(search_engine: &Engine) {
+        let passed = Bench::run_deterministic(search_engine);
+        std::process::exit(if passed { 0 } else { 1 })
+    }
+
+    fn bench_threads(max_threads: u32, batch_size: u32, search_engine: &Engine) {
+        Bench::run_threads(max_threads, batch_size, search_engine);
+        std::process::exit(0)
+    }
+
+    fn available_threads() -> u32 {
+        std::thread::available_parallelism()
+            .map(|threads| threads.get() as u32)
+            .unwrap_or(1)
+    }
 }
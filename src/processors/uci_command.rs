@@ -0,0 +1,416 @@
+use std::fmt::{Display, Formatter, Result};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum UciCommand {
+    Uci,
+    Debug(bool),
+    IsReady,
+    SetOption { name: String, value: String },
+    UciNewGame,
+    Position { fen: Option<String>, moves: Vec<String> },
+    Go(GoCommand),
+    Stop,
+    PonderHit,
+    Quit,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GoCommand {
+    pub wtime: Option<u64>,
+    pub btime: Option<u64>,
+    pub winc: Option<u64>,
+    pub binc: Option<u64>,
+    pub moves_to_go: Option<u32>,
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub mate: Option<u32>,
+    pub move_time: Option<u64>,
+    pub infinite: bool,
+    pub ponder: bool,
+    pub search_moves: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum UciParseError {
+    Empty,
+    UnknownCommand(String),
+    MissingArgument { command: &'static str, argument: &'static str },
+    InvalidValue { argument: String, value: String },
+    UnexpectedToken { command: &'static str, token: String },
+}
+
+impl Display for UciParseError {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> Result {
+        match self {
+            Self::Empty => write!(formatter, "Empty command"),
+            Self::UnknownCommand(command) => write!(formatter, "Unknown command: {command}"),
+            Self::MissingArgument { command, argument } => {
+                write!(formatter, "Missing argument '{argument}' in '{command}'")
+            }
+            Self::InvalidValue { argument, value } => {
+                write!(formatter, "Invalid value '{value}' for '{argument}'")
+            }
+            Self::UnexpectedToken { command, token } => {
+                write!(formatter, "Unexpected token '{token}' in '{command}'")
+            }
+        }
+    }
+}
+
+const GO_KEYWORDS: [&str; 12] = [
+    "wtime", "btime", "winc", "binc", "movestogo", "depth",
+    "nodes", "mate", "movetime", "infinite", "ponder", "searchmoves",
+];
+
+impl UciCommand {
+    pub fn parse(input: &str) -> std::result::Result<Self, UciParseError> {
+        let tokens = tokenize(input);
+        let Some(&(_, command)) = tokens.first() else {
+            return Err(UciParseError::Empty);
+        };
+
+        let args = &tokens[1..];
+        match command {
+            "uci" => Ok(Self::Uci),
+            "debug" => Self::parse_debug(args),
+            "isready" => Ok(Self::IsReady),
+            "setoption" => Self::parse_set_option(input, args),
+            "ucinewgame" => Ok(Self::UciNewGame),
+            "position" => Self::parse_position(args),
+            "go" => Self::parse_go(args),
+            "stop" => Ok(Self::Stop),
+            "ponderhit" => Ok(Self::PonderHit),
+            "quit" => Ok(Self::Quit),
+            _ => Err(UciParseError::UnknownCommand(command.to_string())),
+        }
+    }
+
+    fn parse_debug(args: &[(usize, &str)]) -> std::result::Result<Self, UciParseError> {
+        match args.first() {
+            Some((_, "on")) => Ok(Self::Debug(true)),
+            Some((_, "off")) => Ok(Self::Debug(false)),
+            Some((_, token)) => Err(UciParseError::InvalidValue {
+                argument: "debug".to_string(),
+                value: token.to_string(),
+            }),
+            None => Err(UciParseError::MissingArgument { command: "debug", argument: "on|off" }),
+        }
+    }
+
+    //setoption name <id> [value <x>], where both id and x can contain spaces. Value is taken
+    //directly from the input string, so it keeps its original spacing
+    fn parse_set_option(input: &str, args: &[(usize, &str)]) -> std::result::Result<Self, UciParseError> {
+        match args.first() {
+            Some((_, "name")) => (),
+            Some((_, token)) => {
+                return Err(UciParseError::UnexpectedToken { command: "setoption", token: token.to_string() })
+            }
+            None => return Err(UciParseError::MissingArgument { command: "setoption", argument: "name" }),
+        }
+
+        let value_index = args.iter().position(|&(_, token)| token == "value");
+        let name_tokens = &args[1..value_index.unwrap_or(args.len())];
+        if name_tokens.is_empty() {
+            return Err(UciParseError::MissingArgument { command: "setoption", argument: "name" });
+        }
+
+        let name = name_tokens
+            .iter()
+            .map(|&(_, token)| token)
+            .collect::<Vec<&str>>()
+            .join(" ");
+
+        let value = match value_index {
+            Some(index) if index + 1 < args.len() => input[args[index + 1].0..].trim_end().to_string(),
+            Some(_) => return Err(UciParseError::MissingArgument { command: "setoption", argument: "value" }),
+            None => String::new(),
+        };
+
+        Ok(Self::SetOption { name, value })
+    }
+
+    //position [fen <fenstring> | startpos] [moves <move1> ... <movei>]
+    fn parse_position(args: &[(usize, &str)]) -> std::result::Result<Self, UciParseError> {
+        let moves_index = args.iter().position(|&(_, token)| token == "moves");
+        let position_args = &args[..moves_index.unwrap_or(args.len())];
+
+        let fen = match position_args.first() {
+            Some((_, "startpos")) => {
+                if let Some(&(_, token)) = position_args.get(1) {
+                    return Err(UciParseError::UnexpectedToken { command: "position", token: token.to_string() });
+                }
+                None
+            }
+            Some((_, "fen")) => {
+                if position_args.len() < 2 {
+                    return Err(UciParseError::MissingArgument { command: "position", argument: "fen" });
+                }
+
+                let fen = position_args[1..]
+                    .iter()
+                    .map(|&(_, token)| token)
+                    .collect::<Vec<&str>>()
+                    .join(" ");
+                Some(fen)
+            }
+            Some((_, token)) => {
+                return Err(UciParseError::UnexpectedToken { command: "position", token: token.to_string() })
+            }
+            None => {
+                return Err(UciParseError::MissingArgument { command: "position", argument: "startpos|fen" })
+            }
+        };
+
+        let moves = match moves_index {
+            Some(index) => args[index + 1..].iter().map(|&(_, token)| token.to_string()).collect(),
+            None => Vec::new(),
+        };
+
+        Ok(Self::Position { fen, moves })
+    }
+
+    fn parse_go(args: &[(usize, &str)]) -> std::result::Result<Self, UciParseError> {
+        let mut result = GoCommand::default();
+
+        //Empty go command is treated as infinite search
+        if args.is_empty() {
+            result.infinite = true;
+            return Ok(Self::Go(result));
+        }
+
+        let mut index = 0;
+        while index < args.len() {
+            let keyword = args[index].1;
+            index += 1;
+
+            match keyword {
+                "infinite" => result.infinite = true,
+                "ponder" => result.ponder = true,
+                "searchmoves" => {
+                    while index < args.len() && !GO_KEYWORDS.contains(&args[index].1) {
+                        result.search_moves.push(args[index].1.to_string());
+                        index += 1;
+                    }
+                }
+                "nodes" => {
+                    result.nodes = Some(parse_value::<u64>(keyword, args.get(index))?);
+                    index += 1;
+                }
+                "wtime" | "btime" | "winc" | "binc" | "movetime" => {
+                    let value = parse_time(keyword, args.get(index))?;
+                    index += 1;
+                    match keyword {
+                        "wtime" => result.wtime = Some(value),
+                        "btime" => result.btime = Some(value),
+                        "winc" => result.winc = Some(value),
+                        "binc" => result.binc = Some(value),
+                        _ => result.move_time = Some(value),
+                    }
+                }
+                "movestogo" | "depth" | "mate" => {
+                    let value = parse_value::<u32>(keyword, args.get(index))?;
+                    index += 1;
+                    match keyword {
+                        "movestogo" => result.moves_to_go = Some(value),
+                        "depth" => result.depth = Some(value),
+                        _ => result.mate = Some(value),
+                    }
+                }
+                _ => return Err(UciParseError::UnexpectedToken { command: "go", token: keyword.to_string() }),
+            }
+        }
+
+        Ok(Self::Go(result))
+    }
+}
+
+//Splits input into whitespace separated tokens, together with their byte offsets in the input
+fn tokenize(input: &str) -> Vec<(usize, &str)> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+
+    for (index, char) in input.char_indices() {
+        match (char.is_whitespace(), start) {
+            (true, Some(token_start)) => {
+                tokens.push((token_start, &input[token_start..index]));
+                start = None;
+            }
+            (false, None) => start = Some(index),
+            _ => (),
+        }
+    }
+
+    if let Some(token_start) = start {
+        tokens.push((token_start, &input[token_start..]));
+    }
+
+    tokens
+}
+
+fn parse_value<T: std::str::FromStr>(
+    argument: &str,
+    token: Option<&(usize, &str)>,
+) -> std::result::Result<T, UciParseError> {
+    let Some(&(_, value)) = token else {
+        return Err(UciParseError::InvalidValue { argument: argument.to_string(), value: String::new() });
+    };
+
+    value.parse::<T>().map_err(|_| UciParseError::InvalidValue {
+        argument: argument.to_string(),
+        value: value.to_string(),
+    })
+}
+
+//Some GUIs send negative remaining time when flagging, so we clamp it to zero
+fn parse_time(argument: &str, token: Option<&(usize, &str)>) -> std::result::Result<u64, UciParseError> {
+    parse_value::<i64>(argument, token).map(|value| value.max(0) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn go(input: &str) -> GoCommand {
+        match UciCommand::parse(input) {
+            Ok(UciCommand::Go(go_command)) => go_command,
+            other => panic!("Expected go command, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parses_simple_commands() {
+        assert_eq!(UciCommand::parse("uci"), Ok(UciCommand::Uci));
+        assert_eq!(UciCommand::parse("  isready  "), Ok(UciCommand::IsReady));
+        assert_eq!(UciCommand::parse("ucinewgame"), Ok(UciCommand::UciNewGame));
+        assert_eq!(UciCommand::parse("stop"), Ok(UciCommand::Stop));
+        assert_eq!(UciCommand::parse("ponderhit"), Ok(UciCommand::PonderHit));
+        assert_eq!(UciCommand::parse("quit"), Ok(UciCommand::Quit));
+        assert_eq!(UciCommand::parse("debug on"), Ok(UciCommand::Debug(true)));
+        assert_eq!(UciCommand::parse("debug off"), Ok(UciCommand::Debug(false)));
+    }
+
+    #[test]
+    fn parses_position_startpos() {
+        assert_eq!(
+            UciCommand::parse("position startpos"),
+            Ok(UciCommand::Position { fen: None, moves: Vec::new() })
+        );
+
+        assert_eq!(
+            UciCommand::parse("position startpos moves e2e4 e7e5 g1f3"),
+            Ok(UciCommand::Position {
+                fen: None,
+                moves: vec!["e2e4".to_string(), "e7e5".to_string(), "g1f3".to_string()],
+            })
+        );
+    }
+
+    #[test]
+    fn parses_position_fen() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        assert_eq!(
+            UciCommand::parse(&format!("position fen {fen}")),
+            Ok(UciCommand::Position { fen: Some(fen.to_string()), moves: Vec::new() })
+        );
+
+        assert_eq!(
+            UciCommand::parse(&format!("position   fen {fen}   moves e1g1 a6e2")),
+            Ok(UciCommand::Position {
+                fen: Some(fen.to_string()),
+                moves: vec!["e1g1".to_string(), "a6e2".to_string()],
+            })
+        );
+
+        assert_eq!(
+            UciCommand::parse("position startpos moves"),
+            Ok(UciCommand::Position { fen: None, moves: Vec::new() })
+        );
+    }
+
+    #[test]
+    fn parses_go_with_every_limit() {
+        let go_command = go("go wtime 1000 btime 2000 winc 10 binc 20 movestogo 30 depth 12 nodes 50000 mate 3 movetime 700");
+        assert_eq!(
+            go_command,
+            GoCommand {
+                wtime: Some(1000),
+                btime: Some(2000),
+                winc: Some(10),
+                binc: Some(20),
+                moves_to_go: Some(30),
+                depth: Some(12),
+                nodes: Some(50000),
+                mate: Some(3),
+                move_time: Some(700),
+                ..GoCommand::default()
+            }
+        );
+    }
+
+    #[test]
+    fn parses_go_flags_and_searchmoves() {
+        assert!(go("go infinite").infinite);
+        assert!(go("go").infinite);
+
+        let go_command = go("go ponder wtime 100 btime 100");
+        assert!(go_command.ponder);
+        assert!(!go_command.infinite);
+        assert_eq!(go_command.wtime, Some(100));
+
+        let go_command = go("go searchmoves e2e4 d2d4 depth 5");
+        assert_eq!(go_command.search_moves, vec!["e2e4".to_string(), "d2d4".to_string()]);
+        assert_eq!(go_command.depth, Some(5));
+
+        let go_command = go("go infinite searchmoves g1f3");
+        assert!(go_command.infinite);
+        assert_eq!(go_command.search_moves, vec!["g1f3".to_string()]);
+    }
+
+    #[test]
+    fn clamps_negative_time() {
+        assert_eq!(go("go wtime -50 btime 100").wtime, Some(0));
+    }
+
+    #[test]
+    fn parses_setoption_with_spaces() {
+        assert_eq!(
+            UciCommand::parse("setoption name Move Overhead value 100"),
+            Ok(UciCommand::SetOption { name: "Move Overhead".to_string(), value: "100".to_string() })
+        );
+
+        assert_eq!(
+            UciCommand::parse("setoption name SyzygyPath value /tables/my tables  "),
+            Ok(UciCommand::SetOption { name: "SyzygyPath".to_string(), value: "/tables/my tables".to_string() })
+        );
+
+        assert_eq!(
+            UciCommand::parse("setoption name Clear Hash"),
+            Ok(UciCommand::SetOption { name: "Clear Hash".to_string(), value: String::new() })
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert_eq!(UciCommand::parse(""), Err(UciParseError::Empty));
+        assert_eq!(UciCommand::parse("   "), Err(UciParseError::Empty));
+        assert_eq!(UciCommand::parse("hello"), Err(UciParseError::UnknownCommand("hello".to_string())));
+
+        assert!(matches!(UciCommand::parse("debug maybe"), Err(UciParseError::InvalidValue { .. })));
+        assert!(matches!(UciCommand::parse("debug"), Err(UciParseError::MissingArgument { .. })));
+
+        assert!(matches!(UciCommand::parse("setoption"), Err(UciParseError::MissingArgument { .. })));
+        assert!(matches!(UciCommand::parse("setoption value 1"), Err(UciParseError::UnexpectedToken { .. })));
+        assert!(matches!(UciCommand::parse("setoption name"), Err(UciParseError::MissingArgument { .. })));
+        assert!(matches!(UciCommand::parse("setoption name Hash value"), Err(UciParseError::MissingArgument { .. })));
+
+        assert!(matches!(UciCommand::parse("position"), Err(UciParseError::MissingArgument { .. })));
+        assert!(matches!(UciCommand::parse("position fen"), Err(UciParseError::MissingArgument { .. })));
+        assert!(matches!(UciCommand::parse("position startpos e2e4"), Err(UciParseError::UnexpectedToken { .. })));
+        assert!(matches!(UciCommand::parse("position somewhere"), Err(UciParseError::UnexpectedToken { .. })));
+
+        assert!(matches!(UciCommand::parse("go depth"), Err(UciParseError::InvalidValue { .. })));
+        assert!(matches!(UciCommand::parse("go nodes many"), Err(UciParseError::InvalidValue { .. })));
+        assert!(matches!(UciCommand::parse("go mate -1"), Err(UciParseError::InvalidValue { .. })));
+        assert!(matches!(UciCommand::parse("go wtime 100 fast"), Err(UciParseError::UnexpectedToken { .. })));
+    }
+}
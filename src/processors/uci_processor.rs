@@ -1,23 +1,40 @@
 use spear::{ChessPosition, Move, Side, FEN};
 
-use crate::search::{SearchEngine, SearchLimits};
+use crate::{
+    search::{Engine, SearchLimits},
+    Chess960,
+};
+
+use super::{GoCommand, UciCommand, UciParseError};
 
 pub struct UciProcessor;
 impl UciProcessor {
     //Handles commands from UCI protocol
-    pub fn execute(command: &str, args: &[String], search_engine: &mut SearchEngine) {
+    pub fn execute(input_command: &str, search_engine: &mut Engine) {
+        let command = match UciCommand::parse(input_command) {
+            Ok(command) => command,
+            Err(UciParseError::Empty) => return,
+            Err(error) => {
+                println!("info string {}", error);
+                return;
+            }
+        };
+
         match command {
-            "uci" => Self::uci_message(search_engine),
-            "isready" => println!("readyok"),
-            "setoption" => Self::set_option(args, search_engine),
-            "position" => Self::position(args, search_engine),
-            "ucinewgame" => search_engine.reset(),
-            "go" => Self::go(args, search_engine),
-            _ => (),
+            UciCommand::Uci => Self::uci_message(search_engine),
+            UciCommand::IsReady => println!("readyok"),
+            UciCommand::SetOption { name, value } => search_engine.set_option(&name, &value),
+            UciCommand::Position { fen, moves } => Self::position(fen, &moves, search_engine),
+            UciCommand::UciNewGame => search_engine.reset(),
+            UciCommand::Go(go_command) => Self::go(&go_command, search_engine),
+            UciCommand::Stop => search_engine.stop(),
+            UciCommand::PonderHit => search_engine.ponder_hit(),
+            UciCommand::Quit => std::process::exit(0),
+            UciCommand::Debug(_) => (),
         }
     }
 
-    fn uci_message(search_engine: &mut SearchEngine) {
+    fn uci_message(search_engine: &mut Engine) {
         println!("id name Jackal v{}", env!("CARGO_PKG_VERSION"));
         println!("id author Tomasz Jaworski");
         search_engine.engine_options().print();
@@ -26,152 +43,125 @@ impl UciProcessor {
         search_engine.init_uci()
     }
 
-    fn set_option(args: &[String], search_engine: &mut SearchEngine) {
-        //Checks if command was initially correct
-        if args.len() != 4 || args[0] != "name" || args[2] != "value" {
+    fn position(fen: Option<String>, moves: &[String], search_engine: &mut Engine) {
+        let fen = fen.unwrap_or_else(|| FEN::start_position().to_string());
+
+        //Shredder-FEN and X-FEN castling rights are converted into standard form
+        let (fen, castling_files) = Chess960::normalize_castling_rights(&fen);
+        if !FEN::validate_fen(&fen) {
+            println!("info string Invalid fen: {}", fen);
             return;
         }
 
-        let command = args[1].as_str();
-        let new_value = args[3].as_str();
+        //Castling moves are matched using rooks of the new position
+        let previous_castling_files = Chess960::castling_files();
+        Chess960::set_castling_files(castling_files);
+
+        //Prepare chess position based on parsed fen and map the moves into it. Whole move list has
+        //to be legal, otherwise we keep the previous position untouched
+        let chess960 = search_engine.engine_options().chess960();
+        let mut chess_position = ChessPosition::from_fen(&FEN::from_string(fen.clone()));
+        let mut legal_moves = Vec::with_capacity(moves.len());
+        for (index, mv) in moves.iter().enumerate() {
+            let Some(legal_mv) = Self::find_legal_move(mv, &chess_position, chess960) else {
+                println!("info string Illegal move {} at index {}", mv, index);
+                Chess960::set_castling_files(previous_castling_files);
+                return;
+            };
 
-        //Tries to execute the set option command
-        search_engine.engine_options_mut().set(command, new_value);
+            if chess_position.board().side_to_move() == Side::WHITE {
+                chess_position.make_move::<true, false>(legal_mv)
+            } else {
+                chess_position.make_move::<false, true>(legal_mv)
+            }
 
-        if command == "Hash" {
-            let hash_size = search_engine.engine_options().hash();
-            let hash_percentage = search_engine.engine_options().hash_percentage() / 10.0;
-            search_engine.tree_mut().resize_tree(hash_size, hash_percentage)
+            legal_moves.push(legal_mv);
         }
+
+        //Set new game, game ply is counted from fullmove counter of the fen
+        search_engine.set_game(&fen, &legal_moves)
     }
 
-    fn position(args: &[String], search_engine: &mut SearchEngine) {
-        let mut move_flag = false;
-        let mut fen_flag = false;
-        let mut fen = String::new();
-        let mut moves = Vec::new();
-
-        //Parse the command arguments into fen and move list
-        for arg in args {
-            match arg.as_str() {
-                "startpos" => fen = FEN::start_position().to_string(),
-                "fen" => {
-                    fen_flag = true;
-                    move_flag = false;
-                }
-                "moves" => {
-                    fen_flag = false;
-                    move_flag = true;
-                }
-                _ => {
-                    if fen_flag {
-                        fen.push_str(&format!("{arg} "))
-                    }
-
-                    if move_flag {
-                        moves.push(arg)
-                    }
-                }
-            }
+    fn go(go_command: &GoCommand, search_engine: &mut Engine) {
+        let mut search_limits = SearchLimits::new(search_engine.game_ply());
+
+        let position = search_engine.current_position();
+        let chess960 = search_engine.engine_options().chess960();
+
+        //Convert command into search parameters
+        let (time, increment) = if position.board().side_to_move() == Side::WHITE {
+            (go_command.wtime, go_command.winc)
+        } else {
+            (go_command.btime, go_command.binc)
+        };
+
+        if let Some(time) = time {
+            search_limits.add_time_remaining(time)
         }
 
-        //Prepare method that will map move strings from move list into actual confirmed legal moves
-        fn map_move<const STM_WHITE: bool, const NSTM_WHITE: bool>(
-            mv: &String,
-            chess_position: &mut ChessPosition,
-        ) {
-            let mut _mv = Move::NULL;
-            chess_position
-                .board()
-                .map_moves::<_, STM_WHITE, NSTM_WHITE>(|legal_mv| {
-                    if *mv == legal_mv.to_string() {
-                        _mv = legal_mv;
-                    }
-                });
-            chess_position.make_move::<STM_WHITE, NSTM_WHITE>(_mv);
+        if let Some(increment) = increment {
+            search_limits.add_increment(increment)
         }
 
-        if !FEN::validate_fen(&fen) {
-            return;
+        if let Some(moves_to_go) = go_command.moves_to_go {
+            search_limits.add_moves_to_go(moves_to_go)
         }
 
-        //Prepare chess position based on parsed fen and map the moves into it
-        let mut chess_position = ChessPosition::from_fen(&FEN::from_string(fen));
-        for mv in moves {
-            if chess_position.board().side_to_move() == Side::WHITE {
-                map_move::<true, false>(mv, &mut chess_position)
-            } else {
-                map_move::<false, true>(mv, &mut chess_position)
-            }
+        if let Some(move_time) = go_command.move_time {
+            search_limits.add_move_time(move_time)
         }
 
-        //Set new position
-        search_engine.replace_position(chess_position)
-    }
+        if let Some(depth) = go_command.depth {
+            search_limits.add_depth(depth)
+        }
 
-    fn go(args: &[String], search_engine: &mut SearchEngine) {
-        let mut search_limits = SearchLimits::new(search_engine.game_ply());
+        if let Some(nodes) = go_command.nodes {
+            search_limits.add_iters(nodes)
+        }
 
-        let parse_u64 = |str: &str| -> u64 { str.parse::<u64>().unwrap_or(0).max(0) };
-        let parse_u32 = |str: &str| -> u32 { str.parse::<u32>().unwrap_or(0).max(0) };
+        if let Some(mate) = go_command.mate {
+            search_limits.add_mate(mate)
+        }
 
-        //Convert args into search parameters
-        if args.is_empty() {
+        for mv in &go_command.search_moves {
+            if let Some(mv) = Self::find_legal_move(mv, &position, chess960) {
+                search_limits.add_search_move(mv)
+            }
+        }
+
+        if go_command.infinite {
             search_limits.go_infinite()
-        } else {
-            let mut search_param = "";
-            let param_list = [
-                "wtime",
-                "btime",
-                "winc",
-                "binc",
-                "movestogo",
-                "depth",
-                "nodes",
-                "infinite",
-                "movetime",
-            ];
-            let side_to_move = search_engine.current_position().board().side_to_move();
-            for arg in args {
-                if param_list.contains(&arg.as_str()) {
-                    search_param = arg;
-                    if search_param == "infinite" {
-                        search_limits.go_infinite()
-                    }
-                    continue;
-                }
+        }
 
-                match search_param {
-                    "wtime" => {
-                        if side_to_move == Side::WHITE {
-                            search_limits.add_time_remaining(parse_u64(arg))
-                        }
-                    }
-                    "btime" => {
-                        if side_to_move == Side::BLACK {
-                            search_limits.add_time_remaining(parse_u64(arg))
-                        }
-                    }
-                    "winc" => {
-                        if side_to_move == Side::WHITE {
-                            search_limits.add_increment(parse_u64(arg))
-                        }
-                    }
-                    "binc" => {
-                        if side_to_move == Side::BLACK {
-                            search_limits.add_increment(parse_u64(arg))
-                        }
-                    }
-                    "movestogo" => search_limits.add_moves_to_go(parse_u32(arg)),
-                    "movetime" => search_limits.add_move_time(parse_u64(arg)),
-                    "depth" => search_limits.add_depth(parse_u32(arg)),
-                    "nodes" => search_limits.add_iters(parse_u32(arg)),
-                    _ => continue,
-                }
-            }
+        if go_command.ponder {
+            search_limits.go_ponder()
         }
 
         search_limits.calculate_time_limits();
-        search_engine.search(&search_limits, true);
+        search_engine.start_search(search_limits);
+    }
+
+    //Finds legal move in the position, that matches provided move string
+    pub(super) fn find_legal_move(mv: &str, chess_position: &ChessPosition, chess960: bool) -> Option<Move> {
+        fn find<const STM_WHITE: bool, const NSTM_WHITE: bool>(
+            mv: &str,
+            chess_position: &ChessPosition,
+            chess960: bool,
+        ) -> Option<Move> {
+            let mut result = None;
+            let board = chess_position.board();
+            board.map_moves::<_, STM_WHITE, NSTM_WHITE>(|legal_mv| {
+                if mv == Chess960::move_to_string(legal_mv, board, chess960) {
+                    result = Some(legal_mv);
+                }
+            });
+            result
+        }
+
+        if chess_position.board().side_to_move() == Side::WHITE {
+            find::<true, false>(mv, chess_position, chess960)
+        } else {
+            find::<false, true>(mv, chess_position, chess960)
+        }
     }
 }
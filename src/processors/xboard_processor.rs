@@ -0,0 +1,230 @@
+use spear::{Move, Side, FEN};
+
+use crate::{
+    search::{Engine, SearchLimits},
+    CastlingFiles, Chess960,
+};
+
+use super::UciProcessor;
+
+pub struct XboardProcessor {
+    force: bool,
+    engine_side: Side,
+    post: bool,
+    analyzing: bool,
+    thinking: bool,
+    moves_per_session: u32,
+    base_time: u64,
+    increment: u64,
+    move_time: Option<u64>,
+    time_remaining: Option<u64>,
+}
+
+impl Default for XboardProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl XboardProcessor {
+    pub fn new() -> Self {
+        Self {
+            force: false,
+            engine_side: Side::BLACK,
+            post: false,
+            analyzing: false,
+            thinking: false,
+            moves_per_session: 0,
+            base_time: 0,
+            increment: 0,
+            move_time: None,
+            time_remaining: None,
+        }
+    }
+
+    //Handles commands from XBoard protocol and returns true, if command was found
+    pub fn execute(&mut self, command: &str, args: &[String], search_engine: &mut Engine) -> bool {
+        //Only commands changing the position or the game end the current search, so they are
+        //processed on the final position. Other commands are handled while the engine thinks
+        let interrupts_search = matches!(
+            command,
+            "usermove" | "force" | "result" | "new" | "setboard" | "undo" | "remove" | "quit" | "?" | "exit"
+        );
+        if interrupts_search {
+            self.finish_search(search_engine);
+        }
+
+        match command {
+            "xboard" => search_engine.init_xboard(),
+            "protover" => Self::features(),
+            "new" => self.new_game(search_engine),
+            "setboard" => self.set_board(args, search_engine),
+            "usermove" => self.user_move(args, search_engine),
+            "go" => {
+                self.force = false;
+                self.engine_side = search_engine.current_position().board().side_to_move();
+                self.think(search_engine)
+            }
+            "force" | "result" => self.force = true,
+            "level" => self.level(args),
+            "st" => self.move_time = args.first().and_then(|arg| arg.parse::<u64>().ok()).map(|st| st * 1000),
+            "time" => self.time_remaining = args.first().and_then(|arg| arg.parse::<i64>().ok()).map(|time| time.max(0) as u64 * 10),
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+            "analyze" => self.analyzing = true,
+            "exit" => self.analyzing = false,
+            "undo" => self.undo(1, search_engine),
+            "remove" => self.undo(2, search_engine),
+            "ping" => self.ping(args, search_engine),
+            "quit" => std::process::exit(0),
+            "otim" | "accepted" | "rejected" | "random" | "hard" | "easy" | "computer" | "name" | "rating"
+            | "draw" | "variant" | "?" => (),
+            _ => return false,
+        }
+
+        //Analysis is restarted after every command that ended it, until 'exit' is received
+        if self.analyzing && (interrupts_search || command == "analyze") {
+            self.analyze(search_engine)
+        }
+
+        true
+    }
+
+    fn features() {
+        println!(
+            "feature myname=\"Jackal v{}\" usermove=1 setboard=1 ping=1 analyze=1 sigint=0 sigterm=0 colors=0 done=1",
+            env!("CARGO_PKG_VERSION")
+        )
+    }
+
+    fn new_game(&mut self, search_engine: &mut Engine) {
+        search_engine.reset();
+        Chess960::set_castling_files(CastlingFiles::default());
+        self.force = false;
+        self.engine_side = Side::BLACK;
+        self.move_time = None;
+        self.time_remaining = None;
+    }
+
+    fn set_board(&mut self, args: &[String], search_engine: &mut Engine) {
+        let (fen, castling_files) = Chess960::normalize_castling_rights(&args.join(" "));
+        if !FEN::validate_fen(&fen) {
+            println!("tellusererror Illegal position");
+            return;
+        }
+
+        Chess960::set_castling_files(castling_files);
+
+        search_engine.set_game(&fen, &[]);
+    }
+
+    fn user_move(&mut self, args: &[String], search_engine: &mut Engine) {
+        let Some(mv) = args.first() else {
+            return;
+        };
+
+        let position = search_engine.current_position();
+        let chess960 = search_engine.engine_options().chess960();
+        let Some(legal_mv) = UciProcessor::find_legal_move(mv, &position, chess960) else {
+            println!("Illegal move: {}", mv);
+            return;
+        };
+
+        search_engine.make_move(legal_mv);
+
+        if !self.force
+            && !self.analyzing
+            && search_engine.current_position().board().side_to_move() == self.engine_side
+        {
+            self.think(search_engine)
+        }
+    }
+
+    //level MPS BASE INC, where base is given in minutes or minutes:seconds and increment in seconds
+    fn level(&mut self, args: &[String]) {
+        if args.len() < 3 {
+            return;
+        }
+
+        let base_time = match args[1].split_once(':') {
+            Some((minutes, seconds)) => minutes.parse::<u64>().ok().zip(seconds.parse::<u64>().ok()).map(|(m, s)| m * 60 + s),
+            None => args[1].parse::<u64>().ok().map(|minutes| minutes * 60),
+        };
+
+        self.moves_per_session = args[0].parse::<u32>().unwrap_or(0);
+        self.base_time = base_time.unwrap_or(0) * 1000;
+        self.increment = (args[2].parse::<f64>().unwrap_or(0.0) * 1000.0) as u64;
+        self.move_time = None;
+    }
+
+    //Ping received while thinking is answered only after the move is sent, so the search is
+    //allowed to finish on its own first
+    fn ping(&mut self, args: &[String], search_engine: &mut Engine) {
+        if self.thinking {
+            self.complete_search(search_engine);
+        }
+
+        println!("pong {}", args.first().map(|arg| arg.as_str()).unwrap_or_default())
+    }
+
+    fn undo(&mut self, count: usize, search_engine: &mut Engine) {
+        for _ in 0..count {
+            search_engine.undo_move();
+        }
+    }
+
+    fn think(&mut self, search_engine: &mut Engine) {
+        let mut search_limits = SearchLimits::new(search_engine.game_ply());
+
+        if let Some(move_time) = self.move_time {
+            search_limits.add_move_time(move_time)
+        } else {
+            match self.time_remaining {
+                Some(time) => search_limits.add_time_remaining(time),
+                None if self.base_time > 0 => search_limits.add_time_remaining(self.base_time),
+                None => (),
+            }
+            search_limits.add_increment(self.increment);
+
+            //In classical time control moves to go are counted from the fullmove number, so games
+            //started with setboard continue the session of the position
+            if self.moves_per_session > 0 {
+                let moves_played = search_engine.game_ply() / 2;
+                search_limits.add_moves_to_go(self.moves_per_session - moves_played % self.moves_per_session)
+            }
+        }
+
+        search_limits.calculate_time_limits();
+
+        search_engine.set_print_reports(self.post);
+        search_engine.start_search(search_limits);
+        self.thinking = true;
+    }
+
+    fn analyze(&mut self, search_engine: &mut Engine) {
+        let mut search_limits = SearchLimits::new(search_engine.game_ply());
+        search_limits.go_infinite();
+        search_limits.calculate_time_limits();
+
+        search_engine.set_print_reports(true);
+        search_engine.start_search(search_limits);
+    }
+
+    fn finish_search(&mut self, search_engine: &mut Engine) {
+        search_engine.stop();
+        self.complete_search(search_engine)
+    }
+
+    //Move is sent to the GUI by the search printer, here we only update the game with it
+    fn complete_search(&mut self, search_engine: &mut Engine) {
+        let result = search_engine.wait();
+
+        if !std::mem::take(&mut self.thinking) {
+            return;
+        }
+
+        if let Some(result) = result.filter(|result| result.best_move != Move::NULL) {
+            search_engine.make_move(result.best_move)
+        }
+    }
+}
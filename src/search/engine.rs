@@ -0,0 +1,307 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+use spear::{ChessBoard, ChessPosition, Move};
+
+use crate::{
+    options::{EngineOptions, OptionError},
+    GameRecord, Syzygy,
+};
+
+use super::{
+    print::{JsonPrint, NoPrint, PrettyPrint, UciPrint, XboardPrint},
+    search_limits::SearchLimits,
+    tree::Tree,
+    Mcts, Score, SearchInfo, SearchStats,
+};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Protocol {
+    Console,
+    Uci,
+    Xboard,
+}
+
+#[derive(Clone, Copy)]
+pub struct SearchResult {
+    pub best_move: Move,
+    pub score: Score,
+    pub nodes: u64,
+    pub time: u64,
+}
+
+struct ActiveSearch {
+    handle: JoinHandle<SearchResult>,
+    limits: Arc<SearchLimits>,
+    stats: Arc<SearchStats>,
+}
+
+//Owned engine handle. Search runs on its own thread, so the caller stays responsive and
+//decides itself where the commands come from
+pub struct Engine {
+    game_record: GameRecord,
+    previous_board: ChessBoard,
+    interruption_token: Arc<AtomicBool>,
+    tree: Arc<Tree>,
+    options: Arc<EngineOptions>,
+    progress_sender: Option<Sender<SearchInfo>>,
+    active_search: Option<ActiveSearch>,
+    protocol: Protocol,
+    print_reports: bool,
+    json_output: bool,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        self.stop();
+        self.wait();
+    }
+}
+
+impl Engine {
+    pub fn new() -> Self {
+        let game_record = GameRecord::default();
+        let options = EngineOptions::new();
+        let tree = Tree::new(options.hash(), options.hash_percentage() / 10.0);
+
+        Self {
+            previous_board: *game_record.position().board(),
+            game_record,
+            interruption_token: Arc::new(AtomicBool::new(false)),
+            tree: Arc::new(tree),
+            options: Arc::new(options),
+            progress_sender: None,
+            active_search: None,
+            protocol: Protocol::Console,
+            print_reports: false,
+            json_output: false,
+        }
+    }
+
+    pub fn init_uci(&mut self) {
+        self.protocol = Protocol::Uci;
+    }
+
+    pub fn init_xboard(&mut self) {
+        self.protocol = Protocol::Xboard;
+    }
+
+    pub fn protocol_initialized(&self) -> bool {
+        self.protocol != Protocol::Console
+    }
+
+    pub fn xboard_initialized(&self) -> bool {
+        self.protocol == Protocol::Xboard
+    }
+
+    //Enables printing search reports of the current protocol to stdout
+    pub fn set_print_reports(&mut self, print_reports: bool) {
+        self.print_reports = print_reports
+    }
+
+    //Replaces protocol search reports with JSON objects, one per search update
+    pub fn set_json_output(&mut self, json_output: bool) {
+        self.json_output = json_output
+    }
+
+    pub fn engine_options(&self) -> &EngineOptions {
+        &self.options
+    }
+
+    //Running search keeps the options it was started with, changes apply to the next search
+    pub fn engine_options_mut(&mut self) -> &mut EngineOptions {
+        Arc::make_mut(&mut self.options)
+    }
+
+    pub fn set_option(&mut self, name: &str, value: &str) {
+        if let Err(error) = self.try_set_option(name, value) {
+            println!("{}", error);
+        }
+    }
+
+    pub fn try_set_option(&mut self, name: &str, value: &str) -> Result<(), OptionError> {
+        //Side effects depend on the declared option name, so "syzygy path" triggers them as well
+        match self.engine_options_mut().try_set(name, value)? {
+            "Hash" => {
+                let hash_size = self.engine_options().hash();
+                let hash_percentage = self.engine_options().hash_percentage() / 10.0;
+                self.tree_mut().resize_tree(hash_size, hash_percentage)
+            }
+            "SyzygyPath" => {
+                let tables = Syzygy::init(&self.engine_options().syzygy_path());
+                println!("info string Found {} tablebases", tables);
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    //Replaces the game with the one played from the fen, moves have to be legal. Running search
+    //is finished first, so its result can be recorded with the move it has chosen
+    pub fn set_game(&mut self, fen: &str, moves: &[Move]) {
+        self.stop();
+        self.wait();
+        self.game_record = self.game_record.with_moves(fen, moves)
+    }
+
+    pub fn make_move(&mut self, mv: Move) {
+        self.stop();
+        self.wait();
+        self.game_record.push(mv)
+    }
+
+    //Takes back the last move and returns false, if there was no move to take back
+    pub fn undo_move(&mut self) -> bool {
+        self.stop();
+        self.wait();
+        self.game_record.pop()
+    }
+
+    pub fn game_record(&self) -> &GameRecord {
+        &self.game_record
+    }
+
+    pub fn current_position(&self) -> ChessPosition {
+        self.game_record.position()
+    }
+
+    pub fn game_ply(&self) -> u32 {
+        self.game_record.ply()
+    }
+
+    pub fn tree(&self) -> &Tree {
+        &self.tree
+    }
+
+    //Tree can only be modified when no search is using it, so running search is stopped first
+    pub fn tree_mut(&mut self) -> &mut Tree {
+        self.stop();
+        self.wait();
+        Arc::get_mut(&mut self.tree).expect("Tree is still used by the search")
+    }
+
+    pub fn reset(&mut self) {
+        self.tree_mut().clear();
+        self.game_record = GameRecord::default();
+    }
+
+    //Returns receiver of search progress updates, replaces previous subscriber
+    pub fn subscribe_progress(&mut self) -> Receiver<SearchInfo> {
+        let (sender, receiver) = mpsc::channel();
+        self.progress_sender = Some(sender);
+        receiver
+    }
+
+    //Starts the search in the background and returns immediately, previous search is stopped
+    pub fn start_search(&mut self, search_limits: SearchLimits) {
+        let current_board = *self.current_position().board();
+        let previous_board = self.previous_board;
+        let tree_reuse = self.tree_mut().reuse_tree(&previous_board, &current_board);
+        self.previous_board = current_board;
+
+        if self.print_reports && self.protocol == Protocol::Uci && !self.json_output {
+            match (tree_reuse.found, tree_reuse.depth) {
+                (true, Some(depth)) => println!("info string tree reuse hit depth {} visits {}", depth, tree_reuse.visits),
+                (true, None) => println!("info string tree reuse hit detached visits {}", tree_reuse.visits),
+                (false, _) => println!("info string tree reuse miss"),
+            }
+        }
+        self.interruption_token.store(false, Ordering::Relaxed);
+
+        let position = self.current_position();
+        let tree = Arc::clone(&self.tree);
+        let options = Arc::clone(&self.options);
+        let interruption_token = Arc::clone(&self.interruption_token);
+        let progress_sender = self.progress_sender.clone();
+        let limits = Arc::new(search_limits);
+        let stats = Arc::new(SearchStats::new());
+        let protocol = self.protocol;
+        let print_reports = self.print_reports;
+        let json_output = self.json_output;
+
+        let search_limits = Arc::clone(&limits);
+        let search_stats = Arc::clone(&stats);
+        let handle = thread::spawn(move || {
+            let mut mcts = Mcts::new(position, &tree, &interruption_token, &options, &search_stats, &search_limits);
+            if let Some(sender) = &progress_sender {
+                mcts = mcts.with_progress(sender);
+            }
+
+            //XBoard printer is always used, because it's responsible for sending the move,
+            //thinking output is controlled by 'post' command
+            let (best_move, score) = match (protocol, print_reports) {
+                (_, true) if json_output => mcts.search::<JsonPrint>(),
+                (Protocol::Xboard, true) => mcts.search::<XboardPrint<true>>(),
+                (Protocol::Xboard, false) => mcts.search::<XboardPrint<false>>(),
+                (Protocol::Uci, true) => mcts.search::<UciPrint>(),
+                (Protocol::Console, true) => mcts.search::<PrettyPrint>(),
+                (_, false) => mcts.search::<NoPrint>(),
+            };
+
+            SearchResult {
+                best_move,
+                score,
+                nodes: search_stats.iters(),
+                time: search_stats.time_passed(),
+            }
+        });
+
+        self.active_search = Some(ActiveSearch { handle, limits, stats });
+    }
+
+    pub fn is_searching(&self) -> bool {
+        self.active_search
+            .as_ref()
+            .is_some_and(|search| !search.handle.is_finished())
+    }
+
+    //Infinite and pondering searches don't end on their own, they have to be stopped
+    pub fn is_search_unlimited(&self) -> bool {
+        self.active_search
+            .as_ref()
+            .is_some_and(|search| search.limits.is_infinite() || search.limits.is_pondering())
+    }
+
+    //Requests the search to finish, result can be obtained with 'wait'
+    pub fn stop(&self) {
+        if self.active_search.is_some() {
+            self.interruption_token.store(true, Ordering::Relaxed)
+        }
+    }
+
+    //Switches pondering search into normal search, counting the time from now on
+    pub fn ponder_hit(&self) {
+        if let Some(search) = &self.active_search {
+            search.stats.update_time_passed();
+            search.limits.ponder_hit(search.stats.time_passed())
+        }
+    }
+
+    //Blocks until the search finishes and returns its result, None if there was no search started.
+    //Result is also added to the game record
+    pub fn wait(&mut self) -> Option<SearchResult> {
+        let result = self
+            .active_search
+            .take()
+            .map(|search| search.handle.join().expect("Search thread panicked"))?;
+
+        if result.best_move != Move::NULL {
+            self.game_record.add_search_result(result)
+        }
+
+        Some(result)
+    }
+}
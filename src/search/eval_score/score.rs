@@ -42,6 +42,11 @@ impl Score {
         self.as_cp() as f32 / 100.0
     }
 
+    //Moves the score towards the other one, weight 1.0 returns the other score
+    pub fn blend(&self, other: Score, weight: f32) -> Self {
+        Self(self.0 + (other.0 - self.0) * weight, self.1 + (other.1 - self.1) * weight)
+    }
+
     pub fn reversed(&self) -> Self {
         Self(self.lose_chance(), self.1)
     }
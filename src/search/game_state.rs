@@ -7,6 +7,24 @@ pub enum GameState {
     Won(u8),
 }
 
+impl GameState {
+    //Tablebase results don't know the distance to mate, so they are stored as the longest mates.
+    //Proven mates are always preferred over them and mate scores are not reported for them
+    pub const TABLEBASE_PLIES: u8 = 192;
+
+    pub fn is_tablebase_result(&self) -> bool {
+        matches!(self, Self::Won(x) | Self::Lost(x) if *x >= Self::TABLEBASE_PLIES)
+    }
+
+    //Plies to mate of proven wins and losses. Tablebase results are not mates, so they have none
+    pub fn mate_plies(&self) -> Option<u8> {
+        match self {
+            Self::Won(x) | Self::Lost(x) if !self.is_tablebase_result() => Some(*x),
+            _ => None,
+        }
+    }
+}
+
 impl From<GameState> for u16 {
     fn from(value: GameState) -> Self {
         match value {
@@ -31,3 +49,26 @@ impl From<u16> for GameState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tablebase_results_have_no_mate_distance() {
+        assert_eq!(GameState::Won(5).mate_plies(), Some(5));
+        assert_eq!(GameState::Lost(0).mate_plies(), Some(0));
+        assert_eq!(GameState::Won(GameState::TABLEBASE_PLIES).mate_plies(), None);
+        assert_eq!(GameState::Won(193).mate_plies(), None);
+        assert_eq!(GameState::Lost(194).mate_plies(), None);
+        assert_eq!(GameState::Drawn.mate_plies(), None);
+        assert_eq!(GameState::Unresolved.mate_plies(), None);
+    }
+
+    #[test]
+    fn state_survives_encoding() {
+        for state in [GameState::Unresolved, GameState::Drawn, GameState::Won(193), GameState::Lost(0), GameState::Lost(255)] {
+            assert!(GameState::from(u16::from(state)) == state);
+        }
+    }
+}
@@ -1,10 +1,10 @@
 use crate::{
     options::EngineOptions,
-    search::{print::SearchDisplay, Score},
-    SearchLimits, SearchStats, Tree,
+    search::{print::SearchDisplay, Score, SearchInfo, StrengthLimit},
+    GameState, SearchLimits, SearchStats, Syzygy, Tree,
 };
 use spear::{ChessPosition, Move, Piece, Side};
-use std::sync::atomic::AtomicBool;
+use std::sync::{atomic::AtomicBool, mpsc::Sender};
 
 pub struct Mcts<'a> {
     pub(super) root_position: ChessPosition,
@@ -13,7 +13,9 @@ pub struct Mcts<'a> {
     pub(super) options: &'a EngineOptions,
     pub(super) stats: &'a SearchStats,
     pub(super) limits: &'a SearchLimits,
-    pub(super) start_material: i32
+    pub(super) progress: Option<&'a Sender<SearchInfo>>,
+    pub(super) start_material: i32,
+    pub(super) moves_left: u32
 }
 
 impl<'a> Mcts<'a> {
@@ -32,30 +34,60 @@ impl<'a> Mcts<'a> {
             options,
             stats,
             limits,
-            start_material: Self::calculate_stm_material(&root_position, root_position.board().side_to_move())
+            progress: None,
+            start_material: Self::calculate_stm_material(&root_position, root_position.board().side_to_move()),
+            moves_left: SearchInfo::estimate_moves_left(&root_position)
         }
     }
 
+    //Sends search progress to the channel every time a search report is drawn
+    pub fn with_progress(mut self, sender: &'a Sender<SearchInfo>) -> Self {
+        self.progress = Some(sender);
+        self
+    }
+
     pub fn search<PRINTER: SearchDisplay>(&self) -> (Move, Score) {
         let mut printer = PRINTER::new(&self.root_position, self.options, &self.tree);
+        self.tree.hash_table().new_search();
 
         //Check if root node is expanded, and if not then expand it
         let root_index = self.tree.root_index();
         let side_to_move = self.root_position.board().side_to_move();
+        let mut search_moves = self.limits.search_moves().to_vec();
+
+        //In tablebase positions only moves keeping the best result are searched, so won endings
+        //are converted before the 50 move rule
+        if let Some(tablebase_moves) = Syzygy::root_moves(&self.root_position, &search_moves) {
+            search_moves = tablebase_moves;
+        }
+
+        let search_moves = search_moves.as_slice();
         if !self.tree[root_index].has_children() {
             if side_to_move == Side::WHITE {
-                self.tree[root_index].expand::<true, false, true>(&self.root_position, self.options)
+                self.tree[root_index].expand::<true, false, true>(&self.root_position, self.options, search_moves)
             } else {
-                self.tree[root_index].expand::<false, true, true>(&self.root_position, self.options)
+                self.tree[root_index].expand::<false, true, true>(&self.root_position, self.options, search_moves)
             }
         } else {
-            if side_to_move == Side::WHITE {
-                self.tree[root_index].recalculate_policy::<true, false, true>(&self.root_position, self.options)
+            let actions_changed = if side_to_move == Side::WHITE {
+                self.tree[root_index].recalculate_policy::<true, false, true>(&self.root_position, self.options, search_moves)
             } else {
-                self.tree[root_index].recalculate_policy::<false, true, true>(&self.root_position, self.options)
+                self.tree[root_index].recalculate_policy::<false, true, true>(&self.root_position, self.options, search_moves)
+            };
+
+            //Statistics of the moves removed by searchmoves are dropped from the root too
+            if actions_changed {
+                self.tree.rebuild_root_edge();
             }
         }
 
+        //Proven state of the root depends on the searched moves, a restricted move set can lose or draw
+        //a position that isn't lost, so the root is proven again with the current moves. Root without
+        //moves is mate or stalemate and keeps its state
+        if self.tree[root_index].has_children() {
+            self.tree[root_index].set_state(GameState::Unresolved);
+        }
+
         //Start mcts search loop
         if self.root_position.board().side_to_move() == Side::WHITE {
             self.search_loop::<PRINTER, true, false>(&mut printer)
@@ -66,19 +98,40 @@ impl<'a> Mcts<'a> {
         //At the end of the search print the last search update raport and then print
         //end of search message containing search result
         let draw_contempt = self.options.draw_contempt();
-        let (best_move, best_score) = self.tree[self.tree.root_index()].get_best_move(self.tree, draw_contempt);
+        let (mut best_move, mut best_score) = self.tree[self.tree.root_index()].get_best_move(self.tree, draw_contempt);
+
+        //When strength is limited, we pick the move by sampling root visit distribution
+        if let Some(strength_limit) = StrengthLimit::from_options(self.options) {
+            let actions = self.tree[self.tree.root_index()].actions();
+            //Deterministic search samples with the seed taken from the position
+            let seed = self.options.deterministic().then(|| self.root_position.board().get_key().get_raw());
+            if let Some(action_index) = strength_limit.sample_action(&actions, seed) {
+                best_move = actions[action_index].mv();
+                best_score = actions[action_index].score();
+            }
+        }
+
         self.stats.update_time_passed();
-        printer.print_search_raport::<true>(
-            self.stats,
-            self.options,
-            self.limits,
-            self.tree.total_usage(),
-            &self.tree.get_pvs(self.options.multi_pv(), draw_contempt)
-        );
-        printer.print_search_result(best_move, best_score);
+        let search_info = SearchInfo::new(self.stats, self.tree, self.options, self.moves_left);
+        printer.print_search_raport::<true>(&search_info, self.limits);
+        self.send_progress(&search_info);
+
+        //Ponder move is the expected reply from the principal variation of the best move
+        let ponder_move = match search_info.pvs[0].moves.as_slice() {
+            [mv, ponder_move, ..] if *mv == best_move => *ponder_move,
+            _ => Move::NULL,
+        };
+        printer.print_search_result(best_move, ponder_move, best_score);
         (best_move, best_score)
     }
 
+    pub(super) fn send_progress(&self, search_info: &SearchInfo) {
+        if let Some(sender) = self.progress {
+            //Receiver could be dropped by the caller, it shouldn't affect the search
+            let _ = sender.send(search_info.clone());
+        }
+    }
+
     pub(super) fn calculate_stm_material(position: &ChessPosition, side: Side) -> i32 {
         const PIECE_VALUES: [i32; 5] = [100, 300, 300, 500, 900];
         let mut result = 0;
@@ -0,0 +1,389 @@
+use std::sync::{Condvar, Mutex, MutexGuard};
+
+use spear::ChessPosition;
+
+use crate::{
+    search::{tree::Edge, NodeIndex, Score, SearchHelpers},
+    GameState,
+};
+
+use super::Mcts;
+
+//Leaf selected for batched evaluation, path holds (node, action, child) triples from the root down.
+//Repetition leaf has no node, so its node index and the last child are null
+pub(super) struct BatchLeaf {
+    path: Vec<(NodeIndex, usize, NodeIndex)>,
+    node_index: NodeIndex,
+    position: ChessPosition,
+}
+
+impl BatchLeaf {
+    //Repetition leaves have no node, so they are the same only when they are reached by the same edge
+    fn is_same_leaf(&self, other: &Self) -> bool {
+        if self.node_index.is_null() || other.node_index.is_null() {
+            return self.node_index == other.node_index && self.path.last() == other.path.last();
+        }
+
+        self.node_index == other.node_index
+    }
+}
+
+//Scores known before the evaluation. Terminal and transposition scores are exact and don't get
+//material bonus, hash table scores are adjusted like the evaluated ones
+enum KnownScore {
+    Exact(Score),
+    Hashed(Score),
+    Unknown,
+}
+
+//Batch filled with leaves of all threads. Every thread submits positions of its own leaves and waits
+//for their scores. Batch is evaluated by the thread, that fills it up to 'BatchSize' or that submits
+//as the last of the active threads. Threads leave the batch before waiting at the segment barrier,
+//so the others don't wait for their leaves
+pub(super) struct SharedBatch {
+    threads: usize,
+    batch_size: usize,
+    queue: Mutex<BatchQueue>,
+    evaluated: Condvar,
+}
+
+struct BatchQueue {
+    positions: Vec<ChessPosition>,
+    submissions: usize,
+    //Threads, whose leaves all had known scores, they don't wait for the batch
+    empty_submissions: usize,
+    active_threads: usize,
+    batch_id: u64,
+    //Scores of evaluated batches together with the number of threads, that haven't read them yet
+    results: Vec<(u64, Vec<Score>, usize)>,
+}
+
+impl SharedBatch {
+    pub(super) fn new(threads: usize, batch_size: usize) -> Self {
+        Self {
+            threads,
+            batch_size,
+            queue: Mutex::new(BatchQueue {
+                positions: Vec::with_capacity(batch_size),
+                submissions: 0,
+                empty_submissions: 0,
+                active_threads: threads,
+                batch_id: 0,
+                results: Vec::new(),
+            }),
+            evaluated: Condvar::new(),
+        }
+    }
+
+    //Number of leaves selected by every thread, so together they fill the batch
+    pub(super) fn leaves_per_thread(&self) -> usize {
+        self.batch_size.div_ceil(self.threads)
+    }
+
+    //Adds the positions to the pending batch and returns their scores once the batch is evaluated
+    fn evaluate(&self, mcts: &Mcts, positions: Vec<ChessPosition>) -> Vec<Score> {
+        let mut queue = self.queue.lock().unwrap();
+
+        //Empty submission still counts, so the other threads don't wait for leaves that never come
+        if positions.is_empty() {
+            queue.empty_submissions += 1;
+            if queue.all_submitted() {
+                if queue.submissions > 0 {
+                    drop(self.flush(mcts, queue));
+                } else {
+                    queue.empty_submissions = 0;
+                }
+            }
+
+            return Vec::new();
+        }
+
+        let batch_id = queue.batch_id;
+        let offset = queue.positions.len();
+        let count = positions.len();
+        queue.positions.extend(positions);
+        queue.submissions += 1;
+
+        if queue.positions.len() >= self.batch_size || queue.all_submitted() {
+            queue = self.flush(mcts, queue);
+        }
+
+        loop {
+            if let Some(index) = queue.results.iter().position(|(id, _, _)| *id == batch_id) {
+                let (_, scores, readers) = &mut queue.results[index];
+                let own_scores = scores[offset..offset + count].to_vec();
+                *readers -= 1;
+                if *readers == 0 {
+                    queue.results.swap_remove(index);
+                }
+
+                return own_scores;
+            }
+
+            queue = self.evaluated.wait(queue).unwrap();
+        }
+    }
+
+    //Pending batch is evaluated outside of the lock, so other threads can fill the next one meanwhile
+    fn flush<'b>(&'b self, mcts: &Mcts, mut queue: MutexGuard<'b, BatchQueue>) -> MutexGuard<'b, BatchQueue> {
+        let positions = std::mem::replace(&mut queue.positions, Vec::with_capacity(self.batch_size));
+        let batch_id = queue.batch_id;
+        let readers = queue.submissions;
+        queue.batch_id += 1;
+        queue.submissions = 0;
+        queue.empty_submissions = 0;
+        drop(queue);
+
+        let scores = SearchHelpers::evaluate_batch(&positions, mcts.options, mcts.stats);
+
+        let mut queue = self.queue.lock().unwrap();
+        queue.results.push((batch_id, scores, readers));
+        self.evaluated.notify_all();
+        queue
+    }
+
+    //Evaluates the pending batch, when all remaining threads already submitted their leaves
+    pub(super) fn leave(&self, mcts: &Mcts) {
+        let mut queue = self.queue.lock().unwrap();
+        queue.active_threads -= 1;
+        if queue.submissions > 0 && queue.all_submitted() {
+            drop(self.flush(mcts, queue));
+        }
+    }
+
+    //Has to be called while all threads wait at the segment barrier
+    pub(super) fn rejoin_all(&self) {
+        let mut queue = self.queue.lock().unwrap();
+        queue.active_threads = self.threads;
+        queue.empty_submissions = 0;
+    }
+}
+
+impl BatchQueue {
+    fn all_submitted(&self) -> bool {
+        self.submissions + self.empty_submissions >= self.active_threads
+    }
+}
+
+impl<'a> Mcts<'a> {
+    //Selects this thread's share of the shared batch, waits for the batch to be evaluated and backs
+    //the leaves up in the order of the selection. Returns None, when tree segment got full during
+    //the selection
+    pub(super) fn descend_batch<const STM_WHITE: bool, const NSTM_WHITE: bool>(
+        &self,
+        batch: &SharedBatch,
+    ) -> Option<()> {
+        let (leaves, segment_full) = self.select_leaves::<STM_WHITE, NSTM_WHITE>(batch.leaves_per_thread());
+
+        let scores = self.score_leaves(&leaves, |positions| batch.evaluate(self, positions));
+
+        for (leaf, score) in leaves.iter().zip(scores) {
+            self.backup_leaf(leaf, score);
+        }
+
+        if segment_full {
+            return None;
+        }
+
+        Some(())
+    }
+
+    //Selects leaves one after another. Virtual loss of every selected leaf stays applied until it
+    //is backed up, so the next descends are pushed to other paths. When a descend reaches a leaf
+    //that is already in the batch, including the same repetition, virtual loss can't spread them
+    //anymore, so its virtual loss is removed and the selection stops early. Returns the leaves and
+    //whether tree segment got full
+    pub(super) fn select_leaves<const STM_WHITE: bool, const NSTM_WHITE: bool>(
+        &self,
+        batch_size: usize,
+    ) -> (Vec<BatchLeaf>, bool) {
+        let mut leaves: Vec<BatchLeaf> = Vec::with_capacity(batch_size);
+
+        while leaves.len() < batch_size {
+            let mut leaf = BatchLeaf {
+                path: Vec::new(),
+                node_index: NodeIndex::NULL,
+                position: self.root_position,
+            };
+
+            let root_index = self.tree.root_index();
+            if self
+                .select_leaf::<STM_WHITE, NSTM_WHITE, true, true, false>(root_index, self.tree.root_edge(), &mut leaf)
+                .is_none()
+            {
+                self.release_leaf(&leaf);
+                return (leaves, true);
+            }
+
+            if leaves.iter().any(|other| other.is_same_leaf(&leaf)) {
+                self.release_leaf(&leaf);
+                break;
+            }
+
+            leaves.push(leaf);
+        }
+
+        (leaves, false)
+    }
+
+    //Hash table is probed before the evaluation, so its content doesn't depend on the order in
+    //which positions are evaluated. Evaluated scores are stored before the material bonus
+    pub(super) fn score_leaves<F: FnOnce(Vec<ChessPosition>) -> Vec<Score>>(
+        &self,
+        leaves: &[BatchLeaf],
+        evaluate: F,
+    ) -> Vec<Score> {
+        let mut positions = Vec::new();
+        let known_scores: Vec<KnownScore> = leaves
+            .iter()
+            .map(|leaf| {
+                if leaf.node_index.is_null() {
+                    return KnownScore::Exact(Score::DRAW);
+                }
+
+                let node = &self.tree[leaf.node_index];
+                let exact_score = match node.state() {
+                    GameState::Drawn => Some(Score::DRAW),
+                    GameState::Lost(_) => Some(Score::LOSE),
+                    GameState::Won(_) => Some(Score::WIN),
+                    GameState::Unresolved => {
+                        SearchHelpers::get_transposition_score(node.key(), self.tree, self.options)
+                    }
+                };
+
+                if let Some(score) = exact_score {
+                    return KnownScore::Exact(score);
+                }
+
+                match self.tree.hash_table().probe(node.key(), self.stats) {
+                    Some(score) => KnownScore::Hashed(score),
+                    None => {
+                        positions.push(leaf.position);
+                        KnownScore::Unknown
+                    }
+                }
+            })
+            .collect();
+
+        let mut scores = evaluate(positions).into_iter();
+
+        leaves
+            .iter()
+            .zip(known_scores)
+            .map(|(leaf, known_score)| match known_score {
+                KnownScore::Exact(score) => score,
+                KnownScore::Hashed(score) => self.adjust_leaf_score(leaf, score),
+                KnownScore::Unknown => {
+                    let score = scores.next().expect("Missing leaf evaluation");
+                    self.tree.hash_table().store(self.tree[leaf.node_index].key(), score, self.stats);
+                    self.adjust_leaf_score(leaf, score)
+                }
+            })
+            .collect()
+    }
+
+    //Material bonus and 50 move rule damping, same as in 'get_node_score'
+    fn adjust_leaf_score(&self, leaf: &BatchLeaf, score: Score) -> Score {
+        let current_material = Self::calculate_stm_material(&leaf.position, self.root_position.board().side_to_move());
+        let score = SearchHelpers::add_material_bonus(score, self.start_material - current_material, self.options);
+        SearchHelpers::apply_fifty_move_rule(score, leaf.position.board(), self.options)
+    }
+
+    //Same descend as in 'process_deeper_node', but it stops at the leaf instead of evaluating it
+    fn select_leaf<
+        const STM_WHITE: bool,
+        const NSTM_WHITE: bool,
+        const ROOT: bool,
+        const US: bool,
+        const NOT_US: bool
+    >(
+        &self,
+        current_node_index: NodeIndex,
+        action_cpy: &Edge,
+        leaf: &mut BatchLeaf,
+    ) -> Option<()> {
+        if !ROOT && (self.tree[current_node_index].is_terminal() || action_cpy.visits() == 0) {
+            //Thread counter of the leaf was already increased by this descend
+            if self.tree[current_node_index].threads() > 1 {
+                self.stats.add_collision();
+            }
+
+            leaf.node_index = current_node_index;
+            return Some(());
+        }
+
+        if !self.tree[current_node_index].has_children() {
+            self.tree[current_node_index].expand::<STM_WHITE, NSTM_WHITE, false>(&leaf.position, self.options, &[])
+        }
+
+        let contempt = if US {
+            self.options.draw_contempt()
+        } else {
+            0.0
+        };
+
+        let best_action_index = self.select_action::<ROOT>(current_node_index, action_cpy, contempt);
+        let new_edge_cpy = self.tree.get_edge_clone(current_node_index, best_action_index);
+        leaf.position.make_move::<STM_WHITE, NSTM_WHITE>(new_edge_cpy.mv());
+
+        //Repetition is a draw leaf without a node, same as in 'process_deeper_node'. Its virtual loss
+        //is kept by the edge
+        if leaf.position.is_repetition() {
+            self.tree[current_node_index].actions()[best_action_index].inc_threads();
+            leaf.path.push((current_node_index, best_action_index, NodeIndex::NULL));
+            leaf.node_index = NodeIndex::NULL;
+            return Some(());
+        }
+
+        let new_node_index = self.tree.get_node_index::<NSTM_WHITE, STM_WHITE>(
+            &leaf.position,
+            new_edge_cpy.node_index(),
+            current_node_index,
+            best_action_index,
+            self.stats,
+        )?;
+
+        if new_edge_cpy.node_index().is_null() {
+            self.stats.add_node();
+        }
+
+        self.tree[new_node_index].inc_threads();
+        leaf.path.push((current_node_index, best_action_index, new_node_index));
+
+        self.select_leaf::<NSTM_WHITE, STM_WHITE, false, NOT_US, US>(new_node_index, &new_edge_cpy, leaf)
+    }
+
+    //Removes virtual loss of the leaf, that won't be backed up
+    fn release_leaf(&self, leaf: &BatchLeaf) {
+        for &(node_index, action_index, child_index) in &leaf.path {
+            if child_index.is_null() {
+                self.tree[node_index].actions()[action_index].dec_threads();
+            } else {
+                self.tree[child_index].dec_threads();
+            }
+        }
+    }
+
+    //Backpropagates the score up the path in the same way as recursive descend does
+    pub(super) fn backup_leaf(&self, leaf: &BatchLeaf, score: Score) {
+        let mut score = score.reversed();
+        for &(node_index, action_index, child_index) in leaf.path.iter().rev() {
+            //Repetition leaf has no node, so only its edge is updated
+            if child_index.is_null() {
+                self.tree[node_index].actions()[action_index].dec_threads();
+                self.tree.add_edge_score(node_index, action_index, score);
+                score = score.reversed();
+                continue;
+            }
+
+            self.tree[child_index].dec_threads();
+            self.tree.add_edge_score(node_index, action_index, score);
+            self.add_transposition_score(child_index, score);
+            self.tree.backpropagate_states(node_index, self.tree[child_index].state());
+            score = score.reversed();
+        }
+
+        self.tree.root_edge().add_score(score);
+        self.stats.add_iteration(leaf.path.len() as u32);
+    }
+}
@@ -0,0 +1,103 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Barrier, Mutex, RwLock,
+};
+
+use spear::ChessPosition;
+
+use crate::search::{Score, SearchHelpers};
+
+use super::Mcts;
+
+//Evaluation workers of the deterministic search. Main thread selects the leaves in fixed order,
+//workers only run the value network on them, and the scores are backed up in the same order
+pub(super) struct DeterministicRound {
+    threads: usize,
+    barrier: Barrier,
+    positions: RwLock<Vec<ChessPosition>>,
+    scores: Mutex<Vec<Score>>,
+    finished: AtomicBool,
+}
+
+impl DeterministicRound {
+    pub(super) fn new(threads: usize) -> Self {
+        Self {
+            threads,
+            barrier: Barrier::new(threads),
+            positions: RwLock::new(Vec::new()),
+            scores: Mutex::new(Vec::new()),
+            finished: AtomicBool::new(false),
+        }
+    }
+
+    pub(super) fn threads(&self) -> usize {
+        self.threads
+    }
+
+    pub(super) fn worker_loop(&self, mcts: &Mcts, worker_index: usize) {
+        loop {
+            self.barrier.wait();
+            if self.finished.load(Ordering::Relaxed) {
+                return;
+            }
+
+            self.evaluate_share(mcts, worker_index);
+            self.barrier.wait();
+        }
+    }
+
+    //Releases the workers waiting for the next round
+    pub(super) fn finish(&self) {
+        self.finished.store(true, Ordering::Relaxed);
+        self.barrier.wait();
+    }
+
+    //Evaluates positions in parallel, every thread takes positions with index matching its own
+    fn evaluate(&self, mcts: &Mcts, positions: Vec<ChessPosition>) -> Vec<Score> {
+        *self.scores.lock().unwrap() = vec![Score::default(); positions.len()];
+        *self.positions.write().unwrap() = positions;
+
+        self.barrier.wait();
+        self.evaluate_share(mcts, 0);
+        self.barrier.wait();
+
+        std::mem::take(&mut *self.scores.lock().unwrap())
+    }
+
+    //Share of every thread is evaluated as one batch
+    fn evaluate_share(&self, mcts: &Mcts, worker_index: usize) {
+        let positions = self.positions.read().unwrap();
+        let indices: Vec<usize> = (worker_index..positions.len()).step_by(self.threads).collect();
+        let share: Vec<ChessPosition> = indices.iter().map(|&index| positions[index]).collect();
+        let share_scores = SearchHelpers::evaluate_batch(&share, mcts.options, mcts.stats);
+
+        let mut scores = self.scores.lock().unwrap();
+        for (index, score) in indices.into_iter().zip(share_scores) {
+            scores[index] = score;
+        }
+    }
+}
+
+impl<'a> Mcts<'a> {
+    //Runs single round of the deterministic search, one descend per thread. Returns None, when
+    //tree segment got full during the round
+    pub(super) fn deterministic_round<const STM_WHITE: bool, const NSTM_WHITE: bool>(
+        &self,
+        round: &DeterministicRound,
+    ) -> Option<()> {
+        //Leaves are selected one after another, so virtual loss of the previous
+        //descends is already applied to the next one
+        let (leaves, segment_full) = self.select_leaves::<STM_WHITE, NSTM_WHITE>(round.threads());
+
+        let scores = self.score_leaves(&leaves, |positions| round.evaluate(self, positions));
+        for (leaf, score) in leaves.iter().zip(scores) {
+            self.backup_leaf(leaf, score);
+        }
+
+        if segment_full {
+            return None;
+        }
+
+        Some(())
+    }
+}
@@ -2,11 +2,33 @@ use core::f32;
 
 use spear::ChessPosition;
 
-use crate::search::{tree::Edge, NodeIndex, Score, SearchHelpers};
+use crate::{
+    search::{tree::Edge, NodeIndex, Score, SearchHelpers},
+    GameState,
+};
 
 use super::Mcts;
 
 impl<'a> Mcts<'a> {
+    //Performs single descend from the root and returns None, when tree segment is full
+    pub(super) fn descend<const STM_WHITE: bool, const NSTM_WHITE: bool>(&self) -> Option<()> {
+        let mut depth = 0;
+        let mut position = self.root_position;
+        let root_index = self.tree.root_index();
+        let score = self.process_deeper_node::<STM_WHITE, NSTM_WHITE, true, true, false>(
+            root_index,
+            self.tree.root_edge(),
+            &mut position,
+            &mut depth,
+        )?;
+
+        self.tree.root_edge().add_score(score);
+
+        //Increment search stats
+        self.stats.add_iteration(depth);
+        Some(())
+    }
+
     pub(super) fn process_deeper_node<
         const STM_WHITE: bool,
         const NSTM_WHITE: bool,
@@ -25,6 +47,11 @@ impl<'a> Mcts<'a> {
         let score = if !ROOT
             && (self.tree[current_node_index].is_terminal() || action_cpy.visits() == 0)
         {
+            //Thread counter of the leaf was already increased by this thread
+            if self.tree[current_node_index].threads() > 1 {
+                self.stats.add_collision();
+            }
+
             let current_material = Self::calculate_stm_material(&current_position, self.root_position.board().side_to_move());
             SearchHelpers::get_node_score::<STM_WHITE, NSTM_WHITE>(
                 current_position,
@@ -32,13 +59,14 @@ impl<'a> Mcts<'a> {
                 self.tree[current_node_index].key(),
                 self.tree,
                 self.start_material - current_material,
-                self.options
+                self.options,
+                self.stats
             )
         } else {
             //On second visit we expand the node, if it wasn't already expanded.
             //This allows us to reduce amount of time we evaluate policy net
             if !self.tree[current_node_index].has_children() {
-                self.tree[current_node_index].expand::<STM_WHITE, NSTM_WHITE, false>(current_position, self.options)
+                self.tree[current_node_index].expand::<STM_WHITE, NSTM_WHITE, false>(current_position, self.options, &[])
             }
 
             //Calculate asymetrical draw contempt
@@ -59,14 +87,28 @@ impl<'a> Mcts<'a> {
                 .get_edge_clone(current_node_index, best_action_index);
             current_position.make_move::<STM_WHITE, NSTM_WHITE>(new_edge_cpy.mv());
 
+            //Repetition depends on the path to the position, so it's scored as a draw leaf without
+            //creating a node for it. Edges without a node are never proven, so repetitions don't
+            //prove their parents and don't stay in the tree after the game history changes
+            if current_position.is_repetition() {
+                *depth += 1;
+                self.tree.add_edge_score(current_node_index, best_action_index, Score::DRAW);
+                return Some(Score::DRAW);
+            }
+
             //Process the new action on the tree and obtain it's updated index
             let new_node_index = self.tree.get_node_index::<NSTM_WHITE, STM_WHITE>(
                 current_position,
                 new_edge_cpy.node_index(),
                 current_node_index,
                 best_action_index,
+                self.stats,
             )?;
 
+            if new_edge_cpy.node_index().is_null() {
+                self.stats.add_node();
+            }
+
             //Increase amount of threads visiting this node
             self.tree[new_node_index].inc_threads();
 
@@ -86,10 +128,11 @@ impl<'a> Mcts<'a> {
             //Backpropagate the score up the tree
             self.tree
                 .add_edge_score(current_node_index, best_action_index, score);
+            self.add_transposition_score(new_node_index, score);
 
-            //Backpropagate mates to assure our engine avoids/follows mating line
+            //Backpropagate proven results to assure our engine avoids/follows mating and drawing lines
             self.tree
-                .backpropagate_mates(current_node_index, self.tree[new_node_index].state());
+                .backpropagate_states(current_node_index, self.tree[new_node_index].state());
 
             score
         };
@@ -97,9 +140,18 @@ impl<'a> Mcts<'a> {
         Some(score.reversed())
     }
 
+    //Shares the score with all nodes of the same position. Proven nodes are skipped, because their
+    //score doesn't depend on the search
+    #[inline]
+    pub(super) fn add_transposition_score(&self, node_index: NodeIndex, score: Score) {
+        if self.options.transposition_weight() > 0.0 && self.tree[node_index].state() == GameState::Unresolved {
+            self.tree.transpositions().add_score(self.tree[node_index].key(), score)
+        }
+    }
+
     //PUCT formula V + C * P * (N.max(1).sqrt()/n + 1) where N = number of visits to parent node, n = number of visits to a child
     #[inline]
-    fn select_action<const ROOT: bool>(
+    pub(super) fn select_action<const ROOT: bool>(
         &self,
         node_idx: NodeIndex,
         parent: &Edge,
@@ -129,6 +181,7 @@ impl<'a> Mcts<'a> {
         explore_scale *= (0.679 - 1.634 * (self.tree[node_idx].gini_impurity() + 0.001).ln()).min(2.1);
 
         let explore_value = cpuct * explore_scale;
+        let transposition_weight = self.options.transposition_weight();
         self.tree[node_idx].get_best_action_by_key(|action| {
             let visits = action.visits() + 1;
 
@@ -138,23 +191,45 @@ impl<'a> Mcts<'a> {
                 action.score()
             };
 
-            //Virtual loss
             let idx = action.node_index();
-            if !idx.is_null() {
-                let thrds = f64::from(self.tree[idx].threads());
-                let v = f64::from(visits);
 
-                if thrds > 0.0 {
+            //Proven children use their exact result. Moves proven to lose are only selected
+            //when all moves lose, and then the longest line is preferred
+            if !idx.is_null() {
+                match self.tree[idx].state() {
+                    GameState::Won(n) => return f32::from(n) - 256.0,
+                    GameState::Lost(_) => score = Score::WIN,
+                    GameState::Drawn => score = Score::DRAW,
+                    GameState::Unresolved => (),
+                }
+            }
 
-                    //Score adjusted by the amount of thread visits
-                    let w = f64::from(score.win_chance()) * v / (v + thrds);
-                    let d = f64::from(score.draw_chance()) * v / (v + thrds);
-                    score = Score::new(w as f32, d as f32);
+            //When the position was visited more times through other move orders,
+            //score is blended with statistics of all of its transpositions
+            if !idx.is_null() && transposition_weight > 0.0 && !self.tree[idx].is_terminal() {
+                if let Some((tt_visits, tt_score)) = self.tree.transpositions().probe(self.tree[idx].key()) {
+                    if tt_visits > action.visits() {
+                        score = score.blend(tt_score, transposition_weight);
+                    }
                 }
             }
 
+            //Virtual loss, every thread currently searching below this action counts as a lost visit.
+            //It lowers both the score and the exploration bonus, so concurrent threads pick different paths.
+            //Repetitions have no node, so threads waiting for them are counted by the edge
+            let thrds = if idx.is_null() { u32::from(action.threads()) } else { u32::from(self.tree[idx].threads()) };
+            if thrds > 0 {
+                let v = f64::from(visits);
+                let t = f64::from(thrds);
+
+                //Score adjusted by the amount of thread visits
+                let w = f64::from(score.win_chance()) * v / (v + t);
+                let d = f64::from(score.draw_chance()) * v / (v + t);
+                score = Score::new(w as f32, d as f32);
+            }
+
             let score = score.single(contempt);
-            score + (explore_value * action.policy() / (visits as f32))
+            score + (explore_value * action.policy() / ((visits + thrds) as f32))
         })
     }
 }
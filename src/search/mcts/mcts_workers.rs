@@ -1,10 +1,74 @@
-use std::{sync::atomic::Ordering, thread, time::Instant};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Barrier,
+    },
+    thread,
+    time::Instant,
+};
 
 use spear::Move;
 
-use crate::search::print::SearchDisplay;
+use crate::search::{print::SearchDisplay, SearchInfo};
 
-use super::Mcts;
+use super::{mcts_batch::SharedBatch, mcts_deterministic::DeterministicRound, Mcts};
+
+//Threads are spawned once per search. When the tree segment is full, all of them meet at the barrier,
+//so segments can be swapped while no thread is inside the tree
+struct SegmentSync {
+    barrier: Barrier,
+    segment_full: AtomicBool,
+    finished: AtomicBool,
+    batch: Option<SharedBatch>,
+}
+
+impl SegmentSync {
+    fn new(threads: usize, batch_size: usize) -> Self {
+        Self {
+            barrier: Barrier::new(threads),
+            segment_full: AtomicBool::new(false),
+            finished: AtomicBool::new(false),
+            batch: (batch_size > 1).then(|| SharedBatch::new(threads, batch_size)),
+        }
+    }
+
+    fn batch(&self) -> Option<&SharedBatch> {
+        self.batch.as_ref()
+    }
+
+    fn is_segment_full(&self) -> bool {
+        self.segment_full.load(Ordering::Relaxed)
+    }
+
+    fn mark_segment_full(&self) {
+        self.segment_full.store(true, Ordering::Relaxed)
+    }
+
+    //Waits for all threads and returns true, if the search should continue. Decision is made by
+    //a single thread, so all of them agree on it even if search is stopped in the meantime
+    fn synchronize(&self, mcts: &Mcts) -> bool {
+        if let Some(batch) = self.batch() {
+            batch.leave(mcts);
+        }
+
+        if self.barrier.wait().is_leader() {
+            let interrupted = mcts.interruption_token.load(Ordering::Relaxed);
+            if !interrupted && self.is_segment_full() {
+                mcts.tree.advance_segments();
+            }
+
+            if let Some(batch) = self.batch() {
+                batch.rejoin_all();
+            }
+
+            self.segment_full.store(false, Ordering::Relaxed);
+            self.finished.store(interrupted, Ordering::Relaxed);
+        }
+
+        self.barrier.wait();
+        !self.finished.load(Ordering::Relaxed)
+    }
+}
 
 impl<'a> Mcts<'a> {
     pub(super) fn search_loop<
@@ -15,23 +79,35 @@ impl<'a> Mcts<'a> {
         &self,
         printer: &'a mut PRINTER,
     ) {
-        loop {
-            thread::scope(|s| {
-                s.spawn(|| {
-                    self.main_loop::<PRINTER, STM_WHITE, NSTM_WHITE>(printer);
-                });
+        let threads = self.options.threads().max(1) as usize;
+
+        //Deterministic search is driven only by the main thread, other threads just evaluate its leaves
+        if self.options.deterministic() {
+            let round = DeterministicRound::new(threads);
+            let sync = SegmentSync::new(1, 1);
 
-                for _ in 0..self.options.threads() - 1 {
-                    s.spawn(|| self.worker_loop::<STM_WHITE, NSTM_WHITE>());
+            thread::scope(|s| {
+                for worker_index in 1..threads {
+                    let round = &round;
+                    s.spawn(move || round.worker_loop(self, worker_index));
                 }
+
+                self.main_loop::<PRINTER, STM_WHITE, NSTM_WHITE>(printer, &sync, Some(&round));
+                round.finish();
             });
 
-            if self.interruption_token.load(Ordering::Relaxed) {
-                return;
+            return;
+        }
+
+        let sync = SegmentSync::new(threads, self.options.batch_size().max(1) as usize);
+
+        thread::scope(|s| {
+            for _ in 0..threads - 1 {
+                s.spawn(|| self.worker_loop::<STM_WHITE, NSTM_WHITE>(&sync));
             }
 
-            self.tree.advance_segments();     
-        }
+            self.main_loop::<PRINTER, STM_WHITE, NSTM_WHITE>(printer, &sync, None);
+        });
     }
 
     fn main_loop<
@@ -41,43 +117,59 @@ impl<'a> Mcts<'a> {
     >(
         &self,
         printer: &'a mut PRINTER,
+        sync: &SegmentSync,
+        round: Option<&DeterministicRound>,
     ) {
 
         let mut best_move = Move::NULL;
         let mut best_move_changes = 0;
         let mut previous_score = f32::NEG_INFINITY;
 
+        //Checks are counted with iterations of this thread, because shared counter is increased
+        //by all threads and would skip the exact multiples
+        let mut main_iters = 0u64;
         let mut last_raport_time = Instant::now();
         let mut last_avg_depth = 0;
         loop {
-            //Start tree descend
-            let mut depth = 0;
-            let mut position = self.root_position;
-            let root_index = self.tree.root_index();
-            let result = self.process_deeper_node::<STM_WHITE, NSTM_WHITE, true, true, false>(
-                root_index,
-                self.tree.root_edge(),
-                &mut position,
-                &mut depth,
-            );
+            if sync.is_segment_full() || self.interruption_token.load(Ordering::Relaxed) {
+                if sync.synchronize(self) {
+                    continue;
+                }
 
-            let draw_contempt = self.options.draw_contempt();
-            if let Some(score) = result {
-                self.tree.root_edge().add_score(score);
-            } else {
                 return;
             }
 
-            //Increment search stats
-            self.stats.add_iteration(depth);
+            //Start tree descend, in deterministic search it's a round of descends
+            let completed = match round {
+                Some(round) => self.deterministic_round::<STM_WHITE, NSTM_WHITE>(round),
+                None => self.descend_with_batch::<STM_WHITE, NSTM_WHITE>(sync),
+            };
+
+            if completed.is_none() {
+                sync.mark_segment_full();
+                continue;
+            }
+
+            let draw_contempt = self.options.draw_contempt();
+            let root_index = self.tree.root_index();
+            main_iters += 1;
+
+            //Interrupt search when root becomes terminal node, so when there is a force mate on board.
+            //In mate search we only stop after finding the mate short enough
+            if self.tree[root_index].is_terminal()
+                && !self.options.analyse_mode()
+                && !self.limits.is_pondering()
+                && self.limits.mate().is_none()
+            {
+                self.interruption_token.store(true, Ordering::Relaxed)
+            }
 
-            //Interrupt search when root becomes terminal node, so when there is a force mate on board
-            if self.tree[root_index].is_terminal() && !self.options.analyse_mode() {
+            if self.limits.is_mate_found(self.tree[root_index].state()) {
                 self.interruption_token.store(true, Ordering::Relaxed)
             }
 
             //Update timer every few iterations to reduce the slowdown caused by obtaining time
-            if self.stats.iters() % 256 == 0 {
+            if main_iters % 256 == 0 {
                 self.stats.update_time_passed();
 
                 //Check hard time limit
@@ -94,7 +186,7 @@ impl<'a> Mcts<'a> {
             }
 
             //Check soft time every larger chunk of iterations
-            if self.stats.iters() % 16384 == 0 {
+            if main_iters % 16384 == 0 {
                 if self.limits.is_soft_time_limit_reached(self.stats, self.options, &mut best_move_changes, &mut previous_score, &self.tree) {
                     self.interruption_token.store(true, Ordering::Relaxed)
                 }
@@ -107,7 +199,7 @@ impl<'a> Mcts<'a> {
 
             //Break out of the search
             if self.interruption_token.load(Ordering::Relaxed) {
-                return;
+                continue;
             }
 
             //Draws report when avg_depth increases or if there wasn't any reports for longer than refresh rate
@@ -116,47 +208,38 @@ impl<'a> Mcts<'a> {
             {
                 last_avg_depth = last_avg_depth.max(self.stats.avg_depth());
                 last_raport_time = Instant::now();
-                printer.print_search_raport::<false>(
-                    self.stats,
-                    self.options,
-                    self.limits,
-                    self.tree.total_usage(),
-                    &self.tree.get_pvs(self.options.multi_pv(), draw_contempt)
-                )
+                let search_info = SearchInfo::new(self.stats, self.tree, self.options, self.moves_left);
+                printer.print_search_raport::<false>(&search_info, self.limits);
+                self.send_progress(&search_info)
             }
         }
     }
 
-    fn worker_loop<const STM_WHITE: bool, const NSTM_WHITE: bool>(&self) {
-        loop {
-            //Start tree descend
-            let mut depth = 0;
-            let mut position = self.root_position;
-            let root_index = self.tree.root_index();
-            let result = self.process_deeper_node::<STM_WHITE, NSTM_WHITE, true, true, false>(
-                root_index,
-                self.tree.root_edge(),
-                &mut position,
-                &mut depth,
-            );
-
-            if let Some(score) = result {
-                self.tree.root_edge().add_score(score);
-            } else {
-                return;
-            }
+    //Single descend, or this thread's share of the shared batch when BatchSize is larger than 1
+    fn descend_with_batch<const STM_WHITE: bool, const NSTM_WHITE: bool>(&self, sync: &SegmentSync) -> Option<()> {
+        match sync.batch() {
+            Some(batch) => self.descend_batch::<STM_WHITE, NSTM_WHITE>(batch),
+            None => self.descend::<STM_WHITE, NSTM_WHITE>(),
+        }
+    }
 
-            //Increment search stats
-            self.stats.add_iteration(depth);
+    fn worker_loop<const STM_WHITE: bool, const NSTM_WHITE: bool>(&self, sync: &SegmentSync) {
+        loop {
+            //Workers wait at the barrier when root is terminal, so there is nothing left to search
+            if sync.is_segment_full()
+                || self.interruption_token.load(Ordering::Relaxed)
+                || self.tree[self.tree.root_index()].is_terminal()
+            {
+                if sync.synchronize(self) {
+                    continue;
+                }
 
-            //Interrupt search when root becomes terminal node, so when there is a force mate on board
-            if self.tree[self.tree.root_index()].is_terminal() {
                 return;
             }
 
-            //Break out of the search
-            if self.interruption_token.load(Ordering::Relaxed) {
-                return;
+            //Start tree descend
+            if self.descend_with_batch::<STM_WHITE, NSTM_WHITE>(sync).is_none() {
+                sync.mark_segment_full();
             }
         }
     }
@@ -1,4 +1,6 @@
 mod mcts_base;
+mod mcts_batch;
+mod mcts_deterministic;
 mod mcts_iteration;
 mod mcts_workers;
 
@@ -1,23 +1,26 @@
+mod engine;
 mod eval_score;
 mod game_state;
 mod mcts;
 mod networks;
 mod print;
-mod search_engine;
+mod search_info;
 mod search_limits;
 mod search_stats;
 mod tree;
 mod utils;
 
+pub use engine::{Engine, SearchResult};
 pub use eval_score::Score;
 pub use game_state::GameState;
 pub use mcts::Mcts;
 pub use networks::PolicyNetwork;
 pub use networks::ValueNetwork;
-pub use print::NoPrint;
-pub use search_engine::SearchEngine;
+pub use print::{JsonPrint, NoPrint};
+pub use search_info::{PvInfo, SearchInfo};
 pub use search_limits::SearchLimits;
 pub use search_stats::SearchStats;
 pub use tree::NodeIndex;
 pub use tree::Tree;
 pub(super) use utils::SearchHelpers;
+pub(super) use utils::StrengthLimit;
@@ -38,6 +38,55 @@ impl<const INPUTS: usize, const OUTPUTS: usize> NetworkLayer<INPUTS, OUTPUTS> {
         result
     }
 
+    //Columns of the sparse batched kernel processed in one pass
+    const TILE_SIZE: usize = 64;
+
+    //Sparse matrix-matrix product of binary inputs, 'features' are (weight row, output) pairs sorted
+    //by the weight row. Outputs are computed in column tiles, so tiles of all outputs stay in cache
+    //during the pass and a tile of every weight row is loaded once for all outputs using it.
+    //Features of an output are always summed in the same order, so it doesn't depend on the others
+    pub fn forward_sparse_batch(&self, features: &[(usize, usize)], outputs: usize) -> Vec<Accumulator<OUTPUTS>> {
+        let mut results = vec![self.biases; outputs];
+
+        for tile_start in (0..OUTPUTS).step_by(Self::TILE_SIZE) {
+            let tile = tile_start..(tile_start + Self::TILE_SIZE).min(OUTPUTS);
+
+            let mut features = features.iter().peekable();
+            while let Some(&(weight_index, output_index)) = features.next() {
+                let weights = &self.weights[weight_index].values()[tile.clone()];
+                Self::add_tile(&mut results[output_index].values_mut()[tile.clone()], weights);
+
+                //Outputs sharing the row reuse the loaded tile
+                while let Some(&(_, output_index)) = features.next_if(|(index, _)| *index == weight_index) {
+                    Self::add_tile(&mut results[output_index].values_mut()[tile.clone()], weights);
+                }
+            }
+        }
+
+        results
+    }
+
+    #[inline]
+    fn add_tile(result: &mut [f32], weights: &[f32]) {
+        for (i, &j) in result.iter_mut().zip(weights) {
+            *i += j;
+        }
+    }
+
+    //Matrix-matrix version of 'forward', every row of weights is loaded once for the whole batch
+    pub fn forward_batch(&self, inputs: &[Accumulator<INPUTS>]) -> Vec<Accumulator<OUTPUTS>> {
+        let mut results = vec![self.biases; inputs.len()];
+
+        for (neuron_index, weights) in self.weights.iter().enumerate() {
+            for (result, input) in results.iter_mut().zip(inputs) {
+                let activated = input.values()[neuron_index].clamp(0.0, 1.0).powi(2);
+                result.madd(activated, weights);
+            }
+        }
+
+        results
+    }
+
     pub fn forward_relu(&self, inputs: &Accumulator<INPUTS>) -> Accumulator<OUTPUTS> {
         let mut result = self.biases;
 
@@ -49,3 +98,51 @@ impl<const INPUTS: usize, const OUTPUTS: usize> NetworkLayer<INPUTS, OUTPUTS> {
         result
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //Output size isn't a multiple of the tile, so the last tile is partial
+    fn test_layer() -> NetworkLayer<6, 100> {
+        let mut layer = NetworkLayer::<6, 100>::default();
+        for (row_index, row) in layer.weights.iter_mut().enumerate() {
+            for (column, weight) in row.values_mut().iter_mut().enumerate() {
+                *weight = (row_index * 100 + column) as f32 * 0.01 - 2.5;
+            }
+        }
+
+        for (column, bias) in layer.biases.values_mut().iter_mut().enumerate() {
+            *bias = column as f32 * 0.1;
+        }
+
+        layer
+    }
+
+    #[test]
+    fn sparse_batch_matches_separate_sums() {
+        let layer = test_layer();
+        let active_rows: [&[usize]; 4] = [&[0, 2, 5], &[2], &[], &[1, 2, 3, 5]];
+
+        let mut features: Vec<(usize, usize)> = active_rows
+            .iter()
+            .enumerate()
+            .flat_map(|(output, rows)| rows.iter().map(move |&row| (row, output)))
+            .collect();
+        features.sort_unstable();
+
+        let results = layer.forward_sparse_batch(&features, active_rows.len());
+
+        assert_eq!(results.len(), active_rows.len());
+        for (result, rows) in results.iter().zip(active_rows) {
+            let mut expected = *layer.biases();
+            for &row in rows {
+                for (i, &j) in expected.values_mut().iter_mut().zip(layer.weights()[row].values()) {
+                    *i += j;
+                }
+            }
+
+            assert_eq!(result.values(), expected.values());
+        }
+    }
+}
@@ -1,7 +1,8 @@
 use spear::{ChessBoard, Piece, Side};
 
-use super::NetworkLayer;
+use super::{Accumulator, NetworkLayer};
 
+#[cfg(not(feature = "moves-left"))]
 #[allow(non_upper_case_globals)]
 pub static ValueNetwork: ValueNetwork = unsafe {
     std::mem::transmute(*include_bytes!(
@@ -9,18 +10,48 @@ pub static ValueNetwork: ValueNetwork = unsafe {
     ))
 };
 
+#[cfg(feature = "moves-left")]
+#[allow(non_upper_case_globals)]
+pub static ValueNetwork: ValueNetwork = unsafe {
+    std::mem::transmute(*include_bytes!(
+        "../../../resources/networks/v600cos1024td005wdl_ft5_ml64.network"
+    ))
+};
+
+//Moves left head is trained separately from the wdl head and appended to the network file.
+//Without 'moves-left' feature the network has no such head and estimates no moves left
 #[repr(C)]
 pub struct ValueNetwork {
-    l1: NetworkLayer<{ 768 * 4 }, 1024>,
+    l1: NetworkLayer<{ ValueNetwork::INPUT_SIZE }, 1024>,
     l2: NetworkLayer<1024, 3>,
+    #[cfg(feature = "moves-left")]
+    moves_left_l1: NetworkLayer<{ ValueNetwork::INPUT_SIZE }, 64>,
+    #[cfg(feature = "moves-left")]
+    moves_left_l2: NetworkLayer<64, 1>,
 }
 
 impl ValueNetwork {
+    pub const HAS_MOVES_LEFT: bool = cfg!(feature = "moves-left");
+
+    //Moves left target is scaled into 0-1 range during training
+    pub const MAX_MOVES_LEFT: f32 = 300.0;
+
+    //Inputs are repeated for every range of the 50 move counter, single bucket ignores the counter
+    pub const HALFMOVE_BUCKETS: usize = 1;
+    pub const INPUT_SIZE: usize = 768 * 4 * Self::HALFMOVE_BUCKETS;
+
+    pub fn halfmove_bucket(half_moves: usize) -> usize {
+        (half_moves * Self::HALFMOVE_BUCKETS / 100).min(Self::HALFMOVE_BUCKETS - 1)
+    }
+
+    //Returns win, draw and loss chances together with estimated number of plies until the end of the game
     pub fn forward<const STM_WHITE: bool, const NSTM_WHITE: bool>(
         &self,
         board: &ChessBoard,
-    ) -> (f32,f32,f32) {
+    ) -> (f32,f32,f32,f32) {
         let mut l1_out = *self.l1.biases();
+        #[cfg(feature = "moves-left")]
+        let mut moves_left_l1_out = *self.moves_left_l1.biases();
 
         Self::map_value_inputs::<_, STM_WHITE, NSTM_WHITE>(board, |weight_index| {
             for (i, weight) in l1_out
@@ -30,10 +61,70 @@ impl ValueNetwork {
             {
                 *i += *weight;
             }
+
+            #[cfg(feature = "moves-left")]
+            for (i, weight) in moves_left_l1_out
+                .values_mut()
+                .iter_mut()
+                .zip(&self.moves_left_l1.weights()[weight_index].vals)
+            {
+                *i += *weight;
+            }
         });
 
         let out = self.l2.forward(&l1_out);
 
+        #[cfg(feature = "moves-left")]
+        let moves_left = Self::moves_left(&self.moves_left_l2.forward(&moves_left_l1_out));
+        #[cfg(not(feature = "moves-left"))]
+        let moves_left = 0.0;
+
+        Self::outputs(&out, moves_left)
+    }
+
+    //Evaluates multiple positions at once. Active features of all boards are sorted and the first
+    //layer is computed with the sparse batched kernel, later layers as matrix-matrix products
+    pub fn forward_batch(&self, boards: &[ChessBoard]) -> Vec<(f32, f32, f32, f32)> {
+        let mut features = Vec::with_capacity(boards.len() * 32);
+        for (board_index, board) in boards.iter().enumerate() {
+            let add_feature = |weight_index| features.push((weight_index, board_index));
+            if board.side_to_move() == Side::WHITE {
+                Self::map_value_inputs::<_, true, false>(board, add_feature)
+            } else {
+                Self::map_value_inputs::<_, false, true>(board, add_feature)
+            }
+        }
+
+        features.sort_unstable();
+
+        let l1_out = self.l1.forward_sparse_batch(&features, boards.len());
+        #[cfg(feature = "moves-left")]
+        let moves_left_l1_out = self.moves_left_l1.forward_sparse_batch(&features, boards.len());
+
+        let out = self.l2.forward_batch(&l1_out);
+
+        #[cfg(feature = "moves-left")]
+        let moves_left: Vec<f32> = self
+            .moves_left_l2
+            .forward_batch(&moves_left_l1_out)
+            .iter()
+            .map(Self::moves_left)
+            .collect();
+        #[cfg(not(feature = "moves-left"))]
+        let moves_left = vec![0.0; boards.len()];
+
+        out.iter()
+            .zip(moves_left)
+            .map(|(out, moves_left)| Self::outputs(out, moves_left))
+            .collect()
+    }
+
+    #[cfg(feature = "moves-left")]
+    fn moves_left(moves_left_out: &Accumulator<1>) -> f32 {
+        Self::MAX_MOVES_LEFT / (1.0 + (-moves_left_out.values()[0]).exp())
+    }
+
+    fn outputs(out: &Accumulator<3>, moves_left: f32) -> (f32, f32, f32, f32) {
         let mut win_chance = out.values()[2];
         let mut draw_chance = out.values()[1];
         let mut loss_chance = out.values()[0];
@@ -46,7 +137,7 @@ impl ValueNetwork {
 
         let sum = win_chance + draw_chance + loss_chance;
 
-        (win_chance / sum, draw_chance / sum, loss_chance / sum)
+        (win_chance / sum, draw_chance / sum, loss_chance / sum, moves_left)
     }
 
     fn map_value_inputs<F: FnMut(usize), const STM_WHITE: bool, const NSTM_WHITE: bool>(
@@ -60,6 +151,7 @@ impl ValueNetwork {
         };
         
         let flip = board.side_to_move() == Side::BLACK;
+        let bucket_offset = 768 * 4 * Self::halfmove_bucket(board.half_move_counter() as usize);
 
         let mut threats = board.generate_attack_map::<STM_WHITE, NSTM_WHITE>();
         let mut defences = board.generate_attack_map::<NSTM_WHITE, STM_WHITE>();
@@ -83,7 +175,7 @@ impl ValueNetwork {
             }
 
             stm_bitboard.map(|square| {
-                let mut feat = piece_index + (square.get_raw() as usize ^ horizontal_mirror);
+                let mut feat = bucket_offset + piece_index + (square.get_raw() as usize ^ horizontal_mirror);
 
                 if threats.get_bit(square) {
                     feat += 768;
@@ -97,7 +189,7 @@ impl ValueNetwork {
             });
 
             nstm_bitboard.map(|square| {
-                let mut feat = 384 + piece_index + (square.get_raw() as usize ^ horizontal_mirror);
+                let mut feat = bucket_offset + 384 + piece_index + (square.get_raw() as usize ^ horizontal_mirror);
 
                 if threats.get_bit(square) {
                     feat += 768;
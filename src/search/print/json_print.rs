@@ -0,0 +1,100 @@
+use crate::{search::Score, Chess960, GameState, Tree};
+use spear::{ChessPosition, Move};
+
+use crate::{
+    options::EngineOptions,
+    search::{SearchInfo, SearchLimits},
+};
+
+use super::SearchDisplay;
+
+//Prints every search update as a single line JSON object, so it can be ingested by other tools
+pub struct JsonPrint {
+    root_position: ChessPosition,
+    chess960: bool,
+}
+#[allow(unused)]
+impl SearchDisplay for JsonPrint {
+    const REFRESH_RATE: f32 = 1.0;
+
+    fn new(position: &ChessPosition, engine_options: &EngineOptions, tree: &Tree) -> Self {
+        Self {
+            root_position: *position,
+            chess960: engine_options.chess960(),
+        }
+    }
+
+    fn print_search_raport<const FINAL: bool>(&mut self, search_info: &SearchInfo, search_limits: &SearchLimits) {
+        let mut pvs = Vec::new();
+        for (multi_pv_idx, pv) in search_info.pvs.iter().enumerate() {
+            if pv.moves.is_empty() {
+                break;
+            }
+
+            let score = match pv.mate() {
+                Some(moves) => format!("{{\"mate\":{}}}", moves),
+                None => format!("{{\"cp\":{}}}", pv.cp()),
+            };
+
+            let state = match pv.state {
+                GameState::Unresolved => "unresolved",
+                GameState::Drawn => "drawn",
+                GameState::Won(_) => "lost",
+                GameState::Lost(_) => "won",
+            };
+
+            let moves = Chess960::pv_to_string(&self.root_position, &pv.moves, self.chess960)
+                .split_whitespace()
+                .map(|mv| format!("\"{}\"", mv))
+                .collect::<Vec<String>>()
+                .join(",");
+
+            let (win, draw, lose) = pv.wdl();
+            pvs.push(format!(
+                "{{\"multipv\":{},\"score\":{},\"wdl\":[{},{},{}],\"state\":\"{}\",\"moves\":[{}]}}",
+                multi_pv_idx + 1,
+                score,
+                win,
+                draw,
+                lose,
+                state,
+                moves
+            ));
+        }
+
+        println!(
+            "{{\"type\":\"info\",\"final\":{},\"depth\":{},\"seldepth\":{},\"nodes\":{},\"nps\":{},\"hashfull\":{},\"tbhits\":{},\"created\":{},\"evaluations\":{},\"hash\":{{\"hits\":{},\"misses\":{},\"collisions\":{}}},\"time\":{},\"movesleft\":{},\"pvs\":[{}]}}",
+            FINAL,
+            search_info.depth,
+            search_info.seldepth,
+            search_info.nodes,
+            search_info.nps,
+            search_info.hashfull,
+            search_info.tbhits,
+            search_info.created_nodes,
+            search_info.evaluations,
+            search_info.hash_hits,
+            search_info.hash_misses,
+            search_info.hash_collisions,
+            search_info.time,
+            search_info.moves_left,
+            pvs.join(",")
+        )
+    }
+
+    fn print_search_result(&self, mv: Move, ponder_move: Move, score: Score) {
+        let board = self.root_position.board();
+        let ponder = if ponder_move == Move::NULL {
+            "null".to_string()
+        } else {
+            let pv_string = Chess960::pv_to_string(&self.root_position, &[mv, ponder_move], self.chess960);
+            format!("\"{}\"", pv_string.split_whitespace().nth(1).unwrap_or_default())
+        };
+
+        println!(
+            "{{\"type\":\"bestmove\",\"move\":\"{}\",\"ponder\":{}}}",
+            Chess960::move_to_string(mv, board, self.chess960),
+            ponder
+        )
+    }
+}
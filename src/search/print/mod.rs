@@ -1,9 +1,13 @@
+mod json_print;
 mod no_print;
 mod pretty_print;
 mod search_display_trait;
 mod uci_print;
+mod xboard_print;
 
+pub use json_print::JsonPrint;
 pub use no_print::NoPrint;
 pub use pretty_print::PrettyPrint;
 pub use search_display_trait::SearchDisplay;
 pub use uci_print::UciPrint;
+pub use xboard_print::XboardPrint;
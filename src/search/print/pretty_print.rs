@@ -5,9 +5,9 @@ use spear::{ChessPosition, Move, StringUtils};
 use crate::{
     clear_terminal_screen,
     color_config::{ColorConfig, Colored},
-    search::Score,
+    search::{Score, SearchInfo},
     utils::{heat_color, lerp_color},
-    EngineOptions, GameState, SearchLimits, SearchStats, Tree,
+    EngineOptions, GameState, SearchLimits, Tree,
 };
 
 use super::SearchDisplay;
@@ -39,28 +39,17 @@ impl SearchDisplay for PrettyPrint {
 
         Self {
             start_height,
-            max_history_size: term_height - term_height.min(start_height as usize + 19),
+            max_history_size: term_height - term_height.min(start_height as usize + 21),
             history: Vec::new(),
             last_best_move: Move::NULL,
         }
     }
 
     #[allow(clippy::needless_range_loop)]
-    fn print_search_raport<const FINAL: bool>(
-        &mut self,
-        search_stats: &SearchStats,
-        engine_options: &EngineOptions,
-        search_limits: &SearchLimits,
-        usage: f32,
-        pvs: &Vec<(Score, GameState, Vec<Move>)>
-    ) {
-        let (mut score, state, pv) = &pvs[0];
-        score = match *state {
-            GameState::Drawn => Score::DRAW,
-            GameState::Won(x) => Score::LOSE,
-            GameState::Lost(x) => Score::WIN,
-            _ => score,
-        };
+    fn print_search_raport<const FINAL: bool>(&mut self, search_info: &SearchInfo, search_limits: &SearchLimits) {
+        let best_pv = &search_info.pvs[0];
+        let (score, state, pv) = (best_pv.score, best_pv.state, &best_pv.moves);
+        let usage = search_info.hashfull as f32 / 1000.0;
 
         term_cursor::set_pos(0, self.start_height).expect("Cannot move curser to the position");
 
@@ -68,36 +57,49 @@ impl SearchDisplay for PrettyPrint {
         println!(" {} {}\n", "Tree Usage:".label(), usage_bar(50, usage));
 
         print!("                                    \r");
-        println!(" {} {}", "Avg. Depth:".label(), search_stats.avg_depth());
+        println!(" {} {}", "Avg. Depth:".label(), search_info.depth);
         print!("                                    \r");
-        println!(" {}  {}\n", "Max Depth:".label(), search_stats.max_depth());
+        println!(" {}  {}\n", "Max Depth:".label(), search_info.seldepth);
 
         print!("                                    \r");
         println!(
             " {}      {}",
             "Nodes:".label(),
-            StringUtils::large_number_to_string(search_stats.iters() as u128)
+            StringUtils::large_number_to_string(search_info.nodes as u128)
         );
         print!("                                    \r");
         println!(
             " {}       {}",
             "Time:".label(),
-            StringUtils::time_to_string(search_stats.time_passed() as u128)
+            StringUtils::time_to_string(search_info.time as u128)
         );
         print!("                                    \r");
-        let nps = search_stats.iters() as u64 * 1000 / search_stats.time_passed().max(1);
         println!(
-            " {}        {}\n",
+            " {}        {}",
             "Nps:".label(),
-            StringUtils::large_number_to_string(nps as u128)
+            StringUtils::large_number_to_string(search_info.nps as u128)
+        );
+        print!("                                    \r");
+        println!(
+            " {}      {}",
+            "Evals:".label(),
+            StringUtils::large_number_to_string(search_info.evaluations as u128)
+        );
+        print!("                                                                      \r");
+        println!(
+            " {}       {} hits, {} misses, {} collisions\n",
+            "Hash:".label(),
+            StringUtils::large_number_to_string(search_info.hash_hits as u128),
+            StringUtils::large_number_to_string(search_info.hash_misses as u128),
+            StringUtils::large_number_to_string(search_info.hash_collisions as u128)
         );
 
         print!("                                    \r");
-        let score_cp = score.as_cp_f32_with_contempt(0.0);
-        let mut score_cp_string = match *state {
-            GameState::Drawn => "+0.0".to_string(),
-            GameState::Won(x) => format!("-M{}", ((x+1) as f32 / 2.0).ceil() as u32),
-            GameState::Lost(x) => format!("+M{}", ((x+1) as f32 / 2.0).ceil() as u32),
+        let score_cp = best_pv.cp() as f32 / 100.0;
+        let mut score_cp_string = match (state, best_pv.mate()) {
+            (GameState::Drawn, _) => "+0.0".to_string(),
+            (_, Some(moves)) if moves < 0 => format!("-M{}", -moves),
+            (_, Some(moves)) => format!("+M{}", moves),
             _ => if score_cp >= 0.0 {
                 format!("+{:.2}", score_cp)
             } else {
@@ -136,7 +138,7 @@ impl SearchDisplay for PrettyPrint {
                 println!("                                                                                                             ", );
             }
 
-            term_cursor::set_pos(0, self.start_height + 13)
+            term_cursor::set_pos(0, self.start_height + 15)
                 .expect("Cannot move curser to the position");
         }
 
@@ -166,11 +168,11 @@ impl SearchDisplay for PrettyPrint {
         if !pv.is_empty() && pv[0] != self.last_best_move {
             self.last_best_move = pv[0];
             self.history
-                .push((search_stats.time_passed() as u128, pv_string));
+                .push((search_info.time as u128, pv_string));
         }
     }
 
-    fn print_search_result(&self, mv: Move, score: Score) {
+    fn print_search_result(&self, mv: Move, ponder_move: Move, score: Score) {
         println!("Best Move: {}", format!("{mv}").highlight())
     }
 }
@@ -1,9 +1,9 @@
-use crate::{search::Score, GameState, Tree};
+use crate::{search::Score, Tree};
 use spear::{ChessPosition, Move};
 
 use crate::{
     options::EngineOptions,
-    search::{search_limits::SearchLimits, SearchStats},
+    search::{search_limits::SearchLimits, SearchInfo},
 };
 
 #[allow(unused)]
@@ -11,15 +11,6 @@ pub trait SearchDisplay: Send + Sync {
     const REFRESH_RATE: f32;
 
     fn new(position: &ChessPosition, engine_options: &EngineOptions, tree: &Tree) -> Self;
-    #[allow(clippy::too_many_arguments)]
-    fn print_search_raport<const FINAL: bool>(
-        &mut self,
-        search_stats: &SearchStats,
-        engine_options: &EngineOptions,
-        search_limits: &SearchLimits,
-        usage: f32,
-        pvs: &Vec<(Score, GameState, Vec<Move>)>
-    ) {
-    }
-    fn print_search_result(&self, mv: Move, score: Score) {}
+    fn print_search_raport<const FINAL: bool>(&mut self, search_info: &SearchInfo, search_limits: &SearchLimits) {}
+    fn print_search_result(&self, mv: Move, ponder_move: Move, score: Score) {}
 }
@@ -1,79 +1,90 @@
-use crate::{search::Score, GameState, Tree};
+use crate::{search::Score, Chess960, Tree, ValueNetwork};
 use spear::{ChessPosition, Move};
 
 use crate::{
     options::EngineOptions,
-    search::{SearchLimits, SearchStats},
+    search::{SearchInfo, SearchLimits},
 };
 
 use super::SearchDisplay;
 
-pub struct UciPrint;
+pub struct UciPrint {
+    root_position: ChessPosition,
+    chess960: bool,
+    show_wdl: bool,
+    show_moves_left: bool,
+}
 #[allow(unused)]
 impl SearchDisplay for UciPrint {
     const REFRESH_RATE: f32 = 1.0;
 
     fn new(position: &ChessPosition, engine_options: &EngineOptions, tree: &Tree) -> Self {
-        Self
+        Self {
+            root_position: *position,
+            chess960: engine_options.chess960(),
+            show_wdl: engine_options.show_wdl(),
+            show_moves_left: engine_options.show_moves_left() && ValueNetwork::HAS_MOVES_LEFT,
+        }
     }
 
-    fn print_search_raport<const FINAL: bool>(
-        &mut self,
-        search_stats: &SearchStats,
-        engine_options: &EngineOptions,
-        search_limits: &SearchLimits,
-        usage: f32,
-        pvs: &Vec<(Score, GameState, Vec<Move>)>
-    ) {
-        for multi_pv_idx in 0..pvs.len() {
-            let (mut score, state, pv) = &pvs[multi_pv_idx];
-            score = match *state {
-                GameState::Drawn => Score::DRAW,
-                GameState::Won(x) => Score::LOSE,
-                GameState::Lost(x) => Score::WIN,
-                _ => score,
-            };
-
-            if pv.is_empty() {
-                return;
+    fn print_search_raport<const FINAL: bool>(&mut self, search_info: &SearchInfo, search_limits: &SearchLimits) {
+        for (multi_pv_idx, pv) in search_info.pvs.iter().enumerate() {
+            if pv.moves.is_empty() {
+                break;
             }
 
-            let mut pv_string = String::new();
-            for mv in pv {
-                pv_string.push_str(format!("{} ", mv).as_str())
-            }
+            let pv_string = Chess960::pv_to_string(&self.root_position, &pv.moves, self.chess960);
 
-            let mut score_text = match *state {
-                GameState::Drawn => "score cp 0".to_string(),
-                GameState::Won(x) => format!("score mate -{}", ((x+1) as f32 / 2.0).ceil() as u32),
-                GameState::Lost(x) => format!("score mate {}", ((x+1) as f32 / 2.0).ceil() as u32),
-                _ => format!("score cp {}", score.as_cp_with_contempt(0.0)),
+            let mut score_text = match pv.mate() {
+                Some(moves) => format!("score mate {}", moves),
+                None => format!("score cp {}", pv.cp()),
             };
-    
-            if engine_options.show_wdl() {
-                score_text.push_str(&format!(
-                    " wdl {} {} {}",
-                    (score.win_chance() * 1000.0) as u32,
-                    (score.draw_chance() * 1000.0) as u32,
-                    (score.lose_chance() * 1000.0) as u32
-                ));
+
+            if self.show_wdl {
+                let (win, draw, lose) = pv.wdl();
+                score_text.push_str(&format!(" wdl {} {} {}", win, draw, lose));
+            }
+
+            if self.show_moves_left {
+                score_text.push_str(&format!(" movesleft {}", search_info.moves_left));
             }
 
             println!(
-                "info depth {} seldepth {} {} time {} nodes {} nps {} hashfull {} multipv {} pv {}",
-                search_stats.avg_depth(),
-                search_stats.max_depth(),
+                "info depth {} seldepth {} {} time {} nodes {} nps {} hashfull {} tbhits {} multipv {} pv {}",
+                search_info.depth,
+                search_info.seldepth,
                 score_text,
-                search_stats.time_passed() as u128,
-                search_stats.iters() as u128,
-                search_stats.iters() as u128 * 1000 / search_stats.time_passed().max(1) as u128,
-                (usage * 1000.0) as u32,
+                search_info.time,
+                search_info.nodes,
+                search_info.nps,
+                search_info.hashfull,
+                search_info.tbhits,
                 multi_pv_idx + 1,
                 pv_string
             )
         }
+
+        //Counters have no standard info field, so they are sent as strings with every report
+        println!(
+            "info string iterations {} nodes created {} evaluations {}",
+            search_info.nodes,
+            search_info.created_nodes,
+            search_info.evaluations
+        );
+        println!(
+            "info string hash hits {} misses {} collisions {}",
+            search_info.hash_hits,
+            search_info.hash_misses,
+            search_info.hash_collisions
+        )
     }
-    fn print_search_result(&self, mv: Move, score: Score) {
-        println!("bestmove {}", mv)
+    fn print_search_result(&self, mv: Move, ponder_move: Move, score: Score) {
+        if ponder_move == Move::NULL {
+            println!("bestmove {}", Chess960::move_to_string(mv, self.root_position.board(), self.chess960))
+        } else {
+            let pv_string = Chess960::pv_to_string(&self.root_position, &[mv, ponder_move], self.chess960);
+            let moves: Vec<&str> = pv_string.split_whitespace().collect();
+            println!("bestmove {} ponder {}", moves[0], moves[1])
+        }
     }
 }
@@ -0,0 +1,80 @@
+use crate::{search::Score, Chess960, Tree};
+use spear::{ChessPosition, Move};
+
+use crate::{
+    options::EngineOptions,
+    search::{SearchInfo, SearchLimits},
+};
+
+use super::SearchDisplay;
+
+//POST enables thinking output, which is toggled with 'post' and 'nopost' commands
+pub struct XboardPrint<const POST: bool> {
+    root_position: ChessPosition,
+    chess960: bool,
+    analyze: bool,
+}
+#[allow(unused)]
+impl<const POST: bool> SearchDisplay for XboardPrint<POST> {
+    const REFRESH_RATE: f32 = 1.0;
+
+    fn new(position: &ChessPosition, engine_options: &EngineOptions, tree: &Tree) -> Self {
+        Self {
+            root_position: *position,
+            chess960: engine_options.chess960(),
+            analyze: false,
+        }
+    }
+
+    fn print_search_raport<const FINAL: bool>(&mut self, search_info: &SearchInfo, search_limits: &SearchLimits) {
+        //Infinite search is only used in analyze mode, where we don't send the move
+        self.analyze = search_limits.is_infinite();
+
+        if !POST {
+            return;
+        }
+
+        let pv = &search_info.pvs[0];
+        if pv.moves.is_empty() {
+            return;
+        }
+
+        //Mate scores are reported as 100000 + moves to mate
+        let score = match pv.mate() {
+            Some(moves) => (100000 + moves.abs()) * moves.signum(),
+            None => pv.cp(),
+        };
+
+        let pv_string = Chess960::pv_to_string(&self.root_position, &pv.moves, self.chess960);
+
+        println!(
+            "{} {} {} {} {}",
+            search_info.depth,
+            score,
+            search_info.time / 10,
+            search_info.nodes,
+            pv_string.trim_end()
+        );
+
+        //Lines starting with '#' are ignored by the GUI, so counters are sent as debug output once
+        //the search ends
+        if FINAL {
+            println!(
+                "# iterations {} nodes created {} evaluations {} tbhits {} hash hits {} misses {} collisions {}",
+                search_info.nodes,
+                search_info.created_nodes,
+                search_info.evaluations,
+                search_info.tbhits,
+                search_info.hash_hits,
+                search_info.hash_misses,
+                search_info.hash_collisions
+            )
+        }
+    }
+
+    fn print_search_result(&self, mv: Move, ponder_move: Move, score: Score) {
+        if !self.analyze && mv != Move::NULL {
+            println!("move {}", Chess960::move_to_string(mv, self.root_position.board(), self.chess960))
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use spear::{ChessPosition, Move, Side};
+
+use crate::{options::EngineOptions, GameState};
+
+use super::{networks::ValueNetwork, tree::Tree, Score, SearchStats};
+
+//Snapshot of the search state, consumed by search printers and progress subscribers
+#[derive(Clone)]
+pub struct SearchInfo {
+    pub depth: u32,
+    pub seldepth: u32,
+    pub nodes: u64,
+    pub nps: u64,
+    pub hashfull: u32,
+    pub time: u64,
+    pub created_nodes: u64,
+    pub evaluations: u64,
+    pub tbhits: u64,
+    pub hash_hits: u64,
+    pub hash_misses: u64,
+    pub hash_collisions: u64,
+    pub moves_left: u32,
+    pub pvs: Vec<PvInfo>,
+}
+
+#[derive(Clone)]
+pub struct PvInfo {
+    pub score: Score,
+    pub state: GameState,
+    pub moves: Vec<Move>,
+}
+
+impl SearchInfo {
+    //Moves left don't change during the search, so they are estimated once and passed to every report
+    pub fn new(stats: &SearchStats, tree: &Tree, options: &EngineOptions, moves_left: u32) -> Self {
+        let pvs = tree
+            .get_pvs(options.multi_pv(), options.draw_contempt())
+            .into_iter()
+            .map(|(score, state, moves)| PvInfo::new(score, state, moves))
+            .collect();
+
+        Self {
+            depth: stats.avg_depth(),
+            seldepth: stats.max_depth(),
+            nodes: stats.iters(),
+            nps: stats.iters() * 1000 / stats.time_passed().max(1),
+            hashfull: (tree.total_usage() * 1000.0) as u32,
+            time: stats.time_passed(),
+            created_nodes: stats.nodes(),
+            evaluations: stats.evaluations(),
+            tbhits: stats.tbhits(),
+            hash_hits: stats.hash_hits(),
+            hash_misses: stats.hash_misses(),
+            hash_collisions: stats.hash_collisions(),
+            moves_left,
+            pvs,
+        }
+    }
+
+    //Moves left are estimated by the network in the root position
+    pub fn estimate_moves_left(root_position: &ChessPosition) -> u32 {
+        let board = root_position.board();
+        let (_, _, _, moves_left) = if board.side_to_move() == Side::WHITE {
+            ValueNetwork.forward::<true, false>(board)
+        } else {
+            ValueNetwork.forward::<false, true>(board)
+        };
+
+        moves_left.round() as u32
+    }
+}
+
+impl PvInfo {
+    const TABLEBASE_CP: i32 = 20000;
+
+    //State of the pv is the state of the child node, so it's seen from the opponent's perspective.
+    //Score of the terminal pv is replaced with the certain result
+    fn new(score: Score, state: GameState, moves: Vec<Move>) -> Self {
+        let score = match state {
+            GameState::Drawn => Score::DRAW,
+            GameState::Won(_) => Score::LOSE,
+            GameState::Lost(_) => Score::WIN,
+            GameState::Unresolved => score,
+        };
+
+        Self { score, state, moves }
+    }
+
+    //Returns moves to mate, negative when we are getting mated
+    pub fn mate(&self) -> Option<i32> {
+        let moves = ((self.state.mate_plies()? as f32 + 1.0) / 2.0).ceil() as i32;
+        match self.state {
+            GameState::Won(_) => Some(-moves),
+            _ => Some(moves),
+        }
+    }
+
+    //Tablebase wins are reported with fixed score, because the certain score has no cp value
+    pub fn cp(&self) -> i32 {
+        match self.state {
+            GameState::Drawn => 0,
+            GameState::Lost(_) if self.state.is_tablebase_result() => Self::TABLEBASE_CP,
+            GameState::Won(_) if self.state.is_tablebase_result() => -Self::TABLEBASE_CP,
+            _ => self.score.as_cp_with_contempt(0.0),
+        }
+    }
+
+    //Win, draw and lose chances in permille
+    pub fn wdl(&self) -> (u32, u32, u32) {
+        (
+            (self.score.win_chance() * 1000.0) as u32,
+            (self.score.draw_chance() * 1000.0) as u32,
+            (self.score.lose_chance() * 1000.0) as u32,
+        )
+    }
+}
@@ -1,6 +1,10 @@
-use crate::options::EngineOptions;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 
-use super::{SearchStats, Tree};
+use spear::Move;
+
+use crate::{options::EngineOptions, GameState};
+
+use super::{SearchStats, StrengthLimit, Tree};
 
 #[derive(Default)]
 pub struct SearchLimits {
@@ -9,11 +13,15 @@ pub struct SearchLimits {
     moves_to_go: Option<u32>,
     move_time: Option<u64>,
     max_depth: Option<u32>,
-    max_iters: Option<u32>,
+    max_iters: Option<u64>,
+    max_mate: Option<u32>,
     infinite: bool,
     game_ply: u32,
     soft_limit: Option<u64>,
     hard_limit: Option<u64>,
+    search_moves: Vec<Move>,
+    ponder: AtomicBool,
+    ponder_hit_time: AtomicU64,
 }
 impl SearchLimits {
     pub fn new(game_ply: u32) -> Self {
@@ -24,10 +32,14 @@ impl SearchLimits {
             move_time: None,
             max_depth: None,
             max_iters: None,
+            max_mate: None,
             infinite: false,
             game_ply,
             soft_limit: None,
-            hard_limit: None
+            hard_limit: None,
+            search_moves: Vec::new(),
+            ponder: AtomicBool::new(false),
+            ponder_hit_time: AtomicU64::new(0),
         }
     }
 
@@ -51,14 +63,49 @@ impl SearchLimits {
         self.max_depth = Some(depth);
     }
 
-    pub fn add_iters(&mut self, iters: u32) {
+    pub fn add_iters(&mut self, iters: u64) {
         self.max_iters = Some(iters);
     }
 
+    pub fn add_mate(&mut self, moves: u32) {
+        self.max_mate = Some(moves);
+    }
+
+    pub fn mate(&self) -> Option<u32> {
+        self.max_mate
+    }
+
+    pub fn add_search_move(&mut self, mv: Move) {
+        self.search_moves.push(mv);
+    }
+
+    //Returns list of moves the root is restricted to, empty list means all legal moves are allowed
+    pub fn search_moves(&self) -> &[Move] {
+        &self.search_moves
+    }
+
     pub fn go_infinite(&mut self) {
         self.infinite = true;
     }
 
+    pub fn is_infinite(&self) -> bool {
+        self.infinite
+    }
+
+    pub fn go_ponder(&mut self) {
+        self.ponder.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_pondering(&self) -> bool {
+        self.ponder.load(Ordering::Relaxed)
+    }
+
+    //Switches pondering search into normal search, limits are counted from the moment of ponderhit
+    pub fn ponder_hit(&self, time_passed: u64) {
+        self.ponder_hit_time.store(time_passed, Ordering::Relaxed);
+        self.ponder.store(false, Ordering::Relaxed);
+    }
+
     pub fn calculate_time_limits(&mut self) {
         if let Some(time) = self.time_remaining {
             let (soft, hard) = Self::search_time(time, self.increment, self.moves_to_go, self.game_ply);
@@ -67,11 +114,17 @@ impl SearchLimits {
         }
     }
 
-    pub fn is_limit_reached(&self, search_stats: &SearchStats, _options: &EngineOptions) -> bool {
-        if self.infinite {
+    pub fn is_limit_reached(&self, search_stats: &SearchStats, options: &EngineOptions) -> bool {
+        if self.infinite || self.is_pondering() {
             return false;
         }
 
+        if let Some(strength_limit) = StrengthLimit::from_options(options) {
+            if search_stats.iters() >= u64::from(strength_limit.nodes()) {
+                return true;
+            }
+        }
+
         if let Some(max_depth) = self.max_depth {
             if search_stats.avg_depth() >= max_depth {
                 return true;
@@ -85,7 +138,7 @@ impl SearchLimits {
         }
 
         if let Some(time) = self.move_time {
-            if search_stats.time_passed() >= time {
+            if self.search_time_passed(search_stats) >= time {
                 return true;
             }
         }
@@ -93,9 +146,26 @@ impl SearchLimits {
         false
     }
 
+    //Checks if root is proven to be a mate within the requested amount of moves
+    pub fn is_mate_found(&self, root_state: GameState) -> bool {
+        if self.is_pondering() {
+            return false;
+        }
+
+        //Tablebase wins are not mates, so they don't end the mate search
+        match (self.max_mate, root_state, root_state.mate_plies()) {
+            (Some(max_mate), GameState::Won(_), Some(plies)) => (u32::from(plies) + 1) / 2 <= max_mate,
+            _ => false,
+        }
+    }
+
     pub fn is_hard_time_limit_reached(&self, search_stats: &SearchStats, options: &EngineOptions) -> bool {
+        if self.is_pondering() {
+            return false;
+        }
+
         if let Some(hard_limit) = self.hard_limit {
-            let time_passed = search_stats.time_passed() + options.move_overhead() as u64;
+            let time_passed = self.search_time_passed(search_stats) + options.move_overhead() as u64;
             if time_passed >= hard_limit {
                 return true;
             }
@@ -105,8 +175,12 @@ impl SearchLimits {
     }
 
     pub fn is_soft_time_limit_reached(&self, search_stats: &SearchStats, options: &EngineOptions, best_move_changes: &mut i32, previous_score: &mut f32, tree: &Tree) -> bool {
+        if self.is_pondering() {
+            return false;
+        }
+
         if let Some(soft_limit) = self.soft_limit {
-            let time_passed = search_stats.time_passed() + options.move_overhead() as u64;
+            let time_passed = self.search_time_passed(search_stats) + options.move_overhead() as u64;
 
             let best_move_score = tree[tree.root_index()].get_best_move(tree, options.draw_contempt()).1.as_cp_f32();
             let eval_diff = if *previous_score == f32::NEG_INFINITY {
@@ -143,6 +217,13 @@ impl SearchLimits {
         false
     }
 
+    //Time spent on our own clock, which excludes the time spent pondering before ponderhit
+    fn search_time_passed(&self, search_stats: &SearchStats) -> u64 {
+        search_stats
+            .time_passed()
+            .saturating_sub(self.ponder_hit_time.load(Ordering::Relaxed))
+    }
+
     fn search_time(time: u64, increment: Option<u64>, moves_to_go: Option<u32>, game_ply: u32) -> (u64, u64) {
         let inc = increment.unwrap_or_default();
 
@@ -176,3 +257,30 @@ impl SearchLimits {
         (soft_time, hard_time)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mate_search_stops_on_short_enough_mates() {
+        let mut limits = SearchLimits::new(0);
+        limits.add_mate(3);
+
+        assert!(limits.is_mate_found(GameState::Won(1)));
+        assert!(limits.is_mate_found(GameState::Won(5)));
+        assert!(!limits.is_mate_found(GameState::Won(7)));
+        assert!(!limits.is_mate_found(GameState::Lost(2)));
+        assert!(!limits.is_mate_found(GameState::Drawn));
+    }
+
+    #[test]
+    fn mate_search_ignores_tablebase_wins() {
+        let mut limits = SearchLimits::new(0);
+        limits.add_mate(100);
+
+        assert!(!limits.is_mate_found(GameState::Won(GameState::TABLEBASE_PLIES)));
+        assert!(!limits.is_mate_found(GameState::Won(193)));
+        assert!(limits.is_mate_found(GameState::Won(191)));
+    }
+}
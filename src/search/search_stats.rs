@@ -8,6 +8,13 @@ pub struct SearchStats {
     total_depth: AtomicU64,
     max_depth: AtomicU32,
     iters: AtomicU64,
+    nodes: AtomicU64,
+    evaluations: AtomicU64,
+    collisions: AtomicU64,
+    tbhits: AtomicU64,
+    hash_hits: AtomicU64,
+    hash_misses: AtomicU64,
+    hash_collisions: AtomicU64,
     time_passed: AtomicU64,
 }
 
@@ -24,6 +31,13 @@ impl SearchStats {
             total_depth: AtomicU64::new(0),
             max_depth: AtomicU32::new(0),
             iters: AtomicU64::new(0),
+            nodes: AtomicU64::new(0),
+            evaluations: AtomicU64::new(0),
+            collisions: AtomicU64::new(0),
+            tbhits: AtomicU64::new(0),
+            hash_hits: AtomicU64::new(0),
+            hash_misses: AtomicU64::new(0),
+            hash_collisions: AtomicU64::new(0),
             time_passed: AtomicU64::new(0),
         }
     }
@@ -33,22 +47,83 @@ impl SearchStats {
     }
 
     pub fn avg_depth(&self) -> u32 {
-        (self.total_depth.load(Ordering::Relaxed) / self.iters() as u64) as u32
+        (self.total_depth.load(Ordering::Relaxed) / self.iters().max(1)) as u32
     }
 
     pub fn max_depth(&self) -> u32 {
         self.max_depth.load(Ordering::Relaxed)
     }
 
-    pub fn iters(&self) -> u32 {
-        self.iters.load(Ordering::Relaxed) as u32
+    pub fn iters(&self) -> u64 {
+        self.iters.load(Ordering::Relaxed)
+    }
+
+    //Amount of new nodes added to the tree during the search
+    pub fn nodes(&self) -> u64 {
+        self.nodes.load(Ordering::Relaxed)
+    }
+
+    //Amount of value network evaluations, positions found in hash table are not counted
+    pub fn evaluations(&self) -> u64 {
+        self.evaluations.load(Ordering::Relaxed)
+    }
+
+    //Amount of iterations, that reached the leaf already being evaluated by another thread
+    pub fn collisions(&self) -> u64 {
+        self.collisions.load(Ordering::Relaxed)
+    }
+
+    //Amount of new nodes, whose result was found in bitbases or Syzygy tablebases
+    pub fn tbhits(&self) -> u64 {
+        self.tbhits.load(Ordering::Relaxed)
+    }
+
+    //Value hash table probes, that found the position and that didn't
+    pub fn hash_hits(&self) -> u64 {
+        self.hash_hits.load(Ordering::Relaxed)
+    }
+
+    pub fn hash_misses(&self) -> u64 {
+        self.hash_misses.load(Ordering::Relaxed)
+    }
+
+    //Amount of stores, that replaced an entry of another position from the current search
+    pub fn hash_collisions(&self) -> u64 {
+        self.hash_collisions.load(Ordering::Relaxed)
+    }
+
+    pub fn add_node(&self) {
+        self.nodes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn add_evaluation(&self) {
+        self.evaluations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn add_collision(&self) {
+        self.collisions.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn add_tbhit(&self) {
+        self.tbhits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn add_hash_hit(&self) {
+        self.hash_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn add_hash_miss(&self) {
+        self.hash_misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn add_hash_collision(&self) {
+        self.hash_collisions.fetch_add(1, Ordering::Relaxed);
     }
 
     pub fn add_iteration(&self, depth: u32) {
         self.iters.fetch_add(1, Ordering::Relaxed);
         self.total_depth.fetch_add(depth as u64, Ordering::Relaxed);
-        self.max_depth
-            .store(self.max_depth().max(depth), Ordering::Relaxed);
+        self.max_depth.fetch_max(depth, Ordering::Relaxed);
     }
 
     pub fn update_time_passed(&self) {
@@ -9,7 +9,9 @@ pub struct Edge {
     policy: AtomicI16,
     visits: AtomicU32,
     score: AtomicScore,
-    squared_score: AtomicU32
+    squared_score: AtomicU32,
+    //Threads waiting for the repetition leaf behind this edge, other leaves count them in their node
+    threads: AtomicU16,
 }
 
 impl Clone for Edge {
@@ -21,6 +23,7 @@ impl Clone for Edge {
             visits: AtomicU32::new(self.visits()),
             score: self.score.clone(),
             squared_score: AtomicU32::new(self.squared_score.load(Ordering::Relaxed)),
+            threads: AtomicU16::new(self.threads()),
         }
     }
 }
@@ -40,9 +43,23 @@ impl Edge {
             visits: AtomicU32::new(0),
             score: AtomicScore::default(),
             squared_score: AtomicU32::new(0),
+            threads: AtomicU16::new(0),
         }
     }
 
+    //Edge with already accumulated statistics, used when the parent edge is not known
+    pub fn with_stats(score: Score, visits: u32, squared_score: f64) -> Self {
+        let edge = Self::default();
+        edge.set_stats(score, visits, squared_score);
+        edge
+    }
+
+    pub fn set_stats(&self, score: Score, visits: u32, squared_score: f64) {
+        self.visits.store(visits, Ordering::Relaxed);
+        self.score.store(score);
+        self.squared_score.store((squared_score.clamp(0.0, 1.0) * f64::from(u32::MAX)) as u32, Ordering::Relaxed);
+    }
+
     #[inline]
     pub fn clear(&self) {
         self.replace(NodeIndex::NULL, Move::NULL, 1.0);
@@ -55,6 +72,7 @@ impl Edge {
         self.update_policy(policy);
         self.visits.store(0, Ordering::Relaxed);
         self.score.store(Score::default());
+        self.threads.store(0, Ordering::Relaxed);
     }
 
     #[inline]
@@ -87,6 +105,21 @@ impl Edge {
         self.score.load()
     }
 
+    #[inline]
+    pub fn threads(&self) -> u16 {
+        self.threads.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    pub fn inc_threads(&self) -> u16 {
+        self.threads.fetch_add(1, Ordering::Relaxed)
+    }
+
+    #[inline]
+    pub fn dec_threads(&self) -> u16 {
+        self.threads.fetch_sub(1, Ordering::Relaxed)
+    }
+
     pub fn squared_score(&self) -> f64 {
         f64::from(self.squared_score.load(Ordering::Relaxed)) / f64::from(u32::MAX)
     }
@@ -14,6 +14,8 @@ impl Edge {
     ) {
         let terminal_string = match state {
             GameState::Drawn => "   terminal draw".highlight_alt(),
+            GameState::Lost(_) if state.is_tablebase_result() => "   tablebase lose".highlight_alt(),
+            GameState::Won(_) if state.is_tablebase_result() => "   tablebase win".highlight_alt(),
             GameState::Lost(x) => format!("   terminal lose in {}", x).highlight_alt(),
             GameState::Won(x) => format!("   terminal win in {}", x).highlight_alt(),
             _ => "".to_string(),
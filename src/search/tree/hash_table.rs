@@ -1,41 +1,70 @@
-use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::atomic::{AtomicU32, AtomicU8, Ordering};
 
-use crate::search::{eval_score::AtomicScore, Score};
+use crate::{
+    search::{eval_score::AtomicScore, Score},
+    SearchStats,
+};
 
+const BUCKET_SIZE: usize = 4;
+
+//Lower half of the key is used to verify the entry, bucket index depends mostly on the upper half.
+//Generation 0 marks an empty entry
 pub struct HashTableEntry {
-    key: AtomicU16,
+    key: AtomicU32,
+    generation: AtomicU8,
     score: AtomicScore
 }
 
 impl HashTableEntry {
     pub fn new() -> Self {
-        Self { 
-            key: AtomicU16::new(0), 
-            score: AtomicScore::default() 
+        Self {
+            key: AtomicU32::new(0),
+            generation: AtomicU8::new(0),
+            score: AtomicScore::default()
         }
     }
 
-    pub fn replace(&self, key: u64, score: Score) {
-        self.key.store(key as u16, Ordering::Relaxed);
+    fn matches(&self, key: u64) -> bool {
+        self.generation.load(Ordering::Relaxed) != 0 && self.key.load(Ordering::Relaxed) == key as u32
+    }
+
+    pub fn replace(&self, key: u64, score: Score, generation: u8) {
+        self.key.store(key as u32, Ordering::Relaxed);
+        self.generation.store(generation, Ordering::Relaxed);
         self.score.store(score);
     }
 }
 
+//Entries of one bucket fill single cache line
+#[repr(C, align(64))]
+struct HashBucket {
+    entries: [HashTableEntry; BUCKET_SIZE]
+}
+
+impl HashBucket {
+    fn new() -> Self {
+        Self {
+            entries: std::array::from_fn(|_| HashTableEntry::new())
+        }
+    }
+}
+
 pub struct HashTable {
-    entries: Vec<HashTableEntry>
+    buckets: Vec<HashBucket>,
+    generation: AtomicU8
 }
 
 impl HashTable {
     pub fn new(size_in_bytes: usize) -> Self {
 
-        let size = size_in_bytes / 8;
-        let mut entries = Vec::with_capacity(size);
+        let size = (size_in_bytes / std::mem::size_of::<HashBucket>()).max(1);
+        let mut buckets = Vec::with_capacity(size);
 
         for _ in 0..size {
-            entries.push(HashTableEntry::new());
+            buckets.push(HashBucket::new());
         }
 
-        Self { entries }
+        Self { buckets, generation: AtomicU8::new(0) }
     }
 
     pub fn resize(&mut self, size_in_bytes: usize) {
@@ -43,28 +72,202 @@ impl HashTable {
     }
 
     pub fn clear(&mut self) {
-        let size = self.entries.len();
-        let mut entries = Vec::with_capacity(size);
+        *self = Self::new(self.buckets.len() * std::mem::size_of::<HashBucket>())
+    }
 
-        for _ in 0..size {
-            entries.push(HashTableEntry::new());
+    //Entries stored during previous searches become older and are replaced first
+    pub fn new_search(&self) {
+        let generation = self.generation.load(Ordering::Relaxed);
+        self.generation.store(generation.checked_add(1).unwrap_or(1), Ordering::Relaxed);
+    }
+
+    pub fn probe(&self, key: u64, stats: &SearchStats) -> Option<Score> {
+        let bucket = &self.buckets[self.index(key)];
+        let Some(entry) = bucket.entries.iter().find(|entry| entry.matches(key)) else {
+            stats.add_hash_miss();
+            return None;
+        };
+
+        //Entries used by the current search are kept as long as possible
+        entry.generation.store(self.generation(), Ordering::Relaxed);
+        stats.add_hash_hit();
+        Some(entry.score.load())
+    }
+
+    //Entry of the same position is updated, otherwise empty or the oldest entry is replaced.
+    //When all entries come from the current search, one of them is picked by the key
+    pub fn store(&self, key: u64, score: Score, stats: &SearchStats) {
+        let generation = self.generation();
+        let bucket = &self.buckets[self.index(key)];
+
+        if let Some(entry) = bucket.entries.iter().find(|entry| entry.matches(key)) {
+            entry.replace(key, score, generation);
+            return;
         }
 
-        *self = Self { entries }
+        let age = |entry: &HashTableEntry| Self::age(generation, entry.generation.load(Ordering::Relaxed));
+
+        let oldest = bucket
+            .entries
+            .iter()
+            .max_by_key(|entry| age(entry))
+            .filter(|entry| age(entry) > 0);
+
+        match oldest {
+            Some(entry) => entry.replace(key, score, generation),
+            None => {
+                stats.add_hash_collision();
+                bucket.entries[(key >> 32) as usize % BUCKET_SIZE].replace(key, score, generation)
+            }
+        }
     }
 
-    pub fn probe(&self, key: u64) -> Option<Score> {
-        let idx = (u128::from(key).wrapping_mul(self.entries.len() as u128) >> 64) as usize;
-        let entry = &self.entries[idx];
-        if entry.key.load(Ordering::Relaxed) != key as u16 {
-            return None;
+    //Generations cycle through 1..=255 skipping the empty marker, so the age is wrapping distance
+    //on that cycle. Entries exactly one cycle old look current and are only replaced as collisions
+    fn age(generation: u8, entry_generation: u8) -> u8 {
+        const GENERATIONS: u16 = u8::MAX as u16;
+        match entry_generation {
+            0 => u8::MAX,
+            _ => ((GENERATIONS + u16::from(generation) - u16::from(entry_generation)) % GENERATIONS) as u8,
         }
+    }
 
-        Some(entry.score.load())
+    fn generation(&self) -> u8 {
+        self.generation.load(Ordering::Relaxed).max(1)
+    }
+
+    fn index(&self, key: u64) -> usize {
+        (u128::from(key).wrapping_mul(self.buckets.len() as u128) >> 64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //Single bucket, so every key lands in the same one
+    fn single_bucket_table() -> HashTable {
+        let table = HashTable::new(std::mem::size_of::<HashBucket>());
+        table.new_search();
+        table
+    }
+
+    fn key(index: u64) -> u64 {
+        (index << 32) | (index + 1)
+    }
+
+    #[test]
+    fn counts_hits_and_misses() {
+        let table = single_bucket_table();
+        let stats = SearchStats::new();
+
+        assert_eq!(table.probe(key(0), &stats), None);
+        table.store(key(0), Score::WIN, &stats);
+        assert_eq!(table.probe(key(0), &stats), Some(Score::WIN));
+        assert_eq!(table.probe(key(1), &stats), None);
+
+        assert_eq!(stats.hash_hits(), 1);
+        assert_eq!(stats.hash_misses(), 2);
+        assert_eq!(stats.hash_collisions(), 0);
+    }
+
+    #[test]
+    fn updates_entry_of_the_same_position() {
+        let table = single_bucket_table();
+        let stats = SearchStats::new();
+
+        table.store(key(0), Score::WIN, &stats);
+        table.store(key(0), Score::DRAW, &stats);
+
+        assert_eq!(table.probe(key(0), &stats), Some(Score::DRAW));
+        assert_eq!(stats.hash_collisions(), 0);
+    }
+
+    #[test]
+    fn full_bucket_of_current_search_counts_collision() {
+        let table = single_bucket_table();
+        let stats = SearchStats::new();
+
+        for index in 0..BUCKET_SIZE as u64 {
+            table.store(key(index), Score::DRAW, &stats);
+        }
+        assert_eq!(stats.hash_collisions(), 0);
+
+        //Slot of the new entry is picked by the upper half of its key
+        let new_key = key(BUCKET_SIZE as u64 + 1);
+        table.store(new_key, Score::WIN, &stats);
+        assert_eq!(stats.hash_collisions(), 1);
+        assert_eq!(table.probe(new_key, &stats), Some(Score::WIN));
+
+        let replaced = ((new_key >> 32) as usize % BUCKET_SIZE) as u64;
+        for index in 0..BUCKET_SIZE as u64 {
+            let expected = if index == replaced { None } else { Some(Score::DRAW) };
+            assert_eq!(table.probe(key(index), &stats), expected);
+        }
+    }
+
+    #[test]
+    fn replaces_entries_of_previous_searches_first() {
+        let table = single_bucket_table();
+        let stats = SearchStats::new();
+
+        for index in 0..BUCKET_SIZE as u64 {
+            table.store(key(index), Score::DRAW, &stats);
+        }
+
+        //Probed entry is refreshed, so one of the others is replaced
+        table.new_search();
+        assert_eq!(table.probe(key(0), &stats), Some(Score::DRAW));
+        table.store(key(10), Score::WIN, &stats);
+
+        assert_eq!(stats.hash_collisions(), 0);
+        assert_eq!(table.probe(key(0), &stats), Some(Score::DRAW));
+        assert_eq!(table.probe(key(10), &stats), Some(Score::WIN));
+
+        let kept = (1..BUCKET_SIZE as u64).filter(|&index| table.probe(key(index), &stats).is_some()).count();
+        assert_eq!(kept, BUCKET_SIZE - 2);
+    }
+
+    #[test]
+    fn prefers_empty_entries() {
+        let table = single_bucket_table();
+        let stats = SearchStats::new();
+
+        table.store(key(0), Score::DRAW, &stats);
+        for _ in 0..100 {
+            table.new_search();
+        }
+
+        table.store(key(1), Score::WIN, &stats);
+        assert_eq!(table.probe(key(0), &stats), Some(Score::DRAW));
+        assert_eq!(table.probe(key(1), &stats), Some(Score::WIN));
     }
 
-    pub fn store(&self, key: u64, score: Score) {
-        let idx = (u128::from(key).wrapping_mul(self.entries.len() as u128) >> 64) as usize;
-        self.entries[idx].replace(key, score);
+    #[test]
+    fn age_wraps_with_generation() {
+        assert_eq!(HashTable::age(7, 7), 0);
+        assert_eq!(HashTable::age(7, 3), 4);
+        assert_eq!(HashTable::age(1, 255), 1);
+        assert_eq!(HashTable::age(2, 250), 7);
+        assert_eq!(HashTable::age(1, 0), u8::MAX);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn old_entries_are_replaced_after_generation_wraps() {
+        let table = single_bucket_table();
+        let stats = SearchStats::new();
+
+        for index in 0..BUCKET_SIZE as u64 {
+            table.store(key(index), Score::DRAW, &stats);
+        }
+
+        //Generation goes past 255 and starts again from 1
+        for _ in 0..300 {
+            table.new_search();
+        }
+
+        table.store(key(10), Score::WIN, &stats);
+        assert_eq!(stats.hash_collisions(), 0);
+        assert_eq!(table.probe(key(10), &stats), Some(Score::WIN));
+    }
+}
@@ -6,8 +6,10 @@ mod tree_lru;
 mod tree_reuse;
 mod tree_segment;
 mod hash_table;
+mod transposition_table;
 
 pub use edge::Edge;
 pub use node::Node;
 pub use node::NodeIndex;
 pub use tree_base::Tree;
+pub use tree_reuse::TreeReuse;
@@ -6,7 +6,7 @@ use std::sync::{
 use spear::{ChessBoard, ChessPosition, Move, Piece, Side};
 
 use crate::{
-    search::{tree::Edge, NodeIndex, Score}, EngineOptions, GameState, PolicyNetwork, Tree
+    search::{tree::Edge, NodeIndex, Score, SearchHelpers}, EngineOptions, GameState, PolicyNetwork, Tree
 };
 
 pub struct Node {
@@ -113,7 +113,12 @@ impl Node {
             return (Move::NULL, Score::DRAW);
         }
 
+        //Proven draw is reported with its exact score
         let edge_clone = &self.actions()[action_index];
+        if !edge_clone.node_index().is_null() && tree[edge_clone.node_index()].state() == GameState::Drawn {
+            return (edge_clone.mv(), Score::DRAW);
+        }
+
         (edge_clone.mv(), edge_clone.score())
     }
 
@@ -125,7 +130,7 @@ impl Node {
                 match tree[action.node_index()].state() {
                     GameState::Lost(n) => 1.0 + f32::from(n),
                     GameState::Won(n) => f32::from(n) - 256.0,
-                    GameState::Drawn => 0.5,
+                    GameState::Drawn => Score::DRAW.single(draw_contempt),
                     GameState::Unresolved => action.score().single(draw_contempt),
                 }
             } else {
@@ -152,7 +157,8 @@ impl Node {
     pub fn expand<const STM_WHITE: bool, const NSTM_WHITE: bool, const ROOT: bool>(
         &self,
         position: &ChessPosition,
-        options: &EngineOptions
+        options: &EngineOptions,
+        search_moves: &[Move]
     ) {
         let mut actions = self.actions_mut();
 
@@ -168,7 +174,7 @@ impl Node {
         };
 
         let pst = if ROOT {
-            options.root_pst()
+            SearchHelpers::get_root_pst(options)
         } else {
             1.0
         };
@@ -180,6 +186,10 @@ impl Node {
         position
             .board()
             .map_moves::<_, STM_WHITE, NSTM_WHITE>(|mv| {
+                if !is_move_allowed(mv, search_moves) {
+                    return;
+                }
+
                 let policy = PolicyNetwork.forward::<STM_WHITE, NSTM_WHITE>(position.board(), &inputs, mv, vertical_flip) + mva_lvv(mv, position.board(), options);
                 actions.push(Edge::new(
                     NodeIndex::from_raw((policy * MULTIPLIER) as u32),
@@ -214,13 +224,40 @@ impl Node {
         self.set_gini_impurity(gini_impurity);
     }
 
+    //Returns true, when the action list changed
     pub fn recalculate_policy<const STM_WHITE: bool, const NSTM_WHITE: bool, const ROOT: bool>(
         &self,
         position: &ChessPosition,
-        options: &EngineOptions
-    ) {
+        options: &EngineOptions,
+        search_moves: &[Move]
+    ) -> bool {
         let mut actions = self.actions_mut();
 
+        //Node could have been expanded with different move restriction (searchmoves), so we rebuild
+        //the action list keeping statistics of moves that are still allowed
+        let mut allowed_moves = Vec::new();
+        position.board().map_moves::<_, STM_WHITE, NSTM_WHITE>(|mv| {
+            if is_move_allowed(mv, search_moves) {
+                allowed_moves.push(mv)
+            }
+        });
+
+        let is_same_set = allowed_moves.len() == actions.len()
+            && actions.iter().all(|action| allowed_moves.contains(&action.mv()));
+        if !is_same_set {
+            let new_actions = allowed_moves
+                .iter()
+                .map(|&mv| {
+                    actions
+                        .iter()
+                        .find(|action| action.mv() == mv)
+                        .cloned()
+                        .unwrap_or_else(|| Edge::new(NodeIndex::NULL, mv, 0.0))
+                })
+                .collect();
+            *actions = new_actions;
+        }
+
         let mut inputs: Vec<usize> = Vec::with_capacity(32);
         PolicyNetwork::map_policy_inputs::<_, STM_WHITE, NSTM_WHITE>(position.board(), |idx| {
             inputs.push(idx)
@@ -233,7 +270,7 @@ impl Node {
         };
 
         let pst = if ROOT {
-            options.root_pst()
+            SearchHelpers::get_root_pst(options)
         } else {
             1.0
         };
@@ -262,9 +299,15 @@ impl Node {
         for (i, action) in actions.iter_mut().enumerate() {
             action.update_policy(policies[i] / total);
         }
+
+        !is_same_set
     }
 }
 
+fn is_move_allowed(mv: Move, search_moves: &[Move]) -> bool {
+    search_moves.is_empty() || search_moves.contains(&mv)
+}
+
 const MVA_LVV_PIECE_VALUES: [f32; 5] = [1.0, 3.0, 3.0, 5.0, 9.0];
 fn mva_lvv(mv: Move, board: &ChessBoard, options: &EngineOptions) -> f32 {
     let attacker = board.get_piece_on_square(mv.get_from_square());
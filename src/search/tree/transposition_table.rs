@@ -0,0 +1,78 @@
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+
+use crate::search::{eval_score::AtomicScore, Score};
+
+pub struct TranspositionEntry {
+    key: AtomicU64,
+    visits: AtomicU32,
+    score: AtomicScore
+}
+
+impl TranspositionEntry {
+    pub fn new() -> Self {
+        Self {
+            key: AtomicU64::new(0),
+            visits: AtomicU32::new(0),
+            score: AtomicScore::default()
+        }
+    }
+}
+
+//Statistics of all nodes sharing the same position, no matter which move order led to them.
+//Scores are stored from the perspective of the side that moved into the position, same as in edges
+pub struct TranspositionTable {
+    entries: Vec<TranspositionEntry>
+}
+
+impl TranspositionTable {
+    pub fn new(size_in_bytes: usize) -> Self {
+
+        let size = (size_in_bytes / 20).max(1);
+        let mut entries = Vec::with_capacity(size);
+
+        for _ in 0..size {
+            entries.push(TranspositionEntry::new());
+        }
+
+        Self { entries }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::new(self.entries.len() * 20)
+    }
+
+    pub fn probe(&self, key: u64) -> Option<(u32, Score)> {
+        let entry = &self.entries[self.index(key)];
+        if entry.key.load(Ordering::Relaxed) != key {
+            return None;
+        }
+
+        let visits = entry.visits.load(Ordering::Relaxed);
+        if visits == 0 {
+            return None;
+        }
+
+        Some((visits, entry.score.load()))
+    }
+
+    //Adds backpropagated score to the position statistics, entry of another position is replaced
+    pub fn add_score(&self, key: u64, score: Score) {
+        let entry = &self.entries[self.index(key)];
+        if entry.key.load(Ordering::Relaxed) != key {
+            entry.key.store(key, Ordering::Relaxed);
+            entry.visits.store(0, Ordering::Relaxed);
+            entry.score.store(Score::default());
+        }
+
+        let previous_visits = entry.visits.fetch_add(1, Ordering::Relaxed) as f64;
+        let old_score = entry.score.load();
+
+        let new_win_chance = (f64::from(old_score.win_chance()) * previous_visits + f64::from(score.win_chance())) / (previous_visits + 1.0);
+        let new_draw_chance = (f64::from(old_score.draw_chance()) * previous_visits + f64::from(score.draw_chance())) / (previous_visits + 1.0);
+        entry.score.store(Score::new(new_win_chance as f32, new_draw_chance as f32));
+    }
+
+    fn index(&self, key: u64) -> usize {
+        (u128::from(key).wrapping_mul(self.entries.len() as u128) >> 64) as usize
+    }
+}
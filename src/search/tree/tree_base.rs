@@ -5,7 +5,7 @@ use std::{
 
 use crate::{search::Score, GameState};
 
-use super::{hash_table::HashTable, tree_segment::TreeSegment};
+use super::{hash_table::HashTable, transposition_table::TranspositionTable, tree_segment::TreeSegment};
 use super::{node::NodeIndex, Edge, Node};
 use spear::Move;
 
@@ -15,8 +15,10 @@ pub struct Tree {
     pub(super) segments: [TreeSegment; SEGMENT_COUNT],
     pub(super) root_edge: Edge,
     pub(super) current_segment: AtomicUsize,
+    pub(super) previous_roots: Vec<(u64, NodeIndex)>,
     pub tree_size_in_bytes: usize,
-    hash_table: HashTable
+    hash_table: HashTable,
+    transposition_table: TranspositionTable
 }
 
 impl Index<NodeIndex> for Tree {
@@ -33,8 +35,10 @@ impl Tree {
     pub fn new(size_in_mb: i32, hash_percentage: f32) -> Self {
         let bytes = (size_in_mb as usize) * 1024 * 1024;
 
+        //Hash part of the memory is shared equally by value cache and transposition statistics
         let hash_bytes = (bytes as f32 * hash_percentage) as usize;
-        let hash_table = HashTable::new(hash_bytes);
+        let hash_table = HashTable::new(hash_bytes / 2);
+        let transposition_table = TranspositionTable::new(hash_bytes / 2);
 
         let tree_bytes = bytes - hash_bytes;
         let tree_size = tree_bytes / (56 + 20 * 24);
@@ -48,8 +52,10 @@ impl Tree {
             segments,
             root_edge: Edge::new(NodeIndex::from_raw(0), Move::NULL, 0.0),
             current_segment: AtomicUsize::new(0),
+            previous_roots: Vec::with_capacity(Self::PREVIOUS_ROOTS_LIMIT),
             tree_size_in_bytes: tree_bytes,
-            hash_table
+            hash_table,
+            transposition_table
         }
     }
 
@@ -60,6 +66,7 @@ impl Tree {
     #[inline]
     pub fn clear(&mut self) {
         self.hash_table.clear();
+        self.transposition_table.clear();
 
         let root_key = self[self.root_index()].key();
 
@@ -68,6 +75,7 @@ impl Tree {
         }
 
         self.current_segment.store(0, Ordering::Relaxed);
+        self.previous_roots.clear();
         self.root_edge = Edge::default();
 
         _ = self.current_segment().add(GameState::Unresolved, root_key);
@@ -92,6 +100,10 @@ impl Tree {
         &self.hash_table
     }
 
+    pub fn transpositions(&self) -> &TranspositionTable {
+        &self.transposition_table
+    }
+
     #[inline]
     pub fn total_usage(&self) -> f32 {
         let mut total = 0.0;
@@ -113,31 +125,43 @@ impl Tree {
         self[node_index].actions()[action_index].add_score(score)
     }
 
-    pub fn backpropagate_mates(&self, parent_node_index: NodeIndex, child_state: GameState) {
+    //MCTS-solver, proves the parent when results of its children decide it. Node states are seen
+    //from the perspective of the side to move in the node, so they flip on the way up
+    pub fn backpropagate_states(&self, parent_node_index: NodeIndex, child_state: GameState) {
         match child_state {
-            GameState::Lost(x) => self[parent_node_index].set_state(GameState::Won(x + 1)),
-            GameState::Won(x) => {
-                //To backpropagate won state we need to check, if all states are won (forced check mate)
-                //and if we are sure that its forced, then we select the longest line and we pray that enemy will miss it
-                let mut proven_loss = true;
-                let mut longest_win_length = x;
+            //Single lost child is enough to win the parent, we keep the shortest mate
+            GameState::Lost(x) => {
+                let win_length = match self[parent_node_index].state() {
+                    GameState::Won(y) => y.min(x.saturating_add(1)),
+                    _ => x.saturating_add(1),
+                };
+
+                self[parent_node_index].set_state(GameState::Won(win_length))
+            }
+            GameState::Won(_) | GameState::Drawn => {
+                //To backpropagate lost or drawn state we need to check, if all children are proven. If any of them
+                //is a draw, then we can hold it. Otherwise we select the longest line and we pray that enemy will miss it
+                let mut drawn = false;
+                let mut longest_win_length = 0;
                 for action in self[parent_node_index].actions().iter() {
                     if action.node_index().is_null() {
-                        proven_loss = false;
-                        break;
-                    } else if let GameState::Won(x) = self[action.node_index()].state() {
-                        longest_win_length = x.max(longest_win_length);
-                    } else {
-                        proven_loss = false;
-                        break;
+                        return;
+                    }
+
+                    match self[action.node_index()].state() {
+                        GameState::Won(x) => longest_win_length = x.max(longest_win_length),
+                        GameState::Drawn => drawn = true,
+                        _ => return,
                     }
                 }
 
-                if proven_loss {
-                    self[parent_node_index].set_state(GameState::Lost(longest_win_length + 1));
+                if drawn {
+                    self[parent_node_index].set_state(GameState::Drawn);
+                } else {
+                    self[parent_node_index].set_state(GameState::Lost(longest_win_length.saturating_add(1)));
                 }
             }
-            _ => (),
+            GameState::Unresolved => (),
         }
     }
 
@@ -172,7 +196,7 @@ impl Tree {
                 match self[a.node_index()].state() {
                     GameState::Lost(n) => 1.0 + f32::from(n),
                     GameState::Won(n) => f32::from(n) - 256.0,
-                    GameState::Drawn => 0.5,
+                    GameState::Drawn => Score::DRAW.single(contempt),
                     GameState::Unresolved => a.score().single(contempt),
                 }
             } else {
@@ -185,7 +209,7 @@ impl Tree {
                 match self[b.node_index()].state() {
                     GameState::Lost(n) => 1.0 + f32::from(n),
                     GameState::Won(n) => f32::from(n) - 256.0,
-                    GameState::Drawn => 0.5,
+                    GameState::Drawn => Score::DRAW.single(contempt),
                     GameState::Unresolved => b.score().single(contempt),
                 }
             } else {
@@ -2,7 +2,7 @@ use std::sync::atomic::Ordering;
 
 use spear::ChessPosition;
 
-use crate::{search::SearchHelpers, GameState};
+use crate::{search::SearchHelpers, GameState, SearchStats};
 
 use super::{tree_base::SEGMENT_COUNT, NodeIndex, Tree};
 
@@ -13,6 +13,7 @@ impl Tree {
         child_index: NodeIndex,
         edge_index: NodeIndex,
         action_index: usize,
+        stats: &SearchStats,
     ) -> Option<NodeIndex> {
         //When there is no node assigned to the selected move edge, we spawn new node
         //and return its index
@@ -26,7 +27,7 @@ impl Tree {
             }
 
             //Create new node in the current segment
-            let state = SearchHelpers::get_position_state::<STM_WHITE, NSTM_WHITE>(position);
+            let state = SearchHelpers::get_position_state::<STM_WHITE, NSTM_WHITE>(position, stats);
             let new_index = self.current_segment().add(state, position.board().get_key().get_raw())?;
 
             //Assign new node index to the edge
@@ -1,97 +1,189 @@
-use spear::{ChessBoard, Side};
+use std::collections::VecDeque;
 
-use crate::GameState;
+use spear::ChessBoard;
 
-use super::{Edge, NodeIndex, Tree};
+use crate::{search::Score, GameState};
+
+use super::{Edge, Node, NodeIndex, Tree};
+
+//Result of looking for the new root in the previous tree
+#[derive(Clone, Copy, Default)]
+pub struct TreeReuse {
+    pub found: bool,
+    //Distance from the previous root, None when the node was found detached from the tree
+    pub depth: Option<u32>,
+    pub visits: u32,
+}
 
 impl Tree {
-    pub fn reuse_tree(&mut self, previous_board: &ChessBoard, current_board: &ChessBoard) {
+    //Maximum amount of nodes visited when looking for the new root below the previous one
+    const REUSE_SEARCH_LIMIT: usize = 1 << 20;
+
+    //Previous roots are kept detached from the tree, so their subtrees can be found again after
+    //takebacks or when jumping between analysed lines. Search below each of them is limited, so
+    //the lookup stays cheap even with large trees
+    pub(super) const PREVIOUS_ROOTS_LIMIT: usize = 16;
+    const DETACHED_SEARCH_LIMIT: usize = 1 << 16;
+
+    pub fn reuse_tree(&mut self, previous_board: &ChessBoard, current_board: &ChessBoard) -> TreeReuse {
+        let current_key = current_board.get_key().get_raw();
+
         //When tree is empty then we don't need to check for possible reuse, we can just return,
         //but before that we add a root node to make sure that tree is valid
         if self.total_usage() == 0.0 {
-            _ = self.current_segment().add(GameState::Unresolved, current_board.get_key().get_raw());
-            return;
+            _ = self.current_segment().add(GameState::Unresolved, current_key);
+            return TreeReuse::default();
         }
 
         //If position didn't change then we can continue with current tree
         if previous_board == current_board {
-            return;
+            return TreeReuse {
+                found: true,
+                depth: Some(0),
+                visits: self.root_edge().visits(),
+            };
         }
 
-        //Finds the new position in the tree up to depth 2
-        let (node_index, edge) = if previous_board.side_to_move() == Side::WHITE {
-            self.recurse_find::<_, true, false>(
-                self.root_index(),
-                previous_board,
-                self.root_edge().clone(),
-                2,
-                &|board, _| board == current_board,
-            )
-        } else {
-            self.recurse_find::<_, false, true>(
-                self.root_index(),
-                previous_board,
-                self.root_edge().clone(),
-                2,
-                &|board, _| board == current_board,
-            )
-        };
+        //Finds the new position by its key below the current root, or below one of the previous roots
+        let found = self
+            .find_below(self.root_index(), current_key, Self::REUSE_SEARCH_LIMIT)
+            .or_else(|| self.find_detached(current_key));
 
         //If the position was found and tree after that position is not empty,
         //we can move the position to the root postion
-        if !node_index.is_null() && self[node_index].has_children() {
-            self[self.root_index()].clear();
-            self.copy_node(node_index, self.root_index());
-            self.root_edge = edge;
-
-        //Otherwise we clear the tree and reinit the root node
-        } else {
-            self.clear();
-            _ = self.current_segment().add(GameState::Unresolved, current_board.get_key().get_raw());
+        match found {
+            Some((node_index, edge, depth)) if node_index != self.root_index() => {
+                let visits = edge.visits();
+                self.detach_root();
+                self.copy_node(node_index, self.root_index());
+                self.root_edge = edge;
+
+                //Detached node could be proven with a different set of moves, so it's proven again
+                if depth.is_none() {
+                    self[self.root_index()].set_state(GameState::Unresolved);
+                }
+
+                TreeReuse { found: true, depth, visits }
+            }
+
+            //Otherwise we clear the tree and reinit the root node
+            _ => {
+                self.clear();
+                self[self.root_index()].set_key(current_key);
+                TreeReuse::default()
+            }
         }
     }
 
-    fn recurse_find<
-        F: Fn(&ChessBoard, NodeIndex) -> bool,
-        const STM_WHITE: bool,
-        const NSTM_WHITE: bool,
-    >(
-        &self,
-        start: NodeIndex,
-        board: &ChessBoard,
-        edge: Edge,
-        depth: u8,
-        method: &F,
-    ) -> (NodeIndex, Edge) {
-        if method(board, start) {
-            return (start, edge);
+    //Moves subtree of the current root into a fresh node and remembers it, so it's not lost when
+    //the new root is copied in. When the segment is full the previous root is simply replaced
+    fn detach_root(&mut self) {
+        let root_index = self.root_index();
+        let root_key = self[root_index].key();
+        if !self[root_index].has_children() {
+            self[root_index].clear();
+            return;
         }
 
-        if start.is_null() || depth == 0 {
-            return (NodeIndex::NULL, Edge::default());
+        let Some(detached_index) = self.current_segment().add(GameState::Unresolved, root_key) else {
+            self[root_index].clear();
+            return;
+        };
+
+        self.copy_node(root_index, detached_index);
+        self[root_index].clear();
+
+        self.previous_roots.retain(|&(key, _)| key != root_key);
+        if self.previous_roots.len() >= Self::PREVIOUS_ROOTS_LIMIT {
+            self.previous_roots.remove(0);
         }
 
-        let node = &self[start];
+        self.previous_roots.push((root_key, detached_index));
+    }
 
-        for action in node.actions().iter() {
-            let child_index = action.node_index();
-            let mut child_board = *board;
+    //Breadth first search from the start node, so the closest matching node is found first
+    fn find_below(&self, start_index: NodeIndex, key: u64, limit: usize) -> Option<(NodeIndex, Edge, Option<u32>)> {
+        let mut queue = VecDeque::from([(start_index, 0)]);
+        let mut visited = 0;
+
+        while let Some((node_index, depth)) = queue.pop_front() {
+            visited += 1;
+            if visited > limit {
+                break;
+            }
+
+            for action in self[node_index].actions().iter() {
+                let child_index = action.node_index();
+                if child_index.is_null() {
+                    continue;
+                }
+
+                if self[child_index].key() == key && self[child_index].has_children() {
+                    return Some((child_index, action.clone(), Some(depth + 1)));
+                }
+
+                queue.push_back((child_index, depth + 1));
+            }
+        }
+
+        None
+    }
+
+    //Previous roots are checked from the most recent one. Segment could be cleared and reused since
+    //the root was stored, so only nodes still in use with the expected key are trusted
+    fn find_detached(&self, key: u64) -> Option<(NodeIndex, Edge, Option<u32>)> {
+        for &(root_key, root_index) in self.previous_roots.iter().rev() {
+            if !self.is_live_node(root_index, root_key) {
+                continue;
+            }
+
+            if root_key == key {
+                return Some((root_index, Self::edge_from_children(&self[root_index]), None));
+            }
+
+            if let Some((node_index, _, _)) = self.find_below(root_index, key, Self::DETACHED_SEARCH_LIMIT) {
+                return Some((node_index, Self::edge_from_children(&self[node_index]), None));
+            }
+        }
 
-            child_board.make_move::<STM_WHITE, NSTM_WHITE>(action.mv());
+        None
+    }
 
-            let (idx, edge) = self.recurse_find::<F, NSTM_WHITE, STM_WHITE>(
-                child_index,
-                &child_board,
-                action.clone(),
-                depth - 1,
-                method,
-            );
+    //Root restricted to a different set of moves keeps only the statistics of its remaining children
+    pub fn rebuild_root_edge(&self) {
+        let edge = Self::edge_from_children(&self[self.root_index()]);
+        self.root_edge.set_stats(edge.score(), edge.visits(), edge.squared_score());
+    }
 
-            if !idx.is_null() {
-                return (idx, edge);
+    fn is_live_node(&self, node_index: NodeIndex, key: u64) -> bool {
+        (node_index.index() as usize) < self.segments[node_index.segment()].len()
+            && self[node_index].key() == key
+            && self[node_index].has_children()
+    }
+
+    //Parent edge of detached node is unknown, so its statistics are rebuilt from the children
+    fn edge_from_children(node: &Node) -> Edge {
+        let mut visits = 0;
+        let mut win_chance = 0.0;
+        let mut draw_chance = 0.0;
+        let mut squared_score = 0.0;
+
+        for action in node.actions().iter() {
+            let action_visits = f64::from(action.visits());
+            if action_visits == 0.0 {
+                continue;
             }
+
+            //Children scores are from the perspective of the side to move in the node
+            let score = action.score().reversed();
+            visits += action.visits();
+            win_chance += f64::from(score.win_chance()) * action_visits;
+            draw_chance += f64::from(score.draw_chance()) * action_visits;
+            squared_score += (f64::from(action.variance(0.0)) + f64::from(score.single(0.0)).powi(2)) * action_visits;
         }
 
-        (NodeIndex::NULL, Edge::default())
+        let total = f64::from(visits.max(1));
+        let score = Score::new((win_chance / total) as f32, (draw_chance / total) as f32);
+        Edge::with_stats(score, visits, squared_score / total)
     }
 }
@@ -1,3 +1,5 @@
 mod search_helpers;
+mod strength_limit;
 
 pub use search_helpers::SearchHelpers;
+pub use strength_limit::StrengthLimit;
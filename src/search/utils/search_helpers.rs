@@ -1,11 +1,21 @@
-use spear::ChessPosition;
+use spear::{ChessBoard, ChessPosition};
 
 use crate::{
-    search::{networks::ValueNetwork, Score}, EngineOptions, GameState, Tree
+    search::{networks::ValueNetwork, Score}, Bitbases, EngineOptions, GameState, SearchStats, Syzygy, Tree
 };
 
+use super::StrengthLimit;
+
 pub struct SearchHelpers;
 impl SearchHelpers {
+    //Root policy temperature, which is raised when engine limits its strength
+    #[inline]
+    pub fn get_root_pst(options: &EngineOptions) -> f32 {
+        StrengthLimit::from_options(options)
+            .map(|limit| limit.root_pst())
+            .unwrap_or(options.root_pst())
+    }
+
     #[inline]
     pub fn get_node_score<const STM_WHITE: bool, const NSTM_WHITE: bool>(
         current_position: &mut ChessPosition,
@@ -13,39 +23,131 @@ impl SearchHelpers {
         key: u64,
         tree: &Tree,
         material_difference: i32,
-        options: &EngineOptions
+        options: &EngineOptions,
+        stats: &SearchStats
     ) -> Score {
-
-        let score_bonus = if material_difference != 0 {
-            options.material_reduction_bonus() / 10.0
-        } else {
-            0.0
-        };
-
         match state {
             GameState::Drawn => Score::DRAW,
             GameState::Lost(_) => Score::LOSE,
             GameState::Won(_) => Score::WIN,
             GameState::Unresolved => {
-                if let Some(score) = tree.hash_table().probe(key) {
-                    Score::new(score.win_chance() + score_bonus, score.draw_chance())
+                if let Some(score) = Self::get_transposition_score(key, tree, options) {
+                    return score;
+                }
+
+                //Hash table doesn't know the 50 move counter, so it stores the raw network score
+                let score = if let Some(score) = tree.hash_table().probe(key, stats) {
+                    score
                 } else {
-                    let (win_chance, draw_chance, _) = ValueNetwork.forward::<STM_WHITE, NSTM_WHITE>(current_position.board());
-                    let score = Score::new(win_chance, draw_chance);
+                    let score = Self::evaluate::<STM_WHITE, NSTM_WHITE>(current_position, options, stats);
 
-                    tree.hash_table().store(key, score);
+                    tree.hash_table().store(key, score, stats);
 
-                    Score::new(score.win_chance() + score_bonus, score.draw_chance())
-                }
+                    score
+                };
+
+                let score = Self::add_material_bonus(score, material_difference, options);
+                Self::apply_fifty_move_rule(score, current_position.board(), options)
             },
         }
     }
 
+    //Position reached before through another move order already has backed up statistics, they are
+    //used instead of single evaluation. Material bonus is already included in them
+    #[inline]
+    pub fn get_transposition_score(key: u64, tree: &Tree, options: &EngineOptions) -> Option<Score> {
+        if options.transposition_weight() <= 0.0 {
+            return None;
+        }
+
+        tree.transpositions().probe(key).map(|(_, score)| score.reversed())
+    }
+
+    //Value network score of the position, that is stored in the hash table
+    #[inline]
+    pub fn evaluate<const STM_WHITE: bool, const NSTM_WHITE: bool>(
+        position: &ChessPosition,
+        options: &EngineOptions,
+        stats: &SearchStats
+    ) -> Score {
+        let (win_chance, draw_chance, _, moves_left) = ValueNetwork.forward::<STM_WHITE, NSTM_WHITE>(position.board());
+        stats.add_evaluation();
+        Self::apply_moves_left(Score::new(win_chance, draw_chance), moves_left, options)
+    }
+
+    //Value network scores of multiple positions evaluated in a single batch
+    pub fn evaluate_batch(positions: &[ChessPosition], options: &EngineOptions, stats: &SearchStats) -> Vec<Score> {
+        let boards: Vec<ChessBoard> = positions.iter().map(|position| *position.board()).collect();
+        ValueNetwork
+            .forward_batch(&boards)
+            .into_iter()
+            .map(|(win_chance, draw_chance, _, moves_left)| {
+                stats.add_evaluation();
+                Self::apply_moves_left(Score::new(win_chance, draw_chance), moves_left, options)
+            })
+            .collect()
+    }
+
+    //Positions with different material than the root get a bonus, to make the engine play sharper
+    #[inline]
+    pub fn add_material_bonus(score: Score, material_difference: i32, options: &EngineOptions) -> Score {
+        let score_bonus = if material_difference != 0 {
+            options.material_reduction_bonus() / 10.0
+        } else {
+            0.0
+        };
+
+        Score::new(score.win_chance() + score_bonus, score.draw_chance())
+    }
+
+    //Value network doesn't see the 50 move counter, so decisive part of the score is moved towards
+    //a draw once the counter passes FiftyMoveStart, reaching FiftyMoveScale at 100 half moves
+    #[inline]
+    pub fn apply_fifty_move_rule(score: Score, board: &ChessBoard, options: &EngineOptions) -> Score {
+        let start = options.fifty_move_start();
+        let half_moves = board.half_move_counter() as f32;
+        if half_moves <= start {
+            return score;
+        }
+
+        let progress = ((half_moves - start) / (100.0 - start).max(1.0)).min(1.0);
+        let factor = options.fifty_move_scale() * progress.powf(options.fifty_move_power());
+        let win_chance = score.win_chance() * (1.0 - factor);
+        let draw_chance = score.draw_chance() + (score.win_chance() + score.lose_chance()) * factor;
+
+        Score::new(win_chance, draw_chance)
+    }
+
+    //In decided positions part of the expected result is shifted towards a draw, proportionally to
+    //the number of moves left, so search prefers faster wins and delays the losses. Threshold and
+    //scale aren't tuned yet, so it's disabled with the default scale of 0
+    #[inline]
+    fn apply_moves_left(score: Score, moves_left: f32, options: &EngineOptions) -> Score {
+        if !ValueNetwork::HAS_MOVES_LEFT || options.moves_left_scale() <= 0.0 {
+            return score;
+        }
+
+        let margin = score.win_chance() - score.lose_chance();
+        if margin.abs() < options.moves_left_threshold() {
+            return score;
+        }
+
+        let factor = (options.moves_left_scale() * moves_left / 100.0).min(0.5);
+        if margin > 0.0 {
+            let shift = score.win_chance() * factor;
+            Score::new(score.win_chance() - shift, score.draw_chance() + shift)
+        } else {
+            let shift = score.lose_chance() * factor;
+            Score::new(score.win_chance(), score.draw_chance() + shift)
+        }
+    }
+
     pub fn get_position_state<const STM_WHITE: bool, const NSTM_WHITE: bool>(
         position: &ChessPosition,
+        stats: &SearchStats
     ) -> GameState {
-        if position.is_repetition()
-            || position.board().is_insufficient_material()
+        //Repetitions depend on the path to the position, so they are scored during the descend
+        if position.board().is_insufficient_material()
             || position.board().half_move_counter() >= 100
         {
             return GameState::Drawn;
@@ -64,6 +166,20 @@ impl SearchHelpers {
             }
         }
 
+        //Bitbases and Syzygy tables assume that 50 move counter was just reset, so they are probed
+        //only after captures and pawn moves
+        if position.board().half_move_counter() == 0 {
+            if let Some(state) = Bitbases::probe(position.board()) {
+                stats.add_tbhit();
+                return state;
+            }
+
+            if let Some(state) = Syzygy::probe_state::<STM_WHITE, NSTM_WHITE>(position.board()) {
+                stats.add_tbhit();
+                return state;
+            }
+        }
+
         GameState::Unresolved
     }
 }
@@ -0,0 +1,146 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{search::tree::Edge, EngineOptions};
+
+pub struct StrengthLimit {
+    nodes: u32,
+    root_pst: f32,
+    temperature: f32,
+}
+
+impl StrengthLimit {
+    //Reference points of the strength levels: (level, nodes, root policy temperature, visit sampling temperature).
+    //Values between the points are interpolated, nodes on logarithmic scale. Levels are not calibrated
+    //to any rating list, so they are not exposed as UCI_Elo
+    //Stronger settings have to use more nodes and lower temperatures, which is checked by the tests
+    const LEVELS: [(i32, u32, f32, f32); 6] = [
+        (0, 16, 8.0, 1.6),
+        (4, 64, 6.0, 1.0),
+        (8, 256, 4.5, 0.6),
+        (12, 1024, 3.5, 0.3),
+        (16, 8192, 3.5, 0.1),
+        (20, 262144, 3.5, 0.0),
+    ];
+
+    pub fn from_level(level: i32) -> Self {
+        let levels = &Self::LEVELS;
+        let level = level.clamp(levels[0].0, levels[levels.len() - 1].0);
+
+        let upper = levels
+            .iter()
+            .position(|&(point_level, _, _, _)| point_level >= level)
+            .unwrap_or(levels.len() - 1)
+            .max(1);
+        let (low_level, low_nodes, low_pst, low_temperature) = levels[upper - 1];
+        let (high_level, high_nodes, high_pst, high_temperature) = levels[upper];
+
+        let t = (level - low_level) as f32 / (high_level - low_level) as f32;
+        let nodes = ((low_nodes as f32).ln() + ((high_nodes as f32).ln() - (low_nodes as f32).ln()) * t).exp();
+
+        Self {
+            nodes: nodes as u32,
+            root_pst: low_pst + (high_pst - low_pst) * t,
+            temperature: low_temperature + (high_temperature - low_temperature) * t,
+        }
+    }
+
+    //Returns strength limit, if the engine is set to limit its strength
+    pub fn from_options(options: &EngineOptions) -> Option<Self> {
+        if options.limit_strength() {
+            Some(Self::from_level(options.strength_level()))
+        } else {
+            None
+        }
+    }
+
+    pub fn nodes(&self) -> u32 {
+        self.nodes
+    }
+
+    pub fn root_pst(&self) -> f32 {
+        self.root_pst
+    }
+
+    //Samples action index from root visit distribution sharpened by the temperature,
+    //zero temperature always picks the most visited action. Without the seed it's taken from the clock
+    pub fn sample_action(&self, actions: &[Edge], seed: Option<u64>) -> Option<usize> {
+        let visited = actions.iter().enumerate().filter(|(_, action)| action.visits() > 0);
+
+        if self.temperature <= 0.0 {
+            return visited.max_by_key(|(_, action)| action.visits()).map(|(idx, _)| idx);
+        }
+
+        let weights: Vec<(usize, f64)> = visited
+            .map(|(idx, action)| (idx, f64::from(action.visits()).powf(1.0 / f64::from(self.temperature))))
+            .collect();
+        let total: f64 = weights.iter().map(|(_, weight)| weight).sum();
+        if weights.is_empty() || total <= 0.0 {
+            return None;
+        }
+
+        let mut target = random_f64(seed) * total;
+        for &(idx, weight) in &weights {
+            if target < weight {
+                return Some(idx);
+            }
+            target -= weight;
+        }
+
+        weights.last().map(|&(idx, _)| idx)
+    }
+}
+
+//Small xorshift generator seeded from the clock, we don't need anything stronger to pick a move
+fn random_f64(seed: Option<u64>) -> f64 {
+    let mut seed = seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or(0x2545F4914F6CDD1D)
+    }) | 1;
+
+    seed ^= seed << 13;
+    seed ^= seed >> 7;
+    seed ^= seed << 17;
+
+    (seed >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn level_points_are_ordered() {
+        for pair in StrengthLimit::LEVELS.windows(2) {
+            let (low_level, low_nodes, low_pst, low_temperature) = pair[0];
+            let (high_level, high_nodes, high_pst, high_temperature) = pair[1];
+            assert!(low_level < high_level);
+            assert!(low_nodes < high_nodes);
+            assert!(low_pst >= high_pst);
+            assert!(low_temperature >= high_temperature);
+        }
+    }
+
+    #[test]
+    fn mapping_is_monotonic() {
+        let mut previous = StrengthLimit::from_level(-5);
+        for level in -5..=25 {
+            let limit = StrengthLimit::from_level(level);
+            assert!(limit.nodes >= previous.nodes, "nodes decreased at level {level}");
+            assert!(limit.root_pst <= previous.root_pst + 1e-6, "root pst increased at level {level}");
+            assert!(limit.temperature <= previous.temperature + 1e-6, "temperature increased at level {level}");
+            previous = limit;
+        }
+    }
+
+    #[test]
+    fn mapping_hits_level_points() {
+        for (level, nodes, root_pst, temperature) in StrengthLimit::LEVELS {
+            let limit = StrengthLimit::from_level(level);
+            assert!((limit.nodes as i64 - i64::from(nodes)).abs() <= 1, "nodes at level {level}");
+            assert!((limit.root_pst - root_pst).abs() < 1e-4);
+            assert!((limit.temperature - temperature).abs() < 1e-4);
+        }
+    }
+}
@@ -0,0 +1,286 @@
+mod syzygy_encoding;
+mod syzygy_position;
+mod syzygy_probe;
+mod syzygy_table;
+
+use std::{
+    fs,
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        RwLock,
+    },
+};
+
+use spear::{ChessBoard, ChessPosition, Move, Side};
+
+use crate::GameState;
+
+use syzygy_probe::Tablebases;
+use syzygy_table::SyzygyTable;
+
+pub const MAX_PIECES: usize = 7;
+
+//Tables found in SyzygyPath, shared by all searches
+static TABLEBASES: RwLock<Option<Tablebases>> = RwLock::new(None);
+//Largest amount of pieces covered by the tables, allows skipping the probe without the lock
+static LARGEST_TABLE: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Wdl {
+    Loss = -2,
+    //Loss that is saved by the 50 move rule
+    BlessedLoss = -1,
+    Draw = 0,
+    //Win that is spoiled by the 50 move rule
+    CursedWin = 1,
+    Win = 2,
+}
+
+impl Wdl {
+    pub fn from_raw(value: i32) -> Self {
+        match value {
+            -2 => Self::Loss,
+            -1 => Self::BlessedLoss,
+            1 => Self::CursedWin,
+            2 => Self::Win,
+            _ => Self::Draw,
+        }
+    }
+
+    pub fn raw(self) -> i32 {
+        self as i32
+    }
+
+    pub fn negated(self) -> Self {
+        Self::from_raw(-self.raw())
+    }
+
+    //DTZ of the position, where the best move zeroes the 50 move counter
+    pub fn dtz_before_zeroing(self) -> i32 {
+        match self {
+            Self::Win => 1,
+            Self::CursedWin => 101,
+            Self::BlessedLoss => -101,
+            Self::Loss => -1,
+            Self::Draw => 0,
+        }
+    }
+}
+
+//Syzygy endgame tablebases, WDL tables are probed during the search and DTZ tables filter
+//the root moves. Files are read into memory the first time they are needed
+pub struct Syzygy;
+impl Syzygy {
+    //Loads tables from the directories separated like in PATH variable and returns amount of
+    //found WDL tables. Empty path or "<empty>" removes the tables
+    pub fn init(paths: &str) -> usize {
+        let separator = if cfg!(windows) { ';' } else { ':' };
+
+        let mut tables = Vec::new();
+        let directories: Vec<&Path> = paths
+            .split(separator)
+            .map(str::trim)
+            .filter(|path| !path.is_empty() && *path != "<empty>")
+            .map(Path::new)
+            .collect();
+
+        for directory in &directories {
+            let Ok(entries) = fs::read_dir(directory) else {
+                println!("info string Cannot read Syzygy directory {}", directory.display());
+                continue;
+            };
+
+            for path in entries.flatten().map(|entry| entry.path()) {
+                if path.extension().and_then(|extension| extension.to_str()) != Some("rtbw") {
+                    continue;
+                }
+
+                let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                    continue;
+                };
+
+                let dtz_path = directories
+                    .iter()
+                    .map(|directory| directory.join(format!("{}.rtbz", name)))
+                    .find(|dtz_path| dtz_path.is_file());
+
+                if let Some(table) = SyzygyTable::new(name, path.clone(), dtz_path) {
+                    tables.push((name.len() - 1, table));
+                }
+            }
+        }
+
+        let table_count = tables.len();
+        let largest_table = tables.iter().map(|(pieces, _)| *pieces).max().unwrap_or(0);
+
+        *TABLEBASES.write().unwrap() = if tables.is_empty() {
+            None
+        } else {
+            Some(Tablebases::new(tables.into_iter().map(|(_, table)| table).collect()))
+        };
+
+        LARGEST_TABLE.store(largest_table, Ordering::Relaxed);
+        table_count
+    }
+
+    pub fn max_pieces() -> usize {
+        LARGEST_TABLE.load(Ordering::Relaxed)
+    }
+
+    //Exact result of the position from the perspective of the side to move. Results are correct
+    //only when the 50 move counter is zero, because tables assume that
+    pub fn probe_state<const STM_WHITE: bool, const NSTM_WHITE: bool>(board: &ChessBoard) -> Option<GameState> {
+        if board.get_occupancy().pop_count() as usize > Self::max_pieces() {
+            return None;
+        }
+
+        let tablebases = TABLEBASES.read().ok()?;
+        let wdl = tablebases.as_ref()?.probe_wdl::<STM_WHITE, NSTM_WHITE>(board)?;
+
+        Some(match wdl {
+            Wdl::Win => GameState::Won(GameState::TABLEBASE_PLIES),
+            Wdl::Loss => GameState::Lost(GameState::TABLEBASE_PLIES),
+            Wdl::CursedWin | Wdl::BlessedLoss | Wdl::Draw => GameState::Drawn,
+        })
+    }
+
+    //Root moves preserving the tablebase result of the position, or None when the position
+    //is not covered. Only allowed moves are considered, when the list is not empty
+    pub fn root_moves(position: &ChessPosition, allowed_moves: &[Move]) -> Option<Vec<Move>> {
+        if position.board().get_occupancy().pop_count() as usize > Self::max_pieces() {
+            return None;
+        }
+
+        let tablebases = TABLEBASES.read().ok()?;
+        let tablebases = tablebases.as_ref()?;
+
+        if position.board().side_to_move() == Side::WHITE {
+            tablebases.rank_root_moves::<true, false>(position, allowed_moves)
+        } else {
+            tablebases.rank_root_moves::<false, true>(position, allowed_moves)
+        }
+    }
+}
+
+//Tests use KQvK, KRvK and KPvK tables generated in test-data/syzygy
+#[cfg(test)]
+mod tests {
+    use std::sync::Once;
+
+    use spear::FEN;
+
+    use super::*;
+
+    const TABLES_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/test-data/syzygy");
+
+    fn position(fen: &str) -> ChessPosition {
+        static INIT: Once = Once::new();
+        INIT.call_once(|| {
+            let tables = Syzygy::init(TABLES_PATH);
+            assert_eq!(tables, 3, "Syzygy test tables are missing in {TABLES_PATH}");
+        });
+
+        ChessPosition::from_fen(&FEN::from_str(fen))
+    }
+
+    fn wdl(fen: &str) -> Option<Wdl> {
+        let position = position(fen);
+        let tablebases = TABLEBASES.read().unwrap();
+        let tablebases = tablebases.as_ref()?;
+
+        if position.board().side_to_move() == Side::WHITE {
+            tablebases.probe_wdl::<true, false>(position.board())
+        } else {
+            tablebases.probe_wdl::<false, true>(position.board())
+        }
+    }
+
+    fn dtz(fen: &str) -> Option<i32> {
+        let position = position(fen);
+        let tablebases = TABLEBASES.read().unwrap();
+        let tablebases = tablebases.as_ref()?;
+
+        if position.board().side_to_move() == Side::WHITE {
+            tablebases.probe_dtz::<true, false>(position.board())
+        } else {
+            tablebases.probe_dtz::<false, true>(position.board())
+        }
+    }
+
+    fn root_moves(fen: &str, allowed_moves: &[&str]) -> Vec<String> {
+        let position = position(fen);
+        let mut allowed = Vec::new();
+        let mut all_moves = Vec::new();
+        if position.board().side_to_move() == Side::WHITE {
+            position.board().map_moves::<_, true, false>(|mv| all_moves.push(mv));
+        } else {
+            position.board().map_moves::<_, false, true>(|mv| all_moves.push(mv));
+        }
+
+        for text in allowed_moves {
+            allowed.push(*all_moves.iter().find(|mv| mv.to_string() == *text).expect("Illegal move in the test"));
+        }
+
+        let mut moves: Vec<String> = Syzygy::root_moves(&position, &allowed)
+            .expect("Position is not covered by the tables")
+            .iter()
+            .map(|mv| mv.to_string())
+            .collect();
+        moves.sort();
+        moves
+    }
+
+    #[test]
+    fn probes_wdl() {
+        assert_eq!(wdl("k7/3Q4/1K6/8/8/8/8/8 w - - 0 1"), Some(Wdl::Win));
+        assert_eq!(wdl("4k3/8/8/8/8/8/8/3QK3 b - - 0 1"), Some(Wdl::Loss));
+
+        //Black captures the hanging rook
+        assert_eq!(wdl("8/8/8/8/8/8/k7/R3K3 b - - 0 1"), Some(Wdl::Draw));
+
+        //Rook pawn is faster than the king
+        assert_eq!(wdl("8/8/8/8/8/8/P7/K6k w - - 0 1"), Some(Wdl::Win));
+        assert_eq!(wdl("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1"), Some(Wdl::Win));
+        assert_eq!(wdl("4k3/8/4K3/4P3/8/8/8/8 b - - 0 1"), Some(Wdl::Loss));
+
+        //Stalemate
+        assert_eq!(wdl("4k3/4P3/4K3/8/8/8/8/8 b - - 0 1"), Some(Wdl::Draw));
+    }
+
+    #[test]
+    fn probes_dtz() {
+        //Mate in one is one ply from zeroing the counter
+        assert_eq!(dtz("k7/3Q4/1K6/8/8/8/8/8 w - - 0 1"), Some(1));
+        assert_eq!(dtz("k7/8/1K6/8/8/8/8/7R w - - 0 1"), Some(1));
+
+        //Winning pawn move zeroes the counter right away
+        assert_eq!(dtz("8/8/8/8/8/8/P7/K6k w - - 0 1"), Some(1));
+
+        assert_eq!(dtz("8/8/8/8/8/8/k7/R3K3 b - - 0 1"), Some(0));
+        assert!(dtz("4k3/8/8/8/8/8/8/3QK3 b - - 0 1").is_some_and(|dtz| dtz < 0));
+        assert!(dtz("4k3/8/8/8/8/8/8/3QK3 w - - 0 1").is_some_and(|dtz| dtz > 1));
+    }
+
+    #[test]
+    fn root_moves_keep_the_best_result() {
+        //All mates in one are kept
+        assert_eq!(root_moves("k7/3Q4/1K6/8/8/8/8/8 w - - 0 1", &[]), ["d7a7", "d7b7", "d7c8", "d7d8", "d7e8"]);
+
+        //Only capture of the rook holds the draw
+        assert_eq!(root_moves("8/8/8/8/8/8/k7/R3K3 b - - 0 1", &[]), ["a2a1"]);
+    }
+
+    #[test]
+    fn root_moves_respect_allowed_moves() {
+        assert_eq!(root_moves("8/8/8/8/8/8/k7/R3K3 b - - 0 1", &["a2b3"]), ["a2b3"]);
+        assert_eq!(root_moves("k7/3Q4/1K6/8/8/8/8/8 w - - 0 1", &["d7d8", "d7d5"]), ["d7d8"]);
+    }
+
+    #[test]
+    fn positions_outside_tables_are_not_probed() {
+        let position = position("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert!(Syzygy::root_moves(&position, &[]).is_none());
+        assert!(Syzygy::probe_state::<true, false>(position.board()).is_none());
+    }
+}
@@ -0,0 +1,200 @@
+use std::sync::OnceLock;
+
+//Lookup tables used to turn a position into the index of the table, they follow the layout of
+//the files generated by the Syzygy generator
+pub struct Encoding {
+    //Squares below a1-h8 diagonal mapped to 0..27
+    pub map_b1h1h7: [u64; 64],
+    //Squares of a1-d1-d4 triangle mapped to 0..9, diagonal squares last
+    pub map_a1d1d4: [u64; 64],
+    //462 legal placements of two kings, where the first one is in a1-d1-d4 triangle
+    pub map_kk: [[u64; 64]; 10],
+    pub binomial: [[u64; 64]; 6],
+    //Squares a2-h7 mapped to 0..47, the leading pawn has the highest value
+    pub map_pawns: [u64; 64],
+    pub lead_pawn_idx: [[u64; 64]; 6],
+    pub lead_pawns_size: [[u64; 4]; 6],
+}
+
+static ENCODING: OnceLock<Encoding> = OnceLock::new();
+
+impl Encoding {
+    pub fn get() -> &'static Self {
+        ENCODING.get_or_init(Self::new)
+    }
+
+    fn new() -> Self {
+        let mut encoding = Self {
+            map_b1h1h7: [0; 64],
+            map_a1d1d4: [0; 64],
+            map_kk: [[0; 64]; 10],
+            binomial: [[0; 64]; 6],
+            map_pawns: [0; 64],
+            lead_pawn_idx: [[0; 64]; 6],
+            lead_pawns_size: [[0; 4]; 6],
+        };
+
+        let mut code = 0;
+        for square in 0..64 {
+            if off_a1h8(square) < 0 {
+                encoding.map_b1h1h7[square] = code;
+                code += 1;
+            }
+        }
+
+        let mut diagonal = Vec::new();
+        code = 0;
+        for square in 0..=27 {
+            if off_a1h8(square) < 0 && file_of(square) <= 3 {
+                encoding.map_a1d1d4[square] = code;
+                code += 1;
+            } else if off_a1h8(square) == 0 && file_of(square) <= 3 {
+                diagonal.push(square);
+            }
+        }
+
+        for square in diagonal {
+            encoding.map_a1d1d4[square] = code;
+            code += 1;
+        }
+
+        //When the first king is on a1-d4 diagonal, the second one can't be above a1-h8 diagonal.
+        //Placements with both kings on the diagonal are encoded last
+        let mut both_on_diagonal = Vec::new();
+        code = 0;
+        for idx in 0..10 {
+            for first in 0..=27 {
+                if encoding.map_a1d1d4[first] != idx as u64 || (idx == 0 && first != 1) {
+                    continue;
+                }
+
+                for second in 0..64 {
+                    if square_distance(first, second) <= 1 {
+                        continue;
+                    }
+
+                    if off_a1h8(first) == 0 && off_a1h8(second) > 0 {
+                        continue;
+                    }
+
+                    if off_a1h8(first) == 0 && off_a1h8(second) == 0 {
+                        both_on_diagonal.push((idx, second));
+                    } else {
+                        encoding.map_kk[idx][second] = code;
+                        code += 1;
+                    }
+                }
+            }
+        }
+
+        for (idx, square) in both_on_diagonal {
+            encoding.map_kk[idx][square] = code;
+            code += 1;
+        }
+
+        encoding.binomial[0][0] = 1;
+        for n in 1..64 {
+            for k in 0..6.min(n + 1) {
+                let with = if k > 0 { encoding.binomial[k - 1][n - 1] } else { 0 };
+                let without = if k < n { encoding.binomial[k][n - 1] } else { 0 };
+                encoding.binomial[k][n] = with + without;
+            }
+        }
+
+        //Tables with pawns are split by the file of the leading pawn, so the index restarts
+        //on every file
+        let mut available_squares = 47;
+        for lead_pawns_count in 1..=5 {
+            for file in 0..4 {
+                let mut idx = 0;
+                for rank in 1..=6 {
+                    let square = rank * 8 + file;
+                    if lead_pawns_count == 1 {
+                        encoding.map_pawns[square] = available_squares;
+                        encoding.map_pawns[flip_file(square)] = available_squares - 1;
+                        available_squares -= 2;
+                    }
+
+                    encoding.lead_pawn_idx[lead_pawns_count][square] = idx;
+                    idx += encoding.binomial[lead_pawns_count - 1][encoding.map_pawns[square] as usize];
+                }
+
+                encoding.lead_pawns_size[lead_pawns_count][file] = idx;
+            }
+        }
+
+        encoding
+    }
+}
+
+pub fn file_of(square: usize) -> usize {
+    square & 7
+}
+
+pub fn rank_of(square: usize) -> usize {
+    square >> 3
+}
+
+pub fn flip_file(square: usize) -> usize {
+    square ^ 7
+}
+
+pub fn flip_rank(square: usize) -> usize {
+    square ^ 56
+}
+
+//Positive above a1-h8 diagonal, negative below it
+pub fn off_a1h8(square: usize) -> i32 {
+    rank_of(square) as i32 - file_of(square) as i32
+}
+
+fn square_distance(a: usize, b: usize) -> usize {
+    file_of(a).abs_diff(file_of(b)).max(rank_of(a).abs_diff(rank_of(b)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn triangle_matches_table_layout() {
+        let encoding = Encoding::get();
+
+        //b1, c1, d1, c2, d2, d3 first, then a1, b2, c3, d4 on the diagonal
+        let expected = [(1, 0), (2, 1), (3, 2), (10, 3), (11, 4), (19, 5), (0, 6), (9, 7), (18, 8), (27, 9)];
+        for (square, code) in expected {
+            assert_eq!(encoding.map_a1d1d4[square], code, "square {square}");
+        }
+    }
+
+    #[test]
+    fn below_diagonal_squares_are_numbered_in_order() {
+        let encoding = Encoding::get();
+        assert_eq!(encoding.map_b1h1h7[1], 0);
+        assert_eq!(encoding.map_b1h1h7[7], 6);
+        assert_eq!(encoding.map_b1h1h7[10], 7);
+        assert_eq!(encoding.map_b1h1h7[55], 27);
+    }
+
+    #[test]
+    fn king_pairs_have_462_placements() {
+        let encoding = Encoding::get();
+        let max_code = encoding.map_kk.iter().flatten().max().copied();
+        assert_eq!(max_code, Some(461));
+    }
+
+    #[test]
+    fn binomials_are_correct() {
+        let binomial = &Encoding::get().binomial;
+        assert_eq!(binomial[0][10], 1);
+        assert_eq!(binomial[1][10], 10);
+        assert_eq!(binomial[2][4], 6);
+        assert_eq!(binomial[5][63], 7_028_847);
+    }
+
+    #[test]
+    fn single_leading_pawn_has_six_squares_on_every_file() {
+        let encoding = Encoding::get();
+        assert_eq!(encoding.lead_pawns_size[1], [6, 6, 6, 6]);
+    }
+}
@@ -0,0 +1,76 @@
+use spear::{ChessBoard, Piece, Side};
+
+use super::MAX_PIECES;
+
+pub const PAWN: u8 = 0;
+
+//Pieces of the board in the encoding of the table files, piece type starts from 1 for pawns
+//and black pieces have the fourth bit set
+pub struct SyzygyPosition {
+    pieces: [(u8, u8); MAX_PIECES],
+    count: usize,
+    pub white_to_move: bool,
+    pub key: u64,
+}
+
+impl SyzygyPosition {
+    //Returns None, when the board has more pieces than tables cover
+    pub fn from_board(board: &ChessBoard) -> Option<Self> {
+        let mut position = Self {
+            pieces: [(0, 0); MAX_PIECES],
+            count: 0,
+            white_to_move: board.side_to_move() == Side::WHITE,
+            key: 0,
+        };
+
+        let mut white_counts = [0; 6];
+        let mut black_counts = [0; 6];
+        let mut overflow = false;
+        for (kind, piece) in [Piece::PAWN, Piece::KNIGHT, Piece::BISHOP, Piece::ROOK, Piece::QUEEN, Piece::KING]
+            .into_iter()
+            .enumerate()
+        {
+            let code = kind as u8 + 1;
+            board.get_piece_mask_for_side::<true>(piece).map(|square| {
+                white_counts[kind] += 1;
+                overflow |= !position.add(code, square.get_raw());
+            });
+            board.get_piece_mask_for_side::<false>(piece).map(|square| {
+                black_counts[kind] += 1;
+                overflow |= !position.add(code | 8, square.get_raw());
+            });
+        }
+
+        if overflow {
+            return None;
+        }
+
+        //Leading pawn and order of the pieces depend on the order of the squares
+        position.pieces[..position.count].sort_unstable_by_key(|&(_, square)| square);
+        position.key = material_key(&white_counts, &black_counts);
+        Some(position)
+    }
+
+    pub fn pieces(&self) -> &[(u8, u8)] {
+        &self.pieces[..self.count]
+    }
+
+    fn add(&mut self, code: u8, square: u8) -> bool {
+        if self.count == MAX_PIECES {
+            return false;
+        }
+
+        self.pieces[self.count] = (code, square);
+        self.count += 1;
+        true
+    }
+}
+
+//Unique number of the material, with piece counts of both colors
+pub fn material_key(white_counts: &[usize; 6], black_counts: &[usize; 6]) -> u64 {
+    white_counts
+        .iter()
+        .chain(black_counts.iter())
+        .enumerate()
+        .fold(0, |key, (index, &count)| key | ((count as u64) << (4 * index)))
+}
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+
+use spear::{ChessBoard, ChessPosition, Move, Piece};
+
+use super::{
+    syzygy_position::SyzygyPosition,
+    syzygy_table::{DtzProbe, SyzygyTable},
+    Wdl,
+};
+
+const MAX_DTZ: i32 = 1 << 18;
+
+//All tables found in Syzygy directories, indexed by material of both colors
+pub struct Tablebases {
+    tables: Vec<SyzygyTable>,
+    keys: HashMap<u64, usize>,
+}
+
+impl Tablebases {
+    pub fn new(tables: Vec<SyzygyTable>) -> Self {
+        let mut keys = HashMap::new();
+        for (index, table) in tables.iter().enumerate() {
+            keys.insert(table.key, index);
+            keys.insert(table.key2, index);
+        }
+
+        Self { tables, keys }
+    }
+
+    fn table(&self, position: &SyzygyPosition) -> Option<&SyzygyTable> {
+        self.keys.get(&position.key).map(|&index| &self.tables[index])
+    }
+
+    //Result of the position from the perspective of the side to move
+    pub fn probe_wdl<const STM_WHITE: bool, const NSTM_WHITE: bool>(&self, board: &ChessBoard) -> Option<Wdl> {
+        self.search::<STM_WHITE, NSTM_WHITE, false>(board).map(|(wdl, _)| wdl)
+    }
+
+    //Distance to zeroing move in plies, positive when the side to move wins. Draws return 0
+    pub fn probe_dtz<const STM_WHITE: bool, const NSTM_WHITE: bool>(&self, board: &ChessBoard) -> Option<i32> {
+        let (wdl, zeroing_best_move) = self.search::<STM_WHITE, NSTM_WHITE, true>(board)?;
+        if wdl == Wdl::Draw {
+            return Some(0);
+        }
+
+        //DTZ of the best move, when it zeroes the counter, is not stored in the table
+        if zeroing_best_move {
+            return Some(wdl.dtz_before_zeroing());
+        }
+
+        let position = SyzygyPosition::from_board(board)?;
+        match self.table(&position)?.probe_dtz(&position, wdl)? {
+            DtzProbe::Found(dtz) => {
+                let cursed = if matches!(wdl, Wdl::CursedWin | Wdl::BlessedLoss) { 100 } else { 0 };
+                Some((dtz + cursed) * wdl.raw().signum())
+            }
+            DtzProbe::OtherSide => {
+                //Table stores the other side to move, so we find the best move with 1 ply search
+                let mut min_dtz = i32::MAX;
+                for mv in Self::moves::<STM_WHITE, NSTM_WHITE>(board) {
+                    let zeroing = mv.is_capture() || board.get_piece_on_square(mv.get_from_square()) == Piece::PAWN;
+
+                    let mut next = *board;
+                    next.make_move::<STM_WHITE, NSTM_WHITE>(mv);
+
+                    let mut dtz = if zeroing {
+                        -self.probe_wdl::<NSTM_WHITE, STM_WHITE>(&next)?.dtz_before_zeroing()
+                    } else {
+                        -self.probe_dtz::<NSTM_WHITE, STM_WHITE>(&next)?
+                    };
+
+                    if dtz == 1 && Self::is_mate::<NSTM_WHITE, STM_WHITE>(&next) {
+                        min_dtz = 1;
+                    }
+
+                    //Zeroing moves already return DTZ counted from the position before them
+                    if !zeroing {
+                        dtz += dtz.signum();
+                    }
+
+                    if dtz < min_dtz && dtz.signum() == wdl.raw().signum() {
+                        min_dtz = dtz;
+                    }
+                }
+
+                //Without legal moves the position is a mate
+                Some(if min_dtz == i32::MAX { -1 } else { min_dtz })
+            }
+        }
+    }
+
+    //Root moves that keep the best result. Wins are converted with the fastest zeroing moves
+    //that don't allow the 50 move rule draw, losses are delayed as long as possible
+    pub fn rank_root_moves<const STM_WHITE: bool, const NSTM_WHITE: bool>(
+        &self,
+        position: &ChessPosition,
+        allowed_moves: &[Move],
+    ) -> Option<Vec<Move>> {
+        let half_moves = position.board().half_move_counter() as i32;
+
+        let mut ranked_moves = Vec::new();
+        for mv in Self::moves::<STM_WHITE, NSTM_WHITE>(position.board()) {
+            if !allowed_moves.is_empty() && !allowed_moves.contains(&mv) {
+                continue;
+            }
+
+            let mut next = *position;
+            next.make_move::<STM_WHITE, NSTM_WHITE>(mv);
+            let board = next.board();
+
+            let mut dtz = if board.half_move_counter() == 0 {
+                -self.probe_wdl::<NSTM_WHITE, STM_WHITE>(board)?.dtz_before_zeroing()
+            } else if next.is_repetition() || board.half_move_counter() >= 100 {
+                0
+            } else {
+                let dtz = -self.probe_dtz::<NSTM_WHITE, STM_WHITE>(board)?;
+                dtz + dtz.signum()
+            };
+
+            if dtz == 2 && Self::is_mate::<NSTM_WHITE, STM_WHITE>(board) {
+                dtz = 1;
+            }
+
+            let rank = if dtz > 0 {
+                if dtz + half_moves <= 99 {
+                    MAX_DTZ - dtz
+                } else {
+                    MAX_DTZ / 2 - (dtz + half_moves)
+                }
+            } else if dtz < 0 {
+                if -dtz * 2 + half_moves < 100 {
+                    -MAX_DTZ - dtz
+                } else {
+                    -MAX_DTZ / 2 + (-dtz + half_moves)
+                }
+            } else {
+                0
+            };
+
+            ranked_moves.push((mv, rank));
+        }
+
+        let best_rank = ranked_moves.iter().map(|&(_, rank)| rank).max()?;
+        Some(
+            ranked_moves
+                .into_iter()
+                .filter(|&(_, rank)| rank == best_rank)
+                .map(|(mv, _)| mv)
+                .collect(),
+        )
+    }
+
+    //Captures are searched first, because tables are probed only after all of them are resolved.
+    //Returns the result and whether the best move zeroes the 50 move counter
+    fn search<const STM_WHITE: bool, const NSTM_WHITE: bool, const CHECK_ZEROING: bool>(
+        &self,
+        board: &ChessBoard,
+    ) -> Option<(Wdl, bool)> {
+        let moves = Self::moves::<STM_WHITE, NSTM_WHITE>(board);
+        let mut best = Wdl::Loss;
+        let mut searched = 0;
+        for &mv in &moves {
+            let zeroing = mv.is_capture()
+                || (CHECK_ZEROING && board.get_piece_on_square(mv.get_from_square()) == Piece::PAWN);
+            if !zeroing {
+                continue;
+            }
+
+            searched += 1;
+
+            let mut next = *board;
+            next.make_move::<STM_WHITE, NSTM_WHITE>(mv);
+            let value = self.search::<NSTM_WHITE, STM_WHITE, false>(&next)?.0.negated();
+            if value > best {
+                best = value;
+                if value >= Wdl::Win {
+                    return Some((value, true));
+                }
+            }
+        }
+
+        let no_more_moves = searched > 0 && searched == moves.len();
+        let value = if no_more_moves {
+            best
+        } else {
+            self.probe_wdl_table(board)?
+        };
+
+        if best >= value {
+            return Some((best, best > Wdl::Draw || no_more_moves));
+        }
+
+        Some((value, false))
+    }
+
+    fn probe_wdl_table(&self, board: &ChessBoard) -> Option<Wdl> {
+        let position = SyzygyPosition::from_board(board)?;
+        if position.pieces().len() == 2 {
+            return Some(Wdl::Draw);
+        }
+
+        self.table(&position)?.probe_wdl(&position)
+    }
+
+    fn moves<const STM_WHITE: bool, const NSTM_WHITE: bool>(board: &ChessBoard) -> Vec<Move> {
+        let mut moves = Vec::with_capacity(64);
+        board.map_moves::<_, STM_WHITE, NSTM_WHITE>(|mv| moves.push(mv));
+        moves
+    }
+
+    fn is_mate<const STM_WHITE: bool, const NSTM_WHITE: bool>(board: &ChessBoard) -> bool {
+        board.is_in_check::<STM_WHITE, NSTM_WHITE>() && Self::moves::<STM_WHITE, NSTM_WHITE>(board).is_empty()
+    }
+}
@@ -0,0 +1,719 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use super::{
+    syzygy_encoding::{file_of, flip_file, flip_rank, off_a1h8, rank_of, Encoding},
+    syzygy_position::{material_key, SyzygyPosition, PAWN},
+    Wdl,
+};
+
+const WDL_MAGIC: [u8; 4] = [0x71, 0xE8, 0x23, 0x5D];
+const DTZ_MAGIC: [u8; 4] = [0xD7, 0x66, 0x0C, 0xA5];
+
+const PIECE_CHARS: [char; 6] = ['P', 'N', 'B', 'R', 'Q', 'K'];
+
+//Flags of the single subtable
+const FLAG_STM: u8 = 1;
+const FLAG_MAPPED: u8 = 2;
+const FLAG_WIN_PLIES: u8 = 4;
+const FLAG_LOSS_PLIES: u8 = 8;
+const FLAG_WIDE: u8 = 16;
+const FLAG_SINGLE_VALUE: u8 = 128;
+
+//Result of probing DTZ table, which stores only one side to move
+pub enum DtzProbe {
+    Found(i32),
+    OtherSide,
+}
+
+//Files of single material, e.g. KRvK, that cover positions with both colors of the pieces
+pub struct SyzygyTable {
+    pub key: u64,
+    pub key2: u64,
+    piece_count: usize,
+    has_pawns: bool,
+    has_unique_pieces: bool,
+    //Pawns of the leading color first
+    pawn_count: [usize; 2],
+    wdl: TableFile,
+    dtz: Option<TableFile>,
+}
+
+//Files are read the first time a position of their material is probed
+struct TableFile {
+    path: PathBuf,
+    data: OnceLock<Option<TableData>>,
+}
+
+struct TableData {
+    bytes: Vec<u8>,
+    sides: usize,
+    files: usize,
+    subtables: Vec<PairsData>,
+    dtz_map: usize,
+}
+
+//Compressed subtable for one side to move and one file of the leading pawn. Positions are
+//encoded with canonical Huffman codes of symbols created with recursive pairing
+#[derive(Default)]
+struct PairsData {
+    flags: u8,
+    min_sym_len: u8,
+    block_size: u64,
+    span: u64,
+    blocks_num: u64,
+    block_length_size: u64,
+    sparse_index: usize,
+    sparse_index_size: u64,
+    block_length: usize,
+    data: usize,
+    lowest_sym: usize,
+    btree: usize,
+    base64: Vec<u64>,
+    symlen: Vec<u8>,
+    pieces: [u8; 7],
+    group_idx: [u64; 8],
+    group_len: [usize; 8],
+    map_idx: [usize; 4],
+}
+
+impl SyzygyTable {
+    //Parses name like "KRvK", white pieces are listed first and each side starts with its king
+    pub fn new(name: &str, wdl_path: PathBuf, dtz_path: Option<PathBuf>) -> Option<Self> {
+        let (white, black) = name.split_once('v')?;
+        let white_counts = Self::parse_side(white)?;
+        let black_counts = Self::parse_side(black)?;
+
+        let piece_count = white_counts.iter().chain(black_counts.iter()).sum();
+        if piece_count > super::MAX_PIECES {
+            return None;
+        }
+
+        let white_pawns = white_counts[usize::from(PAWN)];
+        let black_pawns = black_counts[usize::from(PAWN)];
+        let has_unique_pieces = white_counts[..5].iter().chain(black_counts[..5].iter()).any(|&count| count == 1);
+
+        //Side with fewer pawns leads, because it compresses better
+        let white_leads = black_pawns == 0 || (white_pawns > 0 && black_pawns >= white_pawns);
+        let pawn_count = if white_leads {
+            [white_pawns, black_pawns]
+        } else {
+            [black_pawns, white_pawns]
+        };
+
+        Some(Self {
+            key: material_key(&white_counts, &black_counts),
+            key2: material_key(&black_counts, &white_counts),
+            piece_count,
+            has_pawns: white_pawns + black_pawns > 0,
+            has_unique_pieces,
+            pawn_count,
+            wdl: TableFile::new(wdl_path),
+            dtz: dtz_path.map(TableFile::new),
+        })
+    }
+
+    pub fn probe_wdl(&self, position: &SyzygyPosition) -> Option<Wdl> {
+        let data = self.wdl.load(self, false)?;
+        match self.probe_table(data, position, None)? {
+            DtzProbe::Found(value) => Some(Wdl::from_raw(value - 2)),
+            DtzProbe::OtherSide => None,
+        }
+    }
+
+    pub fn probe_dtz(&self, position: &SyzygyPosition, wdl: Wdl) -> Option<DtzProbe> {
+        let data = self.dtz.as_ref()?.load(self, true)?;
+        self.probe_table(data, position, Some(wdl))
+    }
+
+    fn parse_side(side: &str) -> Option<[usize; 6]> {
+        let mut counts = [0; 6];
+        for char in side.chars() {
+            let kind = PIECE_CHARS.iter().position(|&piece| piece == char)?;
+            counts[kind] += 1;
+        }
+
+        (counts[5] == 1 && side.starts_with('K')).then_some(counts)
+    }
+
+    //Index of the position in the subtable, followed by decompression of its value. Wdl is
+    //provided only for DTZ tables, which need it to map the stored value
+    fn probe_table(&self, data: &TableData, position: &SyzygyPosition, wdl: Option<Wdl>) -> Option<DtzProbe> {
+        let encoding = Encoding::get();
+
+        //Symmetric materials store only white to move and other materials store only the stronger
+        //side as white, so the colors are swapped and the board is flipped if needed
+        let symmetric_black_to_move = self.key == self.key2 && !position.white_to_move;
+        let black_stronger = position.key != self.key;
+        let flip = symmetric_black_to_move || black_stronger;
+        let flip_color = if flip { 8 } else { 0 };
+        let flip_squares = if flip { 56 } else { 0 };
+        let stm = usize::from(flip == position.white_to_move);
+
+        let mut squares = [0usize; 7];
+        let mut pieces = [0u8; 7];
+        let mut size = 0;
+        let mut lead_pawns_count = 0;
+        let mut file = 0;
+
+        //Tables with pawns are split by the file of the leading pawn, which is the one closest
+        //to the edge and with the lowest rank
+        if self.has_pawns {
+            let lead_piece = data.subtable(0, 0).pieces[0] ^ flip_color;
+            for &(piece, square) in position.pieces() {
+                if piece == lead_piece {
+                    squares[size] = usize::from(square) ^ flip_squares;
+                    pieces[size] = piece ^ flip_color;
+                    size += 1;
+                }
+            }
+
+            lead_pawns_count = size;
+            let mut lead_index = 0;
+            for index in 1..lead_pawns_count {
+                if encoding.map_pawns[squares[index]] > encoding.map_pawns[squares[lead_index]] {
+                    lead_index = index;
+                }
+            }
+
+            squares.swap(0, lead_index);
+            file = file_of(squares[0]).min(7 - file_of(squares[0]));
+        }
+
+        if wdl.is_some() {
+            let flags = data.subtable(stm, file).flags;
+            if usize::from(flags & FLAG_STM) != stm && (self.key != self.key2 || self.has_pawns) {
+                return Some(DtzProbe::OtherSide);
+            }
+        }
+
+        for &(piece, square) in position.pieces() {
+            if self.has_pawns && piece == data.subtable(0, 0).pieces[0] ^ flip_color {
+                continue;
+            }
+
+            squares[size] = usize::from(square) ^ flip_squares;
+            pieces[size] = piece ^ flip_color;
+            size += 1;
+        }
+
+        let subtable = data.subtable(stm, file);
+
+        //Pieces are reordered to match the sequence stored in the table
+        for i in lead_pawns_count..size.saturating_sub(1) {
+            for j in i + 1..size {
+                if subtable.pieces[i] == pieces[j] {
+                    pieces.swap(i, j);
+                    squares.swap(i, j);
+                    break;
+                }
+            }
+        }
+
+        //Leading piece is mapped into a1-d1-d4 triangle
+        if file_of(squares[0]) > 3 {
+            for square in &mut squares[..size] {
+                *square = flip_file(*square);
+            }
+        }
+
+        let mut idx;
+        if self.has_pawns {
+            idx = encoding.lead_pawn_idx[lead_pawns_count][squares[0]];
+            squares[1..lead_pawns_count].sort_by_key(|&square| encoding.map_pawns[square]);
+            for i in 1..lead_pawns_count {
+                idx += encoding.binomial[i][encoding.map_pawns[squares[i]] as usize];
+            }
+        } else {
+            if rank_of(squares[0]) > 3 {
+                for square in &mut squares[..size] {
+                    *square = flip_rank(*square);
+                }
+            }
+
+            //First piece of the leading group, that is not on a1-h8 diagonal, is mapped below it
+            for i in 0..subtable.group_len[0] {
+                if off_a1h8(squares[i]) == 0 {
+                    continue;
+                }
+
+                if off_a1h8(squares[i]) > 0 {
+                    for square in &mut squares[i..size] {
+                        *square = ((*square >> 3) | (*square << 3)) & 63;
+                    }
+                }
+
+                break;
+            }
+
+            idx = if self.has_unique_pieces {
+                Self::encode_unique_pieces(encoding, &squares)
+            } else {
+                encoding.map_kk[encoding.map_a1d1d4[squares[0]] as usize][squares[1]]
+            };
+        }
+
+        //Remaining groups are encoded as combinations of squares not taken by the previous groups
+        idx *= subtable.group_idx[0];
+        let mut group_start = subtable.group_len[0];
+        let mut remaining_pawns = self.has_pawns && self.pawn_count[1] > 0;
+        let mut next = 1;
+        while subtable.group_len[next] != 0 {
+            let group_end = group_start + subtable.group_len[next];
+            squares[group_start..group_end].sort_unstable();
+
+            let mut n = 0;
+            for i in 0..subtable.group_len[next] {
+                let square = squares[group_start + i];
+                let adjust = squares[..group_start].iter().filter(|&&previous| square > previous).count();
+                let pawn_adjust = if remaining_pawns { 8 } else { 0 };
+                n += encoding.binomial[i + 1][square - adjust - pawn_adjust];
+            }
+
+            remaining_pawns = false;
+            idx += n * subtable.group_idx[next];
+            group_start = group_end;
+            next += 1;
+        }
+
+        let value = subtable.decompress(&data.bytes, idx);
+        Some(DtzProbe::Found(match wdl {
+            Some(wdl) => self.map_dtz(data, file, value, wdl),
+            None => value,
+        }))
+    }
+
+    //Leading group of three pieces, with the first one in b1-d1-d3 triangle or on a1-d4 diagonal
+    fn encode_unique_pieces(encoding: &Encoding, squares: &[usize; 7]) -> u64 {
+        let adjust1 = u64::from(squares[1] > squares[0]);
+        let adjust2 = u64::from(squares[2] > squares[0]) + u64::from(squares[2] > squares[1]);
+        let [first, second, third] = [squares[0] as u64, squares[1] as u64, squares[2] as u64];
+
+        if off_a1h8(squares[0]) != 0 {
+            (encoding.map_a1d1d4[squares[0]] * 63 + (second - adjust1)) * 62 + third - adjust2
+        } else if off_a1h8(squares[1]) != 0 {
+            (6 * 63 + (first >> 3) * 28 + encoding.map_b1h1h7[squares[1]]) * 62 + third - adjust2
+        } else if off_a1h8(squares[2]) != 0 {
+            6 * 63 * 62
+                + 4 * 28 * 62
+                + (first >> 3) * 7 * 28
+                + ((second >> 3) - adjust1) * 28
+                + encoding.map_b1h1h7[squares[2]]
+        } else {
+            6 * 63 * 62
+                + 4 * 28 * 62
+                + 4 * 7 * 28
+                + (first >> 3) * 7 * 6
+                + ((second >> 3) - adjust1) * 6
+                + ((third >> 3) - adjust2)
+        }
+    }
+
+    //DTZ tables store distance to zeroing move in moves or plies, it is always returned in plies
+    fn map_dtz(&self, data: &TableData, file: usize, value: i32, wdl: Wdl) -> i32 {
+        const WDL_MAP: [usize; 5] = [1, 3, 0, 2, 0];
+
+        let subtable = data.subtable(0, file);
+        let mut value = value;
+        if subtable.flags & FLAG_MAPPED != 0 {
+            let map_idx = subtable.map_idx[WDL_MAP[(wdl.raw() + 2) as usize]];
+            value = if subtable.flags & FLAG_WIDE != 0 {
+                i32::from(read_u16_le(&data.bytes, data.dtz_map + 2 * (map_idx + value as usize)))
+            } else {
+                i32::from(data.bytes[data.dtz_map + map_idx + value as usize])
+            };
+        }
+
+        let in_moves = match wdl {
+            Wdl::Win => subtable.flags & FLAG_WIN_PLIES == 0,
+            Wdl::Loss => subtable.flags & FLAG_LOSS_PLIES == 0,
+            Wdl::CursedWin | Wdl::BlessedLoss => true,
+            Wdl::Draw => false,
+        };
+
+        if in_moves {
+            value *= 2;
+        }
+
+        value + 1
+    }
+}
+
+impl TableFile {
+    fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            data: OnceLock::new(),
+        }
+    }
+
+    fn load(&self, table: &SyzygyTable, dtz: bool) -> Option<&TableData> {
+        self.data
+            .get_or_init(|| TableData::load(&self.path, table, dtz))
+            .as_ref()
+    }
+}
+
+impl TableData {
+    fn load(path: &Path, table: &SyzygyTable, dtz: bool) -> Option<Self> {
+        let bytes = fs::read(path).ok()?;
+        let magic = if dtz { DTZ_MAGIC } else { WDL_MAGIC };
+        if !bytes.starts_with(&magic) {
+            return None;
+        }
+
+        let sides = if !dtz && table.key != table.key2 { 2 } else { 1 };
+        let files = if table.has_pawns { 4 } else { 1 };
+        let mut data = Self {
+            bytes,
+            sides,
+            files,
+            subtables: (0..sides * files).map(|_| PairsData::default()).collect(),
+            dtz_map: 0,
+        };
+
+        data.parse(table, dtz);
+        Some(data)
+    }
+
+    fn subtable(&self, stm: usize, file: usize) -> &PairsData {
+        &self.subtables[(stm % self.sides) * self.files + file.min(self.files - 1)]
+    }
+
+    fn subtable_mut(&mut self, side: usize, file: usize) -> &mut PairsData {
+        &mut self.subtables[side * self.files + file]
+    }
+
+    //Offsets of all parts of the file. Header is followed by sizes of every subtable, DTZ value
+    //maps, sparse indices, block lengths and finally the compressed blocks
+    fn parse(&mut self, table: &SyzygyTable, dtz: bool) {
+        let encoding = Encoding::get();
+        let both_pawns = table.has_pawns && table.pawn_count[1] > 0;
+
+        //First byte stores the flags of the file
+        let mut offset = 5;
+        for file in 0..self.files {
+            let first = self.bytes[offset];
+            let second = if both_pawns { self.bytes[offset + 1] } else { 0xFF };
+            let orders = [[first & 0xF, second & 0xF], [first >> 4, second >> 4]];
+            offset += 1 + usize::from(both_pawns);
+
+            for k in 0..table.piece_count {
+                let byte = self.bytes[offset];
+                for side in 0..self.sides {
+                    self.subtable_mut(side, file).pieces[k] = if side == 1 { byte >> 4 } else { byte & 0xF };
+                }
+
+                offset += 1;
+            }
+
+            for (side, order) in orders.iter().enumerate().take(self.sides) {
+                self.subtable_mut(side, file).set_groups(encoding, table, order, file);
+            }
+        }
+
+        offset += offset & 1;
+        for file in 0..self.files {
+            for side in 0..self.sides {
+                let mut subtable = std::mem::take(self.subtable_mut(side, file));
+                offset = subtable.set_sizes(&self.bytes, offset);
+                *self.subtable_mut(side, file) = subtable;
+            }
+        }
+
+        if dtz {
+            offset = self.set_dtz_map(offset);
+        }
+
+        for file in 0..self.files {
+            for side in 0..self.sides {
+                let subtable = self.subtable_mut(side, file);
+                subtable.sparse_index = offset;
+                offset += subtable.sparse_index_size as usize * 6;
+            }
+        }
+
+        for file in 0..self.files {
+            for side in 0..self.sides {
+                let subtable = self.subtable_mut(side, file);
+                subtable.block_length = offset;
+                offset += subtable.block_length_size as usize * 2;
+            }
+        }
+
+        for file in 0..self.files {
+            for side in 0..self.sides {
+                offset = (offset + 0x3F) & !0x3F;
+                let subtable = self.subtable_mut(side, file);
+                subtable.data = offset;
+                offset += (subtable.blocks_num * subtable.block_size) as usize;
+            }
+        }
+    }
+
+    //DTZ values of every WDL result can be remapped, map of each file has four parts
+    fn set_dtz_map(&mut self, mut offset: usize) -> usize {
+        self.dtz_map = offset;
+        for file in 0..self.files {
+            let flags = self.subtable(0, file).flags;
+            if flags & FLAG_MAPPED == 0 {
+                continue;
+            }
+
+            for i in 0..4 {
+                if flags & FLAG_WIDE != 0 {
+                    offset += offset & 1;
+                    self.subtable_mut(0, file).map_idx[i] = (offset - self.dtz_map) / 2 + 1;
+                    offset += 2 * usize::from(read_u16_le(&self.bytes, offset)) + 2;
+                } else {
+                    self.subtable_mut(0, file).map_idx[i] = offset - self.dtz_map + 1;
+                    offset += usize::from(self.bytes[offset]) + 1;
+                }
+            }
+        }
+
+        offset + (offset & 1)
+    }
+}
+
+impl PairsData {
+    //Groups of pieces encoded together. Pieces of the leading group are encoded by their
+    //placement on the board, the order of the groups is given by the table
+    fn set_groups(&mut self, encoding: &Encoding, table: &SyzygyTable, order: &[u8; 2], file: usize) {
+        let mut n = 0;
+        let mut first_len: i32 = if table.has_pawns {
+            0
+        } else if table.has_unique_pieces {
+            3
+        } else {
+            2
+        };
+
+        self.group_len[n] = 1;
+        for i in 1..table.piece_count {
+            first_len -= 1;
+            if first_len > 0 || self.pieces[i] == self.pieces[i - 1] {
+                self.group_len[n] += 1;
+            } else {
+                n += 1;
+                self.group_len[n] = 1;
+            }
+        }
+
+        n += 1;
+        self.group_len[n] = 0;
+
+        let both_pawns = table.has_pawns && table.pawn_count[1] > 0;
+        let mut next = if both_pawns { 2 } else { 1 };
+        let mut free_squares = 64 - self.group_len[0] - if both_pawns { self.group_len[1] } else { 0 };
+        let mut idx = 1;
+
+        let mut k = 0;
+        while next < n || k == order[0] || k == order[1] {
+            if k == order[0] {
+                self.group_idx[0] = idx;
+                idx *= if table.has_pawns {
+                    encoding.lead_pawns_size[self.group_len[0]][file]
+                } else if table.has_unique_pieces {
+                    31332
+                } else {
+                    462
+                };
+            } else if k == order[1] {
+                self.group_idx[1] = idx;
+                idx *= encoding.binomial[self.group_len[1]][48 - self.group_len[0]];
+            } else {
+                self.group_idx[next] = idx;
+                idx *= encoding.binomial[self.group_len[next]][free_squares];
+                free_squares -= self.group_len[next];
+                next += 1;
+            }
+
+            k += 1;
+        }
+
+        self.group_idx[n] = idx;
+    }
+
+    fn set_sizes(&mut self, bytes: &[u8], mut offset: usize) -> usize {
+        self.flags = bytes[offset];
+        offset += 1;
+
+        //Every position of the subtable has the same value
+        if self.flags & FLAG_SINGLE_VALUE != 0 {
+            self.min_sym_len = bytes[offset];
+            return offset + 1;
+        }
+
+        let group_count = self.group_len.iter().position(|&len| len == 0).unwrap_or(7);
+        let table_size = self.group_idx[group_count];
+
+        self.block_size = 1 << bytes[offset];
+        self.span = 1 << bytes[offset + 1];
+        self.sparse_index_size = table_size.div_ceil(self.span);
+        let padding = u64::from(bytes[offset + 2]);
+        self.blocks_num = u64::from(read_u32_le(bytes, offset + 3));
+        self.block_length_size = self.blocks_num + padding;
+        let max_sym_len = bytes[offset + 7];
+        self.min_sym_len = bytes[offset + 8];
+        offset += 9;
+
+        //Canonical Huffman code assigns lower values to longer symbols, base of every symbol
+        //length is right-padded to 64 bits, so length of the code can be found by comparison
+        self.lowest_sym = offset;
+        let lengths = usize::from(max_sym_len - self.min_sym_len) + 1;
+        self.base64 = vec![0; lengths];
+        for i in (0..lengths - 1).rev() {
+            let lowest = u64::from(read_u16_le(bytes, self.lowest_sym + 2 * i));
+            let next_lowest = u64::from(read_u16_le(bytes, self.lowest_sym + 2 * (i + 1)));
+            self.base64[i] = self.base64[i + 1].wrapping_add(lowest).wrapping_sub(next_lowest) / 2;
+        }
+
+        for (i, base) in self.base64.iter_mut().enumerate() {
+            *base = base.checked_shl((64 - i - usize::from(self.min_sym_len)) as u32).unwrap_or(0);
+        }
+
+        offset += lengths * 2;
+
+        let symbols = usize::from(read_u16_le(bytes, offset));
+        offset += 2;
+        self.btree = offset;
+
+        //Symbols are pairs of other symbols, symlen stores amount of values a symbol expands to
+        self.symlen = vec![0; symbols];
+        let mut visited = vec![false; symbols];
+        for symbol in 0..symbols {
+            if !visited[symbol] {
+                self.symlen[symbol] = self.set_symlen(bytes, symbol, &mut visited);
+            }
+        }
+
+        offset + symbols * 3 + (symbols & 1)
+    }
+
+    fn set_symlen(&mut self, bytes: &[u8], symbol: usize, visited: &mut [bool]) -> u8 {
+        visited[symbol] = true;
+
+        let right = self.right_symbol(bytes, symbol);
+        if right == 0xFFF {
+            return 0;
+        }
+
+        let left = self.left_symbol(bytes, symbol);
+        if !visited[left] {
+            self.symlen[left] = self.set_symlen(bytes, left, visited);
+        }
+
+        if !visited[right] {
+            self.symlen[right] = self.set_symlen(bytes, right, visited);
+        }
+
+        self.symlen[left].wrapping_add(self.symlen[right]).wrapping_add(1)
+    }
+
+    fn left_symbol(&self, bytes: &[u8], symbol: usize) -> usize {
+        let offset = self.btree + symbol * 3;
+        (usize::from(bytes[offset + 1] & 0xF) << 8) | usize::from(bytes[offset])
+    }
+
+    fn right_symbol(&self, bytes: &[u8], symbol: usize) -> usize {
+        let offset = self.btree + symbol * 3;
+        (usize::from(bytes[offset + 2]) << 4) | usize::from(bytes[offset + 1] >> 4)
+    }
+
+    //Value stored at the index. Sparse index points near the block holding the index, then
+    //symbols of the block are decoded until the one covering the index is found
+    fn decompress(&self, bytes: &[u8], idx: u64) -> i32 {
+        if self.flags & FLAG_SINGLE_VALUE != 0 {
+            return i32::from(self.min_sym_len);
+        }
+
+        let k = (idx / self.span) as usize;
+        let mut block = read_u32_le(bytes, self.sparse_index + 6 * k) as usize;
+        let mut offset = i64::from(read_u16_le(bytes, self.sparse_index + 6 * k + 4));
+        offset += (idx % self.span) as i64 - (self.span / 2) as i64;
+
+        let block_length = |block: usize| i64::from(read_u16_le(bytes, self.block_length + 2 * block));
+        while offset < 0 {
+            block -= 1;
+            offset += block_length(block) + 1;
+        }
+
+        while offset > block_length(block) {
+            offset -= block_length(block) + 1;
+            block += 1;
+        }
+
+        let mut pointer = self.data + block * self.block_size as usize;
+        let mut buffer = read_u64_be(bytes, pointer);
+        pointer += 8;
+        let mut buffer_size = 64;
+
+        let min_sym_len = usize::from(self.min_sym_len);
+        let mut symbol;
+        loop {
+            let mut len = 0;
+            while buffer < self.base64[len] {
+                len += 1;
+            }
+
+            symbol = ((buffer - self.base64[len]) >> (64 - len - min_sym_len)) as usize;
+            symbol += usize::from(read_u16_le(bytes, self.lowest_sym + 2 * len));
+
+            let symbol_values = i64::from(self.symlen[symbol]) + 1;
+            if offset < symbol_values {
+                break;
+            }
+
+            offset -= symbol_values;
+            len += min_sym_len;
+            buffer = buffer.checked_shl(len as u32).unwrap_or(0);
+            buffer_size -= len;
+
+            if buffer_size <= 32 {
+                buffer_size += 32;
+                buffer |= u64::from(read_u32_be(bytes, pointer)) << (64 - buffer_size);
+                pointer += 4;
+            }
+        }
+
+        //Symbol expands into pairs of symbols, we follow the side holding our value
+        while self.symlen[symbol] != 0 {
+            let left = self.left_symbol(bytes, symbol);
+            let left_values = i64::from(self.symlen[left]) + 1;
+            if offset < left_values {
+                symbol = left;
+            } else {
+                offset -= left_values;
+                symbol = self.right_symbol(bytes, symbol);
+            }
+        }
+
+        self.left_symbol(bytes, symbol) as i32
+    }
+}
+
+fn read_u16_le(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn read_u32_le(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]])
+}
+
+//Compressed blocks can be read past the end of the file, missing bytes are zeros
+fn read_u32_be(bytes: &[u8], offset: usize) -> u32 {
+    let mut buffer = [0; 4];
+    for (i, byte) in buffer.iter_mut().enumerate() {
+        *byte = bytes.get(offset + i).copied().unwrap_or(0);
+    }
+
+    u32::from_be_bytes(buffer)
+}
+
+fn read_u64_be(bytes: &[u8], offset: usize) -> u64 {
+    (u64::from(read_u32_be(bytes, offset)) << 32) | u64::from(read_u32_be(bytes, offset + 4))
+}
@@ -1,6 +1,7 @@
 use std::process::Command;
 
 use colored::Colorize;
+use spear::Square;
 
 use crate::color_config::ColorConfig;
 
@@ -21,6 +22,11 @@ pub fn clear_terminal_screen() {
     };
 }
 
+//Square in coordinate notation, e.g. "e4"
+pub fn square_to_string(square: Square) -> String {
+    format!("{}{}", (b'a' + square.get_raw() % 8) as char, square.get_raw() / 8 + 1)
+}
+
 pub fn heat_color(content: &str, value: f32, min_value: f32, max_value: f32) -> String {
     let difference = max_value - min_value;
     let min_value = min_value + difference * 0.1;
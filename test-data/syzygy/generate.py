@@ -0,0 +1,654 @@
+#!/usr/bin/env python3
+#Generates KQvK, KRvK and KPvK Syzygy tables used by the tests in src/syzygy.
+#
+#Positions are solved with retrograde analysis and written in the Syzygy file format, so they
+#can be read by any Syzygy prober. WDL tables store both sides to move, DTZ tables store white
+#to move with distances in plies. Values are compressed with canonical Huffman codes of single
+#values, without the recursive pairing used by the official generator, so the files are valid
+#but larger than the official ones.
+#
+#Usage: python3 generate.py [output directory]
+
+import os
+import struct
+import sys
+
+WDL_MAGIC = bytes([0x71, 0xE8, 0x23, 0x5D])
+DTZ_MAGIC = bytes([0xD7, 0x66, 0x0C, 0xA5])
+
+#Header flags of the file
+SPLIT = 1
+HAS_PAWNS = 2
+
+#Flags of the subtable
+FLAG_STM = 1
+FLAG_WIN_PLIES = 4
+FLAG_LOSS_PLIES = 8
+FLAG_SINGLE_VALUE = 128
+
+#Piece codes of the table files, black pieces have the fourth bit set
+W_PAWN, W_ROOK, W_QUEEN, W_KING, B_KING = 1, 4, 5, 6, 14
+
+PAWN, ROOK, QUEEN = "P", "R", "Q"
+
+BLOCK_SIZE_LOG = 8
+SPAN_LOG = 10
+MAX_BLOCK_VALUES = 1 << 15
+
+
+def file_of(square):
+    return square & 7
+
+
+def rank_of(square):
+    return square >> 3
+
+
+def off_a1h8(square):
+    return rank_of(square) - file_of(square)
+
+
+def flip_file(square):
+    return square ^ 7
+
+
+def flip_rank(square):
+    return square ^ 56
+
+
+def distance(a, b):
+    return max(abs(file_of(a) - file_of(b)), abs(rank_of(a) - rank_of(b)))
+
+
+#Encoding tables, same as in syzygy_encoding.rs
+class Encoding:
+    def __init__(self):
+        self.map_b1h1h7 = [0] * 64
+        self.map_a1d1d4 = [0] * 64
+        self.binomial = [[0] * 64 for _ in range(6)]
+        self.map_pawns = [0] * 64
+        self.lead_pawn_idx = [[0] * 64 for _ in range(6)]
+        self.lead_pawns_size = [[0] * 4 for _ in range(6)]
+
+        code = 0
+        for square in range(64):
+            if off_a1h8(square) < 0:
+                self.map_b1h1h7[square] = code
+                code += 1
+
+        diagonal = []
+        code = 0
+        for square in range(28):
+            if off_a1h8(square) < 0 and file_of(square) <= 3:
+                self.map_a1d1d4[square] = code
+                code += 1
+            elif off_a1h8(square) == 0 and file_of(square) <= 3:
+                diagonal.append(square)
+
+        for square in diagonal:
+            self.map_a1d1d4[square] = code
+            code += 1
+
+        self.binomial[0][0] = 1
+        for n in range(1, 64):
+            for k in range(min(6, n + 1)):
+                with_n = self.binomial[k - 1][n - 1] if k > 0 else 0
+                without_n = self.binomial[k][n - 1] if k < n else 0
+                self.binomial[k][n] = with_n + without_n
+
+        available_squares = 47
+        for lead_pawns_count in range(1, 6):
+            for file in range(4):
+                idx = 0
+                for rank in range(1, 7):
+                    square = rank * 8 + file
+                    if lead_pawns_count == 1:
+                        self.map_pawns[square] = available_squares
+                        self.map_pawns[flip_file(square)] = available_squares - 1
+                        available_squares -= 2
+
+                    self.lead_pawn_idx[lead_pawns_count][square] = idx
+                    idx += self.binomial[lead_pawns_count - 1][self.map_pawns[square]]
+
+                self.lead_pawns_size[lead_pawns_count][file] = idx
+
+
+ENCODING = Encoding()
+
+
+#Material of the table, white is the stronger side
+class Table:
+    def __init__(self, name, piece):
+        self.name = name
+        self.piece = piece
+        self.has_pawns = piece == PAWN
+        self.files = 4 if self.has_pawns else 1
+        white_piece = {PAWN: W_PAWN, ROOK: W_ROOK, QUEEN: W_QUEEN}[piece]
+
+        #Order of the pieces stored in the file, leading pawn goes first
+        self.pieces = [white_piece, W_KING, B_KING] if self.has_pawns else [W_KING, white_piece, B_KING]
+        self.group_len = [1, 1, 1, 0] if self.has_pawns else [3, 0]
+        self.group_idx = [self.groups(file) for file in range(self.files)]
+
+    #Same as 'set_groups' with the leading group encoded first
+    def groups(self, file):
+        if not self.has_pawns:
+            return [1, 31332]
+
+        idx = [1]
+        size = ENCODING.lead_pawns_size[1][file]
+        idx.append(size)
+        idx.append(size * 63)
+        idx.append(size * 63 * 62)
+        return idx
+
+    def size(self, file):
+        return self.group_idx[file][-1]
+
+    #Index of the position, same as 'probe_table'. Returns file of the subtable and the index
+    def index(self, white_king, piece_square, black_king):
+        pieces = sorted([(self.pieces[0] if self.has_pawns else self.pieces[1], piece_square),
+                         (W_KING, white_king), (B_KING, black_king)], key=lambda entry: entry[1])
+
+        squares = []
+        codes = []
+        file = 0
+        lead_pawns_count = 0
+        if self.has_pawns:
+            squares.append(piece_square)
+            codes.append(W_PAWN)
+            lead_pawns_count = 1
+            file = min(file_of(squares[0]), 7 - file_of(squares[0]))
+
+        for code, square in pieces:
+            if self.has_pawns and code == W_PAWN:
+                continue
+
+            squares.append(square)
+            codes.append(code)
+
+        for i in range(lead_pawns_count, len(squares) - 1):
+            for j in range(i + 1, len(squares)):
+                if self.pieces[i] == codes[j]:
+                    codes[i], codes[j] = codes[j], codes[i]
+                    squares[i], squares[j] = squares[j], squares[i]
+                    break
+
+        if file_of(squares[0]) > 3:
+            squares = [flip_file(square) for square in squares]
+
+        group_idx = self.group_idx[file]
+        if self.has_pawns:
+            idx = ENCODING.lead_pawn_idx[lead_pawns_count][squares[0]]
+        else:
+            if rank_of(squares[0]) > 3:
+                squares = [flip_rank(square) for square in squares]
+
+            for i in range(self.group_len[0]):
+                if off_a1h8(squares[i]) == 0:
+                    continue
+
+                if off_a1h8(squares[i]) > 0:
+                    squares = squares[:i] + [((square >> 3) | (square << 3)) & 63 for square in squares[i:]]
+
+                break
+
+            idx = encode_unique_pieces(squares)
+
+        idx *= group_idx[0]
+        group_start = self.group_len[0]
+        next_group = 1
+        while self.group_len[next_group] != 0:
+            group_end = group_start + self.group_len[next_group]
+            squares[group_start:group_end] = sorted(squares[group_start:group_end])
+
+            n = 0
+            for i in range(self.group_len[next_group]):
+                square = squares[group_start + i]
+                adjust = sum(1 for previous in squares[:group_start] if square > previous)
+                n += ENCODING.binomial[i + 1][square - adjust]
+
+            idx += n * group_idx[next_group]
+            group_start = group_end
+            next_group += 1
+
+        return file, idx
+
+
+def encode_unique_pieces(squares):
+    adjust1 = int(squares[1] > squares[0])
+    adjust2 = int(squares[2] > squares[0]) + int(squares[2] > squares[1])
+    first, second, third = squares[0], squares[1], squares[2]
+
+    if off_a1h8(first) != 0:
+        return (ENCODING.map_a1d1d4[first] * 63 + (second - adjust1)) * 62 + third - adjust2
+    if off_a1h8(second) != 0:
+        return (6 * 63 + (first >> 3) * 28 + ENCODING.map_b1h1h7[second]) * 62 + third - adjust2
+    if off_a1h8(third) != 0:
+        return (6 * 63 * 62 + 4 * 28 * 62 + (first >> 3) * 7 * 28 + ((second >> 3) - adjust1) * 28
+                + ENCODING.map_b1h1h7[third])
+    return (6 * 63 * 62 + 4 * 28 * 62 + 4 * 7 * 28 + (first >> 3) * 7 * 6 + ((second >> 3) - adjust1) * 6
+            + ((third >> 3) - adjust2))
+
+
+KING_STEPS = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)]
+ROOK_DIRECTIONS = [(-1, 0), (1, 0), (0, -1), (0, 1)]
+QUEEN_DIRECTIONS = ROOK_DIRECTIONS + [(-1, -1), (-1, 1), (1, -1), (1, 1)]
+
+
+def king_targets(square):
+    targets = []
+    for file_step, rank_step in KING_STEPS:
+        file, rank = file_of(square) + file_step, rank_of(square) + rank_step
+        if 0 <= file < 8 and 0 <= rank < 8:
+            targets.append(rank * 8 + file)
+    return targets
+
+
+KING_TARGETS = [king_targets(square) for square in range(64)]
+
+
+def slider_targets(square, directions, blockers):
+    targets = []
+    for file_step, rank_step in directions:
+        file, rank = file_of(square) + file_step, rank_of(square) + rank_step
+        while 0 <= file < 8 and 0 <= rank < 8:
+            target = rank * 8 + file
+            targets.append(target)
+            if target in blockers:
+                break
+            file += file_step
+            rank += rank_step
+    return targets
+
+
+#Squares attacked by the white piece, kings block the sliders
+def piece_attacks(piece, square, blockers):
+    if piece == PAWN:
+        targets = []
+        if rank_of(square) < 7:
+            if file_of(square) > 0:
+                targets.append(square + 7)
+            if file_of(square) < 7:
+                targets.append(square + 9)
+        return targets
+
+    return slider_targets(square, QUEEN_DIRECTIONS if piece == QUEEN else ROOK_DIRECTIONS, blockers)
+
+
+def black_in_check(piece, white_king, piece_square, black_king):
+    if distance(white_king, black_king) <= 1:
+        return True
+    if piece_square is None:
+        return False
+    return black_king in piece_attacks(piece, piece_square, {white_king, black_king})
+
+
+def is_legal(piece, white_king, piece_square, black_king, black_to_move):
+    if len({white_king, piece_square, black_king}) != 3 or distance(white_king, black_king) <= 1:
+        return False
+    if piece == PAWN and rank_of(piece_square) in (0, 7):
+        return False
+    return black_to_move or not black_in_check(piece, white_king, piece_square, black_king)
+
+
+def position_index(white_king, piece_square, black_king, black_to_move):
+    return ((white_king * 64 + piece_square) * 64 + black_king) * 2 + int(black_to_move)
+
+
+#Moves of the position. Non-zeroing moves stay in the table, zeroing moves either stay in it
+#(pawn pushes) or lead to another material, which is given by its result for the side to move
+def generate_moves(piece, white_king, piece_square, black_king, black_to_move, solved):
+    quiet = []
+    zeroing = []
+    external = []
+
+    if black_to_move:
+        for target in KING_TARGETS[black_king]:
+            if target == white_king:
+                continue
+
+            captured = target == piece_square
+            if black_in_check(piece, white_king, None if captured else piece_square, target):
+                continue
+
+            if captured:
+                external.append(0)
+            else:
+                quiet.append(position_index(white_king, piece_square, target, False))
+        return quiet, zeroing, external
+
+    for target in KING_TARGETS[white_king]:
+        if target != piece_square and distance(target, black_king) > 1:
+            quiet.append(position_index(target, piece_square, black_king, True))
+
+    if piece != PAWN:
+        for target in slider_targets(piece_square, QUEEN_DIRECTIONS if piece == QUEEN else ROOK_DIRECTIONS,
+                                     {white_king, black_king}):
+            if target not in (white_king, black_king):
+                quiet.append(position_index(white_king, target, black_king, True))
+        return quiet, zeroing, external
+
+    pushes = []
+    if piece_square + 8 not in (white_king, black_king):
+        pushes.append(piece_square + 8)
+        if rank_of(piece_square) == 1 and piece_square + 16 not in (white_king, black_king):
+            pushes.append(piece_square + 16)
+
+    for target in pushes:
+        if rank_of(target) < 7:
+            zeroing.append(position_index(white_king, target, black_king, True))
+            continue
+
+        for promotion in (QUEEN, ROOK):
+            external.append(solved[promotion].wdl[position_index(white_king, target, black_king, True)])
+
+        #Bishop and knight can't win
+        external.extend([0, 0])
+
+    return quiet, zeroing, external
+
+
+#Result and distance to zeroing of every legal position, from the perspective of the side to move
+class Solution:
+    def __init__(self, piece, solved):
+        self.piece = piece
+        size = 64 * 64 * 64 * 2
+        self.wdl = [None] * size
+        self.dtz = [None] * size
+
+        positions = []
+        moves = {}
+        mated = set()
+        for white_king in range(64):
+            for piece_square in range(64):
+                for black_king in range(64):
+                    for black_to_move in (False, True):
+                        if not is_legal(piece, white_king, piece_square, black_king, black_to_move):
+                            continue
+
+                        index = position_index(white_king, piece_square, black_king, black_to_move)
+                        position_moves = generate_moves(piece, white_king, piece_square, black_king,
+                                                        black_to_move, solved)
+                        positions.append(index)
+                        moves[index] = position_moves
+
+                        if not any(position_moves):
+                            in_check = black_to_move and black_in_check(piece, white_king, piece_square, black_king)
+                            self.wdl[index] = -1 if in_check else 0
+                            if in_check:
+                                mated.add(index)
+
+        self.solve_wdl(positions, moves)
+        self.solve_dtz(positions, moves, mated)
+
+    def solve_wdl(self, positions, moves):
+        unresolved = [index for index in positions if self.wdl[index] is None]
+        while True:
+            remaining = []
+            for index in unresolved:
+                quiet, zeroing, external = moves[index]
+                children = [self.wdl[child] for child in quiet + zeroing] + external
+                if -1 in children:
+                    self.wdl[index] = 1
+                elif all(child == 1 for child in children):
+                    self.wdl[index] = -1
+                else:
+                    remaining.append(index)
+
+            if len(remaining) == len(unresolved):
+                break
+            unresolved = remaining
+
+        for index in unresolved:
+            self.wdl[index] = 0
+
+    #Winning side picks the fastest zeroing move, mates count as zeroing. Losing side delays it
+    def solve_dtz(self, positions, moves, mated):
+        wins = []
+        losses = []
+        for index in positions:
+            quiet, zeroing, external = moves[index]
+            if self.wdl[index] == 1:
+                children = [self.wdl[child] for child in zeroing] + external
+                if -1 in children or any(child in mated for child in quiet):
+                    self.dtz[index] = 1
+                else:
+                    wins.append(index)
+            elif self.wdl[index] == -1:
+                losses.append(index)
+
+        #Losses resolved after level d are at most d + 1 plies from zeroing, so wins of level d + 1
+        #only need the losses known before them
+        level = 1
+        while True:
+            remaining_losses = []
+            for index in losses:
+                quiet = moves[index][0]
+                if not quiet:
+                    self.dtz[index] = -1
+                elif all(self.dtz[child] is not None for child in quiet):
+                    self.dtz[index] = -(max(self.dtz[child] for child in quiet) + 1)
+                else:
+                    remaining_losses.append(index)
+
+            if not wins and not remaining_losses:
+                break
+
+            level += 1
+            remaining_wins = []
+            for index in wins:
+                if any(self.dtz[child] == -(level - 1) for child in moves[index][0]):
+                    self.dtz[index] = level
+                else:
+                    remaining_wins.append(index)
+
+            if len(remaining_wins) == len(wins) and len(remaining_losses) == len(losses):
+                raise RuntimeError("Unresolved positions")
+            wins = remaining_wins
+            losses = remaining_losses
+
+        assert all(abs(self.dtz[index]) <= 100 for index in positions if self.dtz[index] is not None), \
+            "Cursed results are not supported"
+
+
+#Huffman code lengths of the values, lengths are limited by the small alphabet
+def code_lengths(frequencies):
+    nodes = [(count, [value]) for value, count in frequencies.items()]
+    lengths = {value: 0 for value in frequencies}
+    while len(nodes) > 1:
+        nodes.sort(key=lambda node: (node[0], min(node[1])))
+        (first_count, first_values), (second_count, second_values) = nodes[0], nodes[1]
+        for value in first_values + second_values:
+            lengths[value] += 1
+        nodes = nodes[2:] + [(first_count + second_count, first_values + second_values)]
+    return lengths
+
+
+#Compressed subtable, as parsed by 'set_sizes'
+def compress(values, flags):
+    frequencies = {}
+    for value in values:
+        frequencies[value] = frequencies.get(value, 0) + 1
+
+    if len(frequencies) == 1:
+        return {"sizes": bytes([flags | FLAG_SINGLE_VALUE, values[0]]), "sparse": b"", "lengths": b"", "data": b""}
+
+    lengths = code_lengths(frequencies)
+    max_len = max(lengths.values())
+    min_len = min(lengths.values())
+    assert max_len <= 32
+
+    #Symbols with longer codes get lower numbers, every symbol expands to a single value
+    symbols = sorted(frequencies, key=lambda value: (-lengths[value], value))
+    symbol_of = {value: symbol for symbol, value in enumerate(symbols)}
+    count = {length: 0 for length in range(min_len, max_len + 2)}
+    for value in symbols:
+        count[lengths[value]] += 1
+
+    lowest = {max_len: 0}
+    base = {max_len: 0}
+    for length in range(max_len - 1, min_len - 1, -1):
+        lowest[length] = lowest[length + 1] + count[length + 1]
+        assert (base[length + 1] + count[length + 1]) % 2 == 0
+        base[length] = (base[length + 1] + count[length + 1]) // 2
+    assert base[min_len] + count[min_len] == 1 << min_len
+
+    codes = {}
+    for value in symbols:
+        length = lengths[value]
+        codes[value] = (base[length] + symbol_of[value] - lowest[length], length)
+
+    #Blocks hold as many values as fit, codes don't cross the blocks
+    block_bits = 8 << BLOCK_SIZE_LOG
+    blocks = []
+    block_starts = []
+    bits = []
+    start = 0
+    for position, value in enumerate(values):
+        code, length = codes[value]
+        if len(bits) + length > block_bits or position - start == MAX_BLOCK_VALUES:
+            blocks.append(bits)
+            block_starts.append(start)
+            bits = []
+            start = position
+        bits.extend((code >> (length - 1 - bit)) & 1 for bit in range(length))
+    blocks.append(bits)
+    block_starts.append(start)
+    block_ends = block_starts[1:] + [len(values)]
+
+    data = bytearray()
+    for bits in blocks:
+        block = bytearray(1 << BLOCK_SIZE_LOG)
+        for position, bit in enumerate(bits):
+            if bit:
+                block[position >> 3] |= 0x80 >> (position & 7)
+        data += block
+
+    block_lengths = bytearray()
+    for block_start, block_end in zip(block_starts, block_ends):
+        block_lengths += struct.pack("<H", block_end - block_start - 1)
+
+    #Sparse index points to the middle of every span, spans past the end point into the last block
+    span = 1 << SPAN_LOG
+    sparse = bytearray()
+    block = 0
+    for k in range((len(values) + span - 1) // span):
+        middle = k * span + span // 2
+        while block + 1 < len(blocks) and block_ends[block] <= middle:
+            block += 1
+        offset = middle - block_starts[block]
+        assert offset < 1 << 16
+        sparse += struct.pack("<IH", block, offset)
+
+    sizes = bytearray([flags, BLOCK_SIZE_LOG, SPAN_LOG, 0])
+    sizes += struct.pack("<I", len(blocks))
+    sizes += bytes([max_len, min_len])
+    for length in range(min_len, max_len + 1):
+        sizes += struct.pack("<H", lowest[length])
+    sizes += struct.pack("<H", len(symbols))
+    for value in symbols:
+        sizes += bytes([value & 0xFF, ((value >> 8) & 0xF) | 0xF0, 0xFF])
+    if len(symbols) & 1:
+        sizes.append(0)
+
+    return {"sizes": bytes(sizes), "sparse": bytes(sparse), "lengths": bytes(block_lengths), "data": bytes(data)}
+
+
+#File layout parsed by 'TableData::parse', subtables are ordered by file and then by side
+def write_file(path, table, dtz, subtables):
+    sides = len(subtables[0])
+    out = bytearray(DTZ_MAGIC if dtz else WDL_MAGIC)
+    out.append(SPLIT | (HAS_PAWNS if table.has_pawns else 0))
+
+    for _ in range(table.files):
+        out.append(0x00)
+        for piece in table.pieces:
+            out.append((piece << 4) | piece)
+
+    if len(out) & 1:
+        out.append(0)
+
+    for file in range(table.files):
+        for side in range(sides):
+            out += subtables[file][side]["sizes"]
+
+    if dtz and len(out) & 1:
+        out.append(0)
+
+    for part in ("sparse", "lengths"):
+        for file in range(table.files):
+            for side in range(sides):
+                out += subtables[file][side][part]
+
+    for file in range(table.files):
+        for side in range(sides):
+            while len(out) & 0x3F:
+                out.append(0)
+            out += subtables[file][side]["data"]
+
+    with open(path, "wb") as output:
+        output.write(out)
+
+
+#Values of every subtable, indices without a legal position get the most common value
+def table_values(table, solution, value_of, sides):
+    values = [[[None] * table.size(file) for _ in sides] for file in range(table.files)]
+    for white_king in range(64):
+        for piece_square in range(64):
+            for black_king in range(64):
+                for side, black_to_move in enumerate(sides):
+                    index = position_index(white_king, piece_square, black_king, black_to_move)
+                    if solution.wdl[index] is None:
+                        continue
+
+                    value = value_of(index)
+                    if value is None:
+                        continue
+
+                    file, idx = table.index(white_king, piece_square, black_king)
+                    stored = values[file][side][idx]
+                    assert stored is None or stored == value, f"Conflicting values at {file} {side} {idx}"
+                    values[file][side][idx] = value
+
+    for file_values in values:
+        for side_values in file_values:
+            known = [value for value in side_values if value is not None]
+            common = max(set(known), key=known.count) if known else 0
+            for idx, value in enumerate(side_values):
+                if value is None:
+                    side_values[idx] = common
+    return values
+
+
+def dtz_value(solution, index):
+    wdl = solution.wdl[index]
+    if wdl == 0:
+        return None
+    return abs(solution.dtz[index]) - 1
+
+
+def generate(directory):
+    solved = {}
+    for name, piece in (("KQvK", QUEEN), ("KRvK", ROOK), ("KPvK", PAWN)):
+        solution = Solution(piece, solved)
+        solved[piece] = solution
+        table = Table(name, piece)
+
+        #Stored WDL values go from 0 for a loss to 4 for a win, without cursed results
+        wdl_values = table_values(table, solution, lambda index: 2 * solution.wdl[index] + 2, (False, True))
+        wdl_subtables = [[compress(side_values, 0) for side_values in file_values] for file_values in wdl_values]
+        write_file(os.path.join(directory, name + ".rtbw"), table, False, wdl_subtables)
+
+        dtz_values = table_values(table, solution, lambda index: dtz_value(solution, index), (False,))
+        dtz_flags = FLAG_WIN_PLIES | FLAG_LOSS_PLIES
+        dtz_subtables = [[compress(side_values, dtz_flags) for side_values in file_values] for file_values in dtz_values]
+        write_file(os.path.join(directory, name + ".rtbz"), table, True, dtz_subtables)
+
+        print(f"{name}: {sum(1 for value in solution.wdl if value is not None)} positions")
+
+    return solved
+
+
+if __name__ == "__main__":
+    generate(sys.argv[1] if len(sys.argv) > 1 else os.path.dirname(os.path.abspath(__file__)))
@@ -15,6 +15,7 @@ fn main() {
             "value-conv" => value_convert(&args),
             "policy-conv" => policy_convert(&args),
             "value" => ValueTrainer::execute(),
+            "value-ml" => ValueTrainer::execute_moves_left(),
             "policy" => PolicyTrainer::execute(),
             _ => continue,
         }
@@ -25,12 +26,14 @@ fn value_convert(args: &Vec<String>) {
     let mut input_path = "./value_data.bin";
     let mut output_path = "./conv_value_data.bin";
     let mut fine_tune = false;
+    let mut moves_left = false;
 
     let mut cmd = String::new();
     for arg in args {
         match arg.as_str() {
             "-i" | "-o" => cmd = arg.clone(),
             "finetune" => fine_tune = true,
+            "movesleft" => moves_left = true,
             _ => {
                 match cmd.as_str() {
                     "-i" => input_path = arg.as_str(),
@@ -41,7 +44,7 @@ fn value_convert(args: &Vec<String>) {
         }
     }
 
-    ValueConverter::convert(input_path, output_path, fine_tune);
+    ValueConverter::convert(input_path, output_path, fine_tune, moves_left);
 }
 
 fn policy_convert(args: &Vec<String>) {
@@ -5,3 +5,9 @@ mod value_trainer;
 pub use value_convert::ValueConverter;
 pub(super) use value_convert_display::ValueConvertDisplay;
 pub use value_trainer::ValueTrainer;
+
+//Unused 'extra' byte of bullet ChessBoard, converter stores the 50 move counter there
+pub(super) const HALFMOVE_BYTE: usize = 29;
+
+//Header of value data written by datagen, it changes together with the record format
+pub(super) const VALUE_DATA_HEADER: &[u8; 8] = b"JVALUE02";
@@ -5,26 +5,45 @@ use std::{
 };
 
 use bullet::format::ChessBoard;
+use jackal::ValueNetwork;
 use spear::{ChessBoardPacked, Move, Piece, Side};
 
-use crate::value::ValueConvertDisplay;
+use crate::value::{ValueConvertDisplay, HALFMOVE_BYTE, VALUE_DATA_HEADER};
 
 pub struct ValueConverter;
 impl ValueConverter {
-    pub fn convert(input_path: &str, output_path: &str, fine_tune: bool) {
+    //Input starts with the header, then records are packed boards followed by u16 number of plies left.
+    //In moves left mode the score is replaced with encoded plies left, so bullet trains sigmoid(score / 400)
+    //towards them
+    pub fn convert(input_path: &str, output_path: &str, fine_tune: bool, moves_left: bool) {
         let input_file = File::open(input_path).expect("Cannot open input file");
         let input_meta = input_file.metadata().expect("Cannot obtain file metadata");
         let mut reader = BufReader::new(input_file);
 
+        let board_size = std::mem::size_of::<ChessBoardPacked>();
+        let entry_size = board_size + std::mem::size_of::<u16>();
+
+        //Files without the header were written before plies left were stored with the positions
+        let mut header = *VALUE_DATA_HEADER;
+        if reader.read_exact(&mut header).is_err() || header != *VALUE_DATA_HEADER {
+            println!("{} has an unsupported record format, generate it again with current datagen", input_path);
+            return;
+        }
+
+        let data_size = input_meta.len() - header.len() as u64;
+        if data_size % entry_size as u64 != 0 {
+            println!("{} doesn't consist of whole records of {} bytes", input_path, entry_size);
+            return;
+        }
+
         let mut output_file = OpenOptions::new()
             .append(true)
             .create(true)
             .open(output_path)
             .expect("Cannot open output file");
 
-        let entry_size = std::mem::size_of::<ChessBoardPacked>();
         let bullet_board_size = std::mem::size_of::<ChessBoard>();
-        let entry_count = input_meta.len() / entry_size as u64;
+        let entry_count = data_size / entry_size as u64;
         let mut buffer = vec![0u8; entry_size];
 
         let mut timer = Instant::now();
@@ -55,10 +74,12 @@ impl ValueConverter {
             }
 
             let position: ChessBoardPacked = unsafe { std::ptr::read(buffer.as_ptr() as *const _) };
+            let plies_left = u16::from_le_bytes([buffer[board_size], buffer[board_size + 1]]);
             entries_processed += 1;
 
+            //Positions with mate scores are the closest to the end, so they are kept for moves left
             let score = position.get_white_perspective_score();
-            if score <= 0.0 || score >= 1.0 {
+            if !moves_left && (score <= 0.0 || score >= 1.0) {
                 mate_scores += 1;
                 continue;
             }
@@ -85,7 +106,12 @@ impl ValueConverter {
 
             //rest of the filters
 
-            let score = -(400.0 * (1.0 / score - 1.0).ln()) as i16;
+            let score = if moves_left {
+                let target = (f32::from(plies_left) / ValueNetwork::MAX_MOVES_LEFT).clamp(0.001, 0.999);
+                (400.0 * (target / (1.0 - target)).ln()) as i16
+            } else {
+                -(400.0 * (1.0 / score - 1.0).ln()) as i16
+            };
             let result = (result + 1) as f32 / 2.0;
 
             let bbs = [
@@ -107,12 +133,16 @@ impl ValueConverter {
             )
             .expect("Couldnt create a bullet board");
 
-            let chess_board_bytes = unsafe {
+            let mut chess_board_bytes = unsafe {
                 std::slice::from_raw_parts(
                     (&bullet_board as *const ChessBoard) as *const u8,
                     bullet_board_size,
                 )
-            };
+            }
+            .to_vec();
+
+            //Halfmove input buckets of the value network need the 50 move counter
+            chess_board_bytes[HALFMOVE_BYTE] = board.half_move_counter().min(255) as u8;
 
             match position.get_result() {
                 -1 => white_loses += 1,
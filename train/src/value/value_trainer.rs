@@ -1,9 +1,13 @@
 use bullet::{
     format::{chess::BoardIter, ChessBoard}, inputs::{self, InputType}, loader, lr, operations, optimiser::{self, AdamWOptimiser, AdamWParams}, outputs, wdl, Activation, ExecutionContext, Graph, GraphBuilder, LocalSettings, Node, QuantTarget, Shape, Trainer, TrainingSchedule, TrainingSteps
 };
+use jackal::ValueNetwork;
 use spear::{Bitboard, Piece, Square};
 
+use super::HALFMOVE_BYTE;
+
 const HIDDEN_SIZE: usize = 1024;
+const MOVES_LEFT_HIDDEN_SIZE: usize = 64;
 
 pub struct ValueTrainer;
 impl ValueTrainer {
@@ -71,6 +75,61 @@ impl ValueTrainer {
             println!("EVAL: [{},{},{}]", w/sum, d/sum, l/sum);
         }
     }
+
+    //Trains moves left head on data converted with 'movesleft' flag. Result is ignored, the head
+    //learns sigmoid(score / 400), which is plies left scaled by ValueNetwork::MAX_MOVES_LEFT.
+    //Quantised weights are appended to the value network file
+    pub fn execute_moves_left() {
+        let mut trainer = make_moves_left_trainer(MOVES_LEFT_HIDDEN_SIZE);
+
+        let schedule: TrainingSchedule<lr::CosineDecayLR, wdl::ConstantWDL> = TrainingSchedule {
+            net_id: "moves_left_001_64".to_string(),
+            eval_scale: 400.0,
+            steps: TrainingSteps {
+                batch_size: 16_384,
+                batches_per_superbatch: 6104,
+                start_superbatch: 1,
+                end_superbatch: 100,
+            },
+            wdl_scheduler: wdl::ConstantWDL { value: 0.0 },
+            lr_scheduler: lr::CosineDecayLR {
+                initial_lr: 0.001,
+                final_lr: 0.00001,
+                final_superbatch: 100,
+            },
+            save_rate: 10,
+        };
+
+        trainer.set_optimiser_params(optimiser::AdamWParams {
+            decay: 0.01,
+            beta1: 0.9,
+            beta2: 0.999,
+            min_weight: -0.99,
+            max_weight: 0.99,
+        });
+
+        let settings = LocalSettings {
+            threads: 8,
+            test_set: None,
+            output_directory: "checkpoints",
+            batch_queue_size: 512,
+        };
+
+        let data_loader = loader::DirectSequentialDataLoader::new(&["./shuffled_moves_left_data.bin"]);
+        trainer.run(&schedule, &settings, &data_loader);
+
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "8/8/8/8/8/5k2/8/4K2Q w - - 0 1",
+        ] {
+            let raw = trainer.eval_raw_output(fen);
+            let moves_left = ValueNetwork::MAX_MOVES_LEFT / (1.0 + (-raw[0]).exp());
+
+            println!("FEN: {fen}");
+            println!("MOVES LEFT: {moves_left:.1}");
+        }
+    }
 }
 
 #[derive(Clone, Copy, Default)]
@@ -81,6 +140,7 @@ pub struct ThreatsDefencesMirroredInputsIter {
     threats: Bitboard,
     defences: Bitboard,
     flip: usize,
+    bucket_offset: usize,
 }
 
 impl inputs::InputType for ThreatsDefencesMirroredInputs {
@@ -88,7 +148,7 @@ impl inputs::InputType for ThreatsDefencesMirroredInputs {
     type FeatureIter = ThreatsDefencesMirroredInputsIter;
 
     fn buckets(&self) -> usize {
-        1
+        ValueNetwork::HALFMOVE_BUCKETS
     }
 
     fn max_active_inputs(&self) -> usize {
@@ -125,11 +185,14 @@ impl inputs::InputType for ThreatsDefencesMirroredInputs {
         let threats = board.generate_attack_map::<true, false>();
         let defences = board.generate_attack_map::<false, true>();
 
+        let half_moves = unsafe { *(position as *const ChessBoard as *const u8).add(HALFMOVE_BYTE) };
+
         ThreatsDefencesMirroredInputsIter {
             board_iter: position.into_iter(),
             threats,
             defences,
             flip: if position.our_ksq() % 8 > 3 { 7 } else { 0 },
+            bucket_offset: 768 * 4 * ValueNetwork::halfmove_bucket(usize::from(half_moves)),
         }
     }
 }
@@ -142,7 +205,7 @@ impl Iterator for ThreatsDefencesMirroredInputsIter {
             let piece_index = usize::from(piece & 7);
             let square = usize::from(square);
             let side = usize::from(piece & 8 > 0);
-            let mut input = (side * 384) + (64 * piece_index) + (square ^ self.flip);
+            let mut input = self.bucket_offset + (side * 384) + (64 * piece_index) + (square ^ self.flip);
 
             if self.threats.get_bit(Square::from_raw(square as u8)) {
                 input += 768;
@@ -208,3 +271,56 @@ fn build_network(inputs: usize, l1: usize) -> (Graph, Node) {
     operations::softmax_crossentropy_loss(&mut builder, l2, targets);
     (builder.build(ExecutionContext::default()), l2)
 }
+
+fn make_moves_left_trainer(l1: usize) -> Trainer<AdamWOptimiser, ThreatsDefencesMirroredInputs, outputs::Single> {
+    let num_inputs = ThreatsDefencesMirroredInputs.size();
+
+    let (mut graph, output_node) = build_moves_left_network(num_inputs, l1);
+
+    let sizes = [num_inputs, l1];
+
+    for (i, &size) in sizes.iter().enumerate() {
+        graph
+            .get_weights_mut(&format!("l{i}w"))
+            .seed_random(0.0, 1.0 / (size as f32).sqrt(), true);
+
+        graph
+            .get_weights_mut(&format!("l{i}b"))
+            .seed_random(0.0, 1.0 / (size as f32).sqrt(), true);
+    }
+
+    Trainer::new(
+        graph,
+        output_node,
+        AdamWParams::default(),
+        ThreatsDefencesMirroredInputs,
+        outputs::Single,
+        vec![
+            ("l0w".to_string(), QuantTarget::Float),
+            ("l0b".to_string(), QuantTarget::Float),
+            ("l1w".to_string(), QuantTarget::Float),
+            ("l1b".to_string(), QuantTarget::Float),
+        ],
+        false
+    )
+}
+
+fn build_moves_left_network(inputs: usize, l1: usize) -> (Graph, Node) {
+    let mut builder = GraphBuilder::default();
+
+    let stm = builder.create_input("stm", Shape::new(inputs, 1));
+    let targets = builder.create_input("targets", Shape::new(1, 1));
+
+    let l0w = builder.create_weights("l0w", Shape::new(l1, inputs));
+    let l0b = builder.create_weights("l0b", Shape::new(l1, 1));
+    let l1w = builder.create_weights("l1w", Shape::new(1, l1));
+    let l1b = builder.create_weights("l1b", Shape::new(1, 1));
+
+    let l1 = operations::affine(&mut builder, l0w, stm, l0b);
+    let l1 = operations::activate(&mut builder, l1, Activation::SCReLU);
+    let l2 = operations::affine(&mut builder, l1w, l1, l1b);
+
+    let predicted = operations::activate(&mut builder, l2, Activation::Sigmoid);
+    operations::mse(&mut builder, predicted, targets);
+    (builder.build(ExecutionContext::default()), l2)
+}